@@ -0,0 +1,47 @@
+// Injected by `docpilot serve`: reloads the page after each rebuild, keeps
+// the scroll position and shows build errors on top of the last good output.
+(function () {
+  var scrollKey = "docpilot-scroll:" + location.pathname;
+
+  var saved = sessionStorage.getItem(scrollKey);
+  if (saved !== null) {
+    sessionStorage.removeItem(scrollKey);
+    window.addEventListener("load", function () {
+      window.scrollTo(0, parseInt(saved, 10) || 0);
+    });
+  }
+
+  function overlay() {
+    var box = document.getElementById("docpilot-overlay");
+    if (!box) {
+      box = document.createElement("pre");
+      box.id = "docpilot-overlay";
+      box.style.cssText =
+        "position:fixed;inset:0;margin:0;padding:2em;z-index:2147483647;" +
+        "overflow:auto;white-space:pre-wrap;font:14px/1.5 monospace;" +
+        "background:rgba(30,0,0,0.92);color:#ffdada;cursor:pointer;";
+      box.title = "Click to dismiss";
+      box.addEventListener("click", function () {
+        box.remove();
+      });
+      document.body.appendChild(box);
+    }
+    return box;
+  }
+
+  var events = new EventSource("/__docpilot/events");
+
+  events.addEventListener("reload", function () {
+    sessionStorage.setItem(scrollKey, String(window.scrollY));
+    location.reload();
+  });
+
+  events.addEventListener("failed", function (event) {
+    document.title = document.title.replace(/^⏳ /, "");
+    overlay().textContent = "docpilot: build failed\n\n" + event.data;
+  });
+
+  events.addEventListener("building", function () {
+    document.title = "⏳ " + document.title.replace(/^⏳ /, "");
+  });
+})();
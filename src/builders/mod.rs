@@ -1,10 +1,18 @@
-use crate::config::{DocumentMetadata, MarkdownFile, ProjectConfig};
+use crate::config::{DocumentMetadata, MarkdownFile, MathMethod, ProjectConfig};
+use crate::dependencies::LatexPreflight;
+use crate::diagrams::DiagramFormat;
 use crate::discovery::MetadataParser;
 use crate::errors::{DocPilotError, Result};
+use crate::hooks;
+use crate::preprocess::Preprocessor;
+use crate::templates::TemplateManager;
+use clap::ValueEnum;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use tracing::{debug, info};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::task::JoinSet;
+use tracing::{debug, error, info, warn};
 
 pub struct PdfBuilder {
     config: ProjectConfig,
@@ -18,6 +26,210 @@ pub struct HtmlBuilder {
     config: ProjectConfig,
 }
 
+pub struct EpubBuilder {
+    config: ProjectConfig,
+}
+
+/// Output formats that can be built
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Pdf,
+    Docx,
+    Html,
+    Epub,
+}
+
+impl OutputFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Pdf => "pdf",
+            OutputFormat::Docx => "docx",
+            OutputFormat::Html => "html",
+            OutputFormat::Epub => "epub",
+        }
+    }
+}
+
+/// Where `format` is written: `<output_dir>/<name>.<format>`
+pub fn output_path(config: &ProjectConfig, format: &str) -> PathBuf {
+    config
+        .output_dir
+        .join(format!("{}.{}", config.name, format))
+}
+
+/// Result of one format in [`build_concurrently`]
+#[derive(Debug)]
+pub struct FormatBuild {
+    pub format: String,
+    pub output_path: PathBuf,
+    pub elapsed: Duration,
+    pub result: Result<()>,
+}
+
+impl FormatBuild {
+    /// One status line, e.g. `✅ pdf   output/document.pdf (2.1s)`
+    pub fn status_line(&self) -> String {
+        match &self.result {
+            Ok(()) => format!(
+                "✅ {:<5} {} ({:.1}s)",
+                self.format,
+                self.output_path.display(),
+                self.elapsed.as_secs_f64()
+            ),
+            Err(e) => format!(
+                "❌ {:<5} {}",
+                self.format,
+                e.to_string().lines().next().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Build several formats at the same time, printing a status line as each
+/// one finishes. Dropping the future aborts the builds that are still
+/// running, which kills their pandoc processes.
+pub async fn build_concurrently(
+    config: &ProjectConfig,
+    formats: &[String],
+    files: &[MarkdownFile],
+) -> Vec<FormatBuild> {
+    fs::create_dir_all(&config.output_dir).ok();
+
+    let mut diagram_formats: Vec<DiagramFormat> = Vec::new();
+    for format in formats {
+        let diagram_format = DiagramFormat::for_output(format);
+        if !diagram_formats.contains(&diagram_format) {
+            diagram_formats.push(diagram_format);
+        }
+    }
+    // A diagram failure fails every format, so report it once up front
+    let preprocessor = Preprocessor::new(config.clone());
+    for diagram_format in diagram_formats {
+        if let Err(e) = preprocessor.render_diagrams(files, diagram_format).await {
+            let message = e.to_string();
+            return formats
+                .iter()
+                .map(|format| FormatBuild {
+                    format: format.clone(),
+                    output_path: output_path(config, format),
+                    elapsed: Duration::ZERO,
+                    result: Err(DocPilotError::Build {
+                        message: message.clone(),
+                    }),
+                })
+                .collect();
+        }
+    }
+
+    let mut builds = JoinSet::new();
+    for format in formats {
+        let config = config.clone();
+        let format = format.clone();
+        let files = files.to_vec();
+        builds.spawn(async move {
+            let started = Instant::now();
+            let output_path = output_path(&config, &format);
+            let result = hooks::build_with_hooks(
+                &config,
+                &format,
+                &output_path,
+                build_format(&config, &format, &files, &output_path),
+            )
+            .await;
+            FormatBuild {
+                format,
+                output_path,
+                elapsed: started.elapsed(),
+                result,
+            }
+        });
+    }
+
+    let mut finished = Vec::with_capacity(formats.len());
+    while let Some(joined) = builds.join_next().await {
+        match joined {
+            Ok(build) => {
+                println!("   {}", build.status_line());
+                finished.push(build);
+            }
+            Err(e) => error!("Build task failed: {}", e),
+        }
+    }
+
+    finished
+}
+
+/// Build `files` into `output_path` with the builder for `format`
+pub async fn build_format(
+    config: &ProjectConfig,
+    format: &str,
+    files: &[MarkdownFile],
+    output_path: &Path,
+) -> Result<()> {
+    match format {
+        "pdf" => {
+            PdfBuilder::new(config.clone())
+                .build(files, output_path)
+                .await
+        }
+        "docx" => {
+            DocxBuilder::new(config.clone())
+                .build(files, output_path)
+                .await
+        }
+        "html" => {
+            HtmlBuilder::new(config.clone())
+                .build(files, output_path)
+                .await
+        }
+        "epub" => {
+            EpubBuilder::new(config.clone())
+                .build(files, output_path)
+                .await
+        }
+        other => Err(DocPilotError::Build {
+            message: format!("Unsupported output format: {}", other),
+        }),
+    }
+}
+
+/// Run the project's pandoc with `args`. The process is killed when the
+/// returned future is dropped, so a watch rebuild can cancel a build that is
+/// still running.
+async fn run_pandoc(config: &ProjectConfig, args: &[String]) -> Result<()> {
+    let output = Command::new(config.tools.command("pandoc"))
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| DocPilotError::Build {
+            message: format!("Failed to execute pandoc: {}", e),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(DocPilotError::Build {
+            message: format!("Pandoc failed: {}", stderr),
+        });
+    }
+
+    Ok(())
+}
+
+/// Append the pandoc flag for the configured math method
+fn add_math_args(args: &mut Vec<String>, method: MathMethod, format: &str) {
+    if method.requires_network() {
+        warn!(
+            "Math method {:?} for {} loads scripts from a CDN; equations will not render offline",
+            method, format
+        );
+    }
+
+    if let Some(flag) = method.pandoc_flag() {
+        args.push(flag.to_string());
+    }
+}
+
 impl PdfBuilder {
     pub fn new(config: ProjectConfig) -> Self {
         Self { config }
@@ -26,29 +238,27 @@ impl PdfBuilder {
     pub async fn build(&self, files: &[MarkdownFile], output_path: &Path) -> Result<()> {
         info!("Building PDF: {}", output_path.display());
 
+        let files = &Preprocessor::new(self.config.clone())
+            .prepare(files, "pdf")
+            .await?;
+
         // Merge metadata from all files
         let metadata = MetadataParser::merge_metadata(files);
+        let template = self.find_template()?;
+
+        // Missing packages and fonts otherwise only show up when xelatex fails
+        let engine = self.config.tools.pdf_engine();
+        if LatexPreflight::applies_to(&engine) {
+            LatexPreflight::new(template.as_deref(), &metadata).check(&engine)?;
+        }
 
         // Build pandoc arguments
-        let args = self.build_pandoc_args(files, output_path, &metadata)?;
+        let args = self.build_pandoc_args(files, output_path, &metadata, template.as_deref())?;
 
         debug!("Pandoc command: pandoc {}", args.join(" "));
 
         // Execute pandoc
-        let output =
-            Command::new("pandoc")
-                .args(&args)
-                .output()
-                .map_err(|e| DocPilotError::Build {
-                    message: format!("Failed to execute pandoc: {}", e),
-                })?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(DocPilotError::Build {
-                message: format!("Pandoc failed: {}", stderr),
-            });
-        }
+        run_pandoc(&self.config, &args).await?;
 
         info!("✅ PDF generated successfully: {}", output_path.display());
         Ok(())
@@ -59,16 +269,21 @@ impl PdfBuilder {
         files: &[MarkdownFile],
         output_path: &Path,
         metadata: &DocumentMetadata,
+        template: Option<&Path>,
     ) -> Result<Vec<String>> {
         let mut args = vec![
             "--standalone".to_string(),
             "--listings".to_string(),
             "--pdf-engine".to_string(),
-            "xelatex".to_string(),
+            self.config.tools.pdf_engine().to_string_lossy().to_string(),
         ];
 
         // Template detection
-        if let Some(template) = self.find_template()? {
+        if let Some(template) = template {
+            // `title` always reaches pandoc through --metadata below
+            for warning in TemplateManager::frontmatter_warnings(template, files, &["title"]) {
+                warn!("{}", warning);
+            }
             args.push("--template".to_string());
             args.push(template.to_string_lossy().to_string());
             info!("Using template: {}", template.display());
@@ -178,25 +393,11 @@ impl PdfBuilder {
     }
 
     fn find_template(&self) -> Result<Option<PathBuf>> {
-        let eisvogel_path = self.config.templates_dir.join("eisvogel.latex");
-
-        if eisvogel_path.exists() {
-            return Ok(Some(eisvogel_path));
-        }
-
-        // Look for any .latex template
-        if self.config.templates_dir.exists() {
-            for entry in fs::read_dir(&self.config.templates_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-
-                if path.extension().is_some_and(|ext| ext == "latex") {
-                    return Ok(Some(path));
-                }
-            }
-        }
-
-        Ok(None)
+        TemplateManager::new(self.config.templates_dir.clone()).resolve_or_bundled(
+            "pdf",
+            self.config.templates.for_format("pdf"),
+            &self.config.output_dir.join(".cache").join("templates"),
+        )
     }
 
     pub fn ensure_output_dir(&self) -> Result<()> {
@@ -218,25 +419,16 @@ impl DocxBuilder {
     pub async fn build(&self, files: &[MarkdownFile], output_path: &Path) -> Result<()> {
         info!("Building DOCX: {}", output_path.display());
 
+        let files = &Preprocessor::new(self.config.clone())
+            .prepare(files, "docx")
+            .await?;
+
         let metadata = MetadataParser::merge_metadata(files);
         let args = self.build_pandoc_args(files, output_path, &metadata)?;
 
         debug!("Pandoc command: pandoc {}", args.join(" "));
 
-        let output =
-            Command::new("pandoc")
-                .args(&args)
-                .output()
-                .map_err(|e| DocPilotError::Build {
-                    message: format!("Failed to execute pandoc: {}", e),
-                })?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(DocPilotError::Build {
-                message: format!("Pandoc failed: {}", stderr),
-            });
-        }
+        run_pandoc(&self.config, &args).await?;
 
         info!("✅ DOCX generated successfully: {}", output_path.display());
         Ok(())
@@ -276,17 +468,8 @@ impl DocxBuilder {
     }
 
     fn find_docx_template(&self) -> Result<Option<PathBuf>> {
-        if self.config.templates_dir.exists() {
-            for entry in fs::read_dir(&self.config.templates_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-
-                if path.extension().is_some_and(|ext| ext == "docx") {
-                    return Ok(Some(path));
-                }
-            }
-        }
-        Ok(None)
+        TemplateManager::new(self.config.templates_dir.clone())
+            .resolve("docx", self.config.templates.for_format("docx"))
     }
 
     pub fn ensure_output_dir(&self) -> Result<()> {
@@ -305,25 +488,16 @@ impl HtmlBuilder {
     pub async fn build(&self, files: &[MarkdownFile], output_path: &Path) -> Result<()> {
         info!("Building HTML: {}", output_path.display());
 
+        let files = &Preprocessor::new(self.config.clone())
+            .prepare(files, "html")
+            .await?;
+
         let metadata = MetadataParser::merge_metadata(files);
         let args = self.build_pandoc_args(files, output_path, &metadata)?;
 
         debug!("Pandoc command: pandoc {}", args.join(" "));
 
-        let output =
-            Command::new("pandoc")
-                .args(&args)
-                .output()
-                .map_err(|e| DocPilotError::Build {
-                    message: format!("Failed to execute pandoc: {}", e),
-                })?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(DocPilotError::Build {
-                message: format!("Pandoc failed: {}", stderr),
-            });
-        }
+        run_pandoc(&self.config, &args).await?;
 
         info!("✅ HTML generated successfully: {}", output_path.display());
         Ok(())
@@ -343,11 +517,16 @@ impl HtmlBuilder {
             "--citeproc".to_string(),
         ];
 
+        add_math_args(&mut args, self.config.math.html, "html");
+
         if metadata.numbersections.unwrap_or(true) {
             args.push("--number-sections".to_string());
         }
 
         if let Some(template) = self.find_html_template()? {
+            for warning in TemplateManager::frontmatter_warnings(&template, files, &[]) {
+                warn!("{}", warning);
+            }
             args.push("--template".to_string());
             args.push(template.to_string_lossy().to_string());
         }
@@ -363,17 +542,78 @@ impl HtmlBuilder {
     }
 
     fn find_html_template(&self) -> Result<Option<PathBuf>> {
-        if self.config.templates_dir.exists() {
-            for entry in fs::read_dir(&self.config.templates_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-
-                if path.extension().is_some_and(|ext| ext == "html") {
-                    return Ok(Some(path));
-                }
-            }
+        TemplateManager::new(self.config.templates_dir.clone())
+            .resolve("html", self.config.templates.for_format("html"))
+    }
+
+    pub fn ensure_output_dir(&self) -> Result<()> {
+        if !self.config.output_dir.exists() {
+            fs::create_dir_all(&self.config.output_dir)?;
         }
-        Ok(None)
+        Ok(())
+    }
+}
+
+impl EpubBuilder {
+    pub fn new(config: ProjectConfig) -> Self {
+        Self { config }
+    }
+
+    pub async fn build(&self, files: &[MarkdownFile], output_path: &Path) -> Result<()> {
+        info!("Building EPUB: {}", output_path.display());
+
+        let files = &Preprocessor::new(self.config.clone())
+            .prepare(files, "epub")
+            .await?;
+
+        let metadata = MetadataParser::merge_metadata(files);
+        let args = self.build_pandoc_args(files, output_path, &metadata)?;
+
+        debug!("Pandoc command: pandoc {}", args.join(" "));
+
+        run_pandoc(&self.config, &args).await?;
+
+        info!("✅ EPUB generated successfully: {}", output_path.display());
+        Ok(())
+    }
+
+    fn build_pandoc_args(
+        &self,
+        files: &[MarkdownFile],
+        output_path: &Path,
+        metadata: &DocumentMetadata,
+    ) -> Result<Vec<String>> {
+        let mut args = vec![
+            "--standalone".to_string(),
+            "--to".to_string(),
+            "epub3".to_string(),
+            "--citeproc".to_string(),
+        ];
+
+        add_math_args(&mut args, self.config.math.epub, "epub");
+
+        if metadata.numbersections.unwrap_or(true) {
+            args.push("--number-sections".to_string());
+        }
+
+        if let Some(stylesheet) = self.find_epub_stylesheet()? {
+            args.push("--css".to_string());
+            args.push(stylesheet.to_string_lossy().to_string());
+        }
+
+        for file in files {
+            args.push(file.path.to_string_lossy().to_string());
+        }
+
+        args.push("-o".to_string());
+        args.push(output_path.to_string_lossy().to_string());
+
+        Ok(args)
+    }
+
+    fn find_epub_stylesheet(&self) -> Result<Option<PathBuf>> {
+        TemplateManager::new(self.config.templates_dir.clone())
+            .resolve("epub", self.config.templates.for_format("epub"))
     }
 
     pub fn ensure_output_dir(&self) -> Result<()> {
@@ -383,3 +623,33 @@ impl HtmlBuilder {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_args_use_configured_math_method() {
+        let mut config = ProjectConfig::default();
+        config.math.html = MathMethod::Katex;
+
+        let builder = HtmlBuilder::new(config);
+        let args = builder
+            .build_pandoc_args(&[], Path::new("out.html"), &DocumentMetadata::default())
+            .unwrap();
+
+        assert!(args.contains(&"--katex".to_string()));
+        assert!(!args.contains(&"--mathml".to_string()));
+    }
+
+    #[test]
+    fn test_epub_args_default_to_mathml() {
+        let builder = EpubBuilder::new(ProjectConfig::default());
+        let args = builder
+            .build_pandoc_args(&[], Path::new("out.epub"), &DocumentMetadata::default())
+            .unwrap();
+
+        assert!(args.contains(&"epub3".to_string()));
+        assert!(args.contains(&"--mathml".to_string()));
+    }
+}
@@ -0,0 +1,597 @@
+//! `docpilot chapter`: add, move, renumber and remove the numbered Markdown
+//! files whose prefixes decide the build order.
+
+use crate::discovery::MetadataParser;
+use crate::errors::{DocPilotError, Result};
+use crate::import::slugify;
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+/// A Markdown file named `<number>-<rest>.md`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChapterFile {
+    pub number: u32,
+    /// File name without the number and its dash, e.g. `introduction.md`
+    pub rest: String,
+    pub file_name: String,
+}
+
+impl ChapterFile {
+    fn parse(file_name: &str) -> Option<Self> {
+        let (prefix, rest) = file_name.split_once('-')?;
+        if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        if !rest.ends_with(".md") || rest == ".md" {
+            return None;
+        }
+
+        Some(Self {
+            number: prefix.parse().ok()?,
+            rest: rest.to_string(),
+            file_name: file_name.to_string(),
+        })
+    }
+}
+
+/// File operations for one chapter command
+#[derive(Debug, Default, PartialEq)]
+pub struct ChapterPlan {
+    /// Old and new file names
+    pub renames: Vec<(String, String)>,
+    /// New chapter file and its content
+    pub create: Option<(String, String)>,
+    pub remove: Option<String>,
+}
+
+impl ChapterPlan {
+    pub fn is_empty(&self) -> bool {
+        self.renames.is_empty() && self.create.is_none() && self.remove.is_none()
+    }
+}
+
+/// What [`ChapterManager::apply`] changed
+#[derive(Debug, Default)]
+pub struct ChapterReport {
+    /// Files whose links to renamed chapters were rewritten
+    pub updated_links: Vec<String>,
+    /// Links that point to a removed chapter, as `file -> target`
+    pub dangling_links: Vec<String>,
+    /// Whether files were moved with `git mv`/`git rm`
+    pub used_git: bool,
+}
+
+pub struct ChapterManager {
+    root: PathBuf,
+}
+
+impl ChapterManager {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Numbered chapters in build order. `00-` files (the setup file) are
+    /// not chapters and never renumbered.
+    pub fn chapters(&self) -> Result<Vec<ChapterFile>> {
+        let mut chapters: Vec<ChapterFile> = fs::read_dir(&self.root)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| ChapterFile::parse(&entry.file_name().to_string_lossy()))
+            .filter(|chapter| chapter.number > 0)
+            .collect();
+
+        // Same natural ordering as discovery
+        chapters.sort_by(|a, b| natord::compare(&a.file_name, &b.file_name));
+        Ok(chapters)
+    }
+
+    /// Find a chapter by number (`3`, `03`), file name or stem
+    fn resolve(chapters: &[ChapterFile], id: &str) -> Result<usize> {
+        let matches: Vec<usize> = match id.parse::<u32>() {
+            Ok(number) => chapters
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.number == number)
+                .map(|(i, _)| i)
+                .collect(),
+            Err(_) => chapters
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.file_name == id || c.file_name.trim_end_matches(".md") == id)
+                .map(|(i, _)| i)
+                .collect(),
+        };
+
+        match matches.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(DocPilotError::Config {
+                message: format!("No chapter '{}'; see 'docpilot chapter list'", id),
+            }),
+            _ => Err(DocPilotError::Config {
+                message: format!(
+                    "Chapter number '{}' is used by several files; run 'docpilot chapter renumber' first",
+                    id
+                ),
+            }),
+        }
+    }
+
+    fn width(chapters: &[ChapterFile], largest: u32) -> usize {
+        chapters
+            .iter()
+            .map(|c| c.file_name.split_once('-').map_or(0, |(p, _)| p.len()))
+            .max()
+            .unwrap_or(2)
+            .max(largest.to_string().len())
+            .max(2)
+    }
+
+    /// Renames needed to give the chapters in `order` the numbers in `numbers`
+    fn renames(order: &[ChapterFile], numbers: &[u32], width: usize) -> Vec<(String, String)> {
+        order
+            .iter()
+            .zip(numbers)
+            .map(|(chapter, number)| {
+                (
+                    chapter.file_name.clone(),
+                    format!("{:0width$}-{}", number, chapter.rest),
+                )
+            })
+            .filter(|(old, new)| old != new)
+            .collect()
+    }
+
+    /// Insert a chapter after or before another one, or at the end. Later
+    /// chapters are only shifted as far as needed to make room.
+    pub fn plan_add(
+        &self,
+        title: &str,
+        after: Option<&str>,
+        before: Option<&str>,
+    ) -> Result<ChapterPlan> {
+        let chapters = self.chapters()?;
+        let position = match (after, before) {
+            (Some(id), _) => Self::resolve(&chapters, id)? + 1,
+            (None, Some(id)) => Self::resolve(&chapters, id)?,
+            (None, None) => chapters.len(),
+        };
+
+        // Walk the chapters after the insertion point, bumping each one that
+        // would no longer sort after its predecessor
+        let mut previous = match position {
+            0 => 0,
+            _ => chapters[position - 1].number,
+        };
+        let new_number = previous + 1;
+        previous = new_number;
+        let mut numbers = Vec::new();
+        for chapter in &chapters[position..] {
+            let number = if chapter.number > previous {
+                chapter.number
+            } else {
+                previous + 1
+            };
+            numbers.push(number);
+            previous = number;
+        }
+
+        let width = Self::width(&chapters, previous);
+        let renames = Self::renames(&chapters[position..], &numbers, width);
+        let file_name = format!("{:0width$}-{}.md", new_number, slugify(title));
+        let moved_away = renames.iter().any(|(old, _)| *old == file_name);
+        if self.root.join(&file_name).exists() && !moved_away {
+            return Err(DocPilotError::Config {
+                message: format!("{} already exists", file_name),
+            });
+        }
+
+        Ok(ChapterPlan {
+            renames,
+            create: Some((file_name, format!("# {}\n\n", title))),
+            remove: None,
+        })
+    }
+
+    /// Move a chapter to the place of the chapter currently numbered
+    /// `position`. The numbers in between are reused, shifted by one.
+    pub fn plan_move(&self, id: &str, position: &str) -> Result<ChapterPlan> {
+        let chapters = self.chapters()?;
+        let from = Self::resolve(&chapters, id)?;
+        let target: u32 = position.parse().map_err(|_| DocPilotError::Config {
+            message: format!("Position '{}' must be a chapter number", position),
+        })?;
+        let to = chapters
+            .iter()
+            .position(|c| c.number >= target)
+            .unwrap_or(chapters.len() - 1)
+            .min(chapters.len() - 1);
+
+        let (start, end) = (from.min(to), from.max(to));
+        let numbers: Vec<u32> = chapters[start..=end].iter().map(|c| c.number).collect();
+        let mut order = chapters[start..=end].to_vec();
+        let moved = order.remove(from - start);
+        order.insert(to - start, moved);
+
+        let width = Self::width(&chapters, *numbers.iter().max().unwrap_or(&0));
+        Ok(ChapterPlan {
+            renames: Self::renames(&order, &numbers, width),
+            ..Default::default()
+        })
+    }
+
+    /// Number all chapters `start`, `start + step`, ... in their current order
+    pub fn plan_renumber(&self, start: u32, step: u32) -> Result<ChapterPlan> {
+        let chapters = self.chapters()?;
+        let numbers: Vec<u32> = (0..chapters.len() as u32)
+            .map(|i| start + i * step)
+            .collect();
+        let width = Self::width(&[], numbers.last().copied().unwrap_or(0));
+
+        Ok(ChapterPlan {
+            renames: Self::renames(&chapters, &numbers, width),
+            ..Default::default()
+        })
+    }
+
+    /// Delete a chapter; the others keep their numbers
+    pub fn plan_remove(&self, id: &str) -> Result<ChapterPlan> {
+        let chapters = self.chapters()?;
+        let index = Self::resolve(&chapters, id)?;
+
+        Ok(ChapterPlan {
+            remove: Some(chapters[index].file_name.clone()),
+            ..Default::default()
+        })
+    }
+
+    pub fn apply(&self, plan: &ChapterPlan) -> Result<ChapterReport> {
+        let mut report = ChapterReport {
+            used_git: self.in_git_repo(),
+            ..Default::default()
+        };
+
+        // Work out link changes against the files as they are now
+        let renamed: BTreeMap<&str, &str> = plan
+            .renames
+            .iter()
+            .map(|(old, new)| (old.as_str(), new.as_str()))
+            .collect();
+        let mut rewritten = Vec::new();
+        for file in self.markdown_files()? {
+            let path = self.root.join(&file);
+            let Some(content) =
+                self.rewrite_links(&path, &renamed, plan.remove.as_deref(), &mut report)?
+            else {
+                continue;
+            };
+            let target = renamed
+                .get(file.as_str())
+                .map_or(file.clone(), |n| n.to_string());
+            report.updated_links.push(target.clone());
+            rewritten.push((target, content));
+        }
+
+        // Two phases, so that swapping 02 and 03 never collides
+        let staged: Vec<(String, String, String)> = plan
+            .renames
+            .iter()
+            .map(|(old, new)| (old.clone(), format!(".{}.renaming", old), new.clone()))
+            .collect();
+        for (old, temporary, _) in &staged {
+            self.rename(old, temporary, report.used_git)?;
+        }
+        for (old, temporary, new) in &staged {
+            self.rename(temporary, new, report.used_git)?;
+            info!("Renamed {} → {}", old, new);
+        }
+
+        if let Some(removed) = &plan.remove {
+            self.remove(removed, report.used_git)?;
+            info!("Removed {}", removed);
+        }
+
+        for (file, content) in rewritten {
+            fs::write(self.root.join(&file), content)?;
+        }
+
+        if let Some((file_name, content)) = &plan.create {
+            fs::write(self.root.join(file_name), content)?;
+            if report.used_git {
+                self.git(&["add", "--", file_name])?;
+            }
+            info!("Created {}", file_name);
+        }
+
+        Ok(report)
+    }
+
+    /// Top-level Markdown files, the same set discovery builds from
+    fn markdown_files(&self) -> Result<Vec<String>> {
+        let mut files: Vec<String> = fs::read_dir(&self.root)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".md"))
+            .collect();
+        files.sort_by(|a, b| natord::compare(a, b));
+        Ok(files)
+    }
+
+    /// New content for `path` if it links to a renamed chapter
+    fn rewrite_links(
+        &self,
+        path: &Path,
+        renamed: &BTreeMap<&str, &str>,
+        removed: Option<&str>,
+        report: &mut ChapterReport,
+    ) -> Result<Option<String>> {
+        let file = match MetadataParser::parse_file(path) {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("Skipping links in {}: {}", path.display(), e);
+                return Ok(None);
+            }
+        };
+
+        // Chapter files this file links to, as found by dependency extraction
+        let chapter_name = |dependency: &Path| -> Option<String> {
+            let dependency = normalize(dependency);
+            (normalize(dependency.parent().unwrap_or(Path::new(""))) == normalize(&self.root))
+                .then(|| {
+                    dependency
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                })
+                .flatten()
+        };
+        let linked: Vec<String> = file
+            .dependencies
+            .iter()
+            .filter_map(|dependency| chapter_name(dependency))
+            .collect();
+
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some(removed) = removed.filter(|r| linked.iter().any(|l| l == r)) {
+            if file_name != removed {
+                report
+                    .dangling_links
+                    .push(format!("{} -> {}", file_name, removed));
+            }
+        }
+        if !linked.iter().any(|l| renamed.contains_key(l.as_str())) {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)?;
+        let link = Regex::new(r"(\]\(<?)((?:\./)?)([^)\s#>/]+\.md)").unwrap();
+        let updated = link.replace_all(&content, |captures: &Captures| {
+            match renamed.get(&captures[3]) {
+                Some(new) => format!("{}{}{}", &captures[1], &captures[2], new),
+                None => captures[0].to_string(),
+            }
+        });
+
+        Ok((updated != content).then(|| updated.into_owned()))
+    }
+
+    fn in_git_repo(&self) -> bool {
+        Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args(["rev-parse", "--is-inside-work-tree"])
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    fn is_tracked(&self, file: &str) -> bool {
+        Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args(["ls-files", "--error-unmatch", "--", file])
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    fn git(&self, args: &[&str]) -> Result<()> {
+        debug!("git {}", args.join(" "));
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args(args)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(DocPilotError::Build {
+                message: format!(
+                    "git {} failed: {}",
+                    args.join(" "),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// `git mv` for tracked files keeps their history
+    fn rename(&self, from: &str, to: &str, use_git: bool) -> Result<()> {
+        if use_git && self.is_tracked(from) {
+            self.git(&["mv", "--", from, to])
+        } else {
+            fs::rename(self.root.join(from), self.root.join(to))?;
+            Ok(())
+        }
+    }
+
+    fn remove(&self, file: &str, use_git: bool) -> Result<()> {
+        if use_git && self.is_tracked(file) {
+            self.git(&["rm", "--quiet", "--", file])
+        } else {
+            fs::remove_file(self.root.join(file))?;
+            Ok(())
+        }
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn project(files: &[&str]) -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        for file in files {
+            fs::write(temp_dir.path().join(file), format!("# {}\n", file)).unwrap();
+        }
+        temp_dir
+    }
+
+    fn renames(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_add_shifts_only_as_needed() {
+        let temp_dir = project(&["00-setup.md", "01-a.md", "02-b.md", "03-c.md", "10-d.md"]);
+        let manager = ChapterManager::new(temp_dir.path().to_path_buf());
+
+        let plan = manager.plan_add("Deployment", Some("01"), None).unwrap();
+        assert_eq!(
+            plan.create,
+            Some((
+                "02-deployment.md".to_string(),
+                "# Deployment\n\n".to_string()
+            ))
+        );
+        assert_eq!(
+            plan.renames,
+            renames(&[("02-b.md", "03-b.md"), ("03-c.md", "04-c.md")])
+        );
+
+        let plan = manager.plan_add("Annex", None, None).unwrap();
+        assert_eq!(plan.create.unwrap().0, "11-annex.md");
+        assert!(plan.renames.is_empty());
+    }
+
+    #[test]
+    fn test_move_reuses_numbers() {
+        let temp_dir = project(&["01-a.md", "02-b.md", "03-c.md", "04-d.md", "05-e.md"]);
+        let manager = ChapterManager::new(temp_dir.path().to_path_buf());
+
+        assert_eq!(
+            manager.plan_move("05", "02").unwrap().renames,
+            renames(&[
+                ("05-e.md", "02-e.md"),
+                ("02-b.md", "03-b.md"),
+                ("03-c.md", "04-c.md"),
+                ("04-d.md", "05-d.md"),
+            ])
+        );
+        assert_eq!(
+            manager.plan_move("2", "4").unwrap().renames,
+            renames(&[
+                ("03-c.md", "02-c.md"),
+                ("04-d.md", "03-d.md"),
+                ("02-b.md", "04-b.md"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_renumber_and_apply_rewrites_links() {
+        let temp_dir = project(&["00-setup.md", "1-intro.md", "5-usage.md", "30-faq.md"]);
+        fs::write(
+            temp_dir.path().join("30-faq.md"),
+            "# FAQ\n\nSee [usage](5-usage.md#install) and [intro](./1-intro.md).\n",
+        )
+        .unwrap();
+        let manager = ChapterManager::new(temp_dir.path().to_path_buf());
+
+        let plan = manager.plan_renumber(1, 1).unwrap();
+        assert_eq!(
+            plan.renames,
+            renames(&[
+                ("1-intro.md", "01-intro.md"),
+                ("5-usage.md", "02-usage.md"),
+                ("30-faq.md", "03-faq.md"),
+            ])
+        );
+
+        let report = manager.apply(&plan).unwrap();
+        assert_eq!(report.updated_links, vec!["03-faq.md".to_string()]);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("03-faq.md")).unwrap(),
+            "# FAQ\n\nSee [usage](02-usage.md#install) and [intro](./01-intro.md).\n"
+        );
+        assert!(temp_dir.path().join("00-setup.md").exists());
+        assert!(!temp_dir.path().join("5-usage.md").exists());
+    }
+
+    #[test]
+    fn test_apply_uses_git_mv() {
+        let temp_dir = project(&["01-a.md", "02-b.md"]);
+        let manager = ChapterManager::new(temp_dir.path().to_path_buf());
+        if manager.git(&["init", "--quiet"]).is_err() {
+            return;
+        }
+        manager.git(&["add", "."]).unwrap();
+        manager
+            .git(&[
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "--quiet",
+                "-m",
+                "init",
+            ])
+            .unwrap();
+
+        let report = manager
+            .apply(&manager.plan_move("2", "1").unwrap())
+            .unwrap();
+        assert!(report.used_git);
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(temp_dir.path())
+            .args(["diff", "--cached", "--name-status", "-M"])
+            .output()
+            .unwrap();
+        let status = String::from_utf8_lossy(&output.stdout);
+        assert!(status.contains("02-b.md\t01-b.md"));
+        assert!(!status.contains(".renaming"));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("01-b.md")).unwrap(),
+            "# 02-b.md\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_reports_dangling_links() {
+        let temp_dir = project(&["01-a.md", "02-b.md"]);
+        fs::write(temp_dir.path().join("01-a.md"), "[b](02-b.md)\n").unwrap();
+        let manager = ChapterManager::new(temp_dir.path().to_path_buf());
+
+        let report = manager
+            .apply(&manager.plan_remove("02-b").unwrap())
+            .unwrap();
+        assert_eq!(
+            report.dangling_links,
+            vec!["01-a.md -> 02-b.md".to_string()]
+        );
+        assert!(!temp_dir.path().join("02-b.md").exists());
+    }
+}
@@ -1,6 +1,7 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +87,291 @@ impl Default for DocumentMetadata {
     }
 }
 
+/// How equations are rendered in HTML-based outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MathMethod {
+    /// Convert TeX to MathML at build time (works offline)
+    Mathml,
+    /// Load MathJax from a CDN when the document is opened
+    Mathjax,
+    /// Load KaTeX from a CDN when the document is opened
+    Katex,
+    /// Leave equations as plain TeX
+    Plain,
+}
+
+impl MathMethod {
+    pub fn pandoc_flag(&self) -> Option<&'static str> {
+        match self {
+            MathMethod::Mathml => Some("--mathml"),
+            MathMethod::Mathjax => Some("--mathjax"),
+            MathMethod::Katex => Some("--katex"),
+            MathMethod::Plain => None,
+        }
+    }
+
+    /// Whether the rendered document needs network access to display equations
+    pub fn requires_network(&self) -> bool {
+        matches!(self, MathMethod::Mathjax | MathMethod::Katex)
+    }
+}
+
+/// Math rendering method per output format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MathSettings {
+    pub html: MathMethod,
+    pub epub: MathMethod,
+}
+
+impl Default for MathSettings {
+    fn default() -> Self {
+        Self {
+            html: MathMethod::Mathml,
+            epub: MathMethod::Mathml,
+        }
+    }
+}
+
+/// Corporate look applied by `templates new docx|html`.
+/// Unset values fall back to the matching frontmatter fields.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeSettings {
+    /// Body text font (frontmatter `mainfont`)
+    pub body_font: Option<String>,
+    /// Heading font (frontmatter `sansfont`)
+    pub heading_font: Option<String>,
+    /// Code font (frontmatter `monofont`)
+    pub mono_font: Option<String>,
+    /// Body text size such as `11pt` (frontmatter `fontsize`)
+    pub font_size: Option<String>,
+    /// Colors as hex values, e.g. `"#1F4E79"`
+    pub text_color: Option<String>,
+    pub heading_color: Option<String>,
+    /// Link color (frontmatter `linkcolor`)
+    pub link_color: Option<String>,
+    /// Page margin such as `2.5cm` (frontmatter `geometry: margin=...`)
+    pub margin: Option<String>,
+    /// Image shown in the page header
+    pub logo: Option<PathBuf>,
+}
+
+impl ThemeSettings {
+    pub fn with_metadata_fallbacks(&self, metadata: &DocumentMetadata) -> Self {
+        let margin = metadata.geometry.as_ref().and_then(|options| {
+            options
+                .iter()
+                .find_map(|option| option.trim().strip_prefix("margin="))
+                .map(|margin| margin.to_string())
+        });
+
+        Self {
+            body_font: self.body_font.clone().or_else(|| metadata.mainfont.clone()),
+            heading_font: self
+                .heading_font
+                .clone()
+                .or_else(|| metadata.sansfont.clone()),
+            mono_font: self.mono_font.clone().or_else(|| metadata.monofont.clone()),
+            font_size: self.font_size.clone().or_else(|| metadata.fontsize.clone()),
+            text_color: self.text_color.clone(),
+            heading_color: self.heading_color.clone(),
+            link_color: self
+                .link_color
+                .clone()
+                .or_else(|| metadata.linkcolor.clone()),
+            margin: self.margin.clone().or(margin),
+            logo: self.logo.clone(),
+        }
+    }
+}
+
+/// Shell commands run around every format build, in normal and watch builds.
+/// They get `DOCPILOT_FORMAT`, `DOCPILOT_OUTPUT` and, after the build,
+/// `DOCPILOT_STATUS`, `DOCPILOT_DURATION_MS` and `DOCPILOT_ERROR`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookSettings {
+    /// Runs before the build; a failure skips the build
+    pub pre_build: Option<String>,
+    /// Runs after every build; a failure fails the build
+    pub post_build: Option<String>,
+    pub on_success: Option<String>,
+    pub on_failure: Option<String>,
+}
+
+/// Template chosen per output format, by package name or file path
+#[derive(Debug, Clone, Default)]
+pub struct TemplateSelection {
+    pub pdf: Option<String>,
+    pub docx: Option<String>,
+    pub html: Option<String>,
+    pub epub: Option<String>,
+}
+
+impl TemplateSelection {
+    pub fn for_format(&self, format: &str) -> Option<&str> {
+        match format {
+            "pdf" => self.pdf.as_deref(),
+            "docx" => self.docx.as_deref(),
+            "html" => self.html.as_deref(),
+            "epub" => self.epub.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, format: &str, template: Option<String>) {
+        match format {
+            "pdf" => self.pdf = template,
+            "docx" => self.docx = template,
+            "html" => self.html = template,
+            "epub" => self.epub = template,
+            _ => {}
+        }
+    }
+}
+
+/// Key of the `tools` entry that picks the PDF engine
+pub const PDF_ENGINE_KEY: &str = "pdf_engine";
+
+const DEFAULT_PDF_ENGINE: &str = "xelatex";
+
+/// Key of the `tools` entry naming the Chrome or Chromium Mermaid diagrams
+/// are rendered with
+pub const BROWSER_KEY: &str = "chrome";
+
+/// Tools docpilot runs itself, so their path and version can be configured.
+/// The PDF engines' own names set the engine's version range.
+const CONFIGURABLE_TOOLS: &[&str] = &[
+    "pandoc",
+    PDF_ENGINE_KEY,
+    "xelatex",
+    "lualatex",
+    "tectonic",
+    "typst",
+    BROWSER_KEY,
+];
+
+/// A `tools` entry: the binary to run and the versions it must have.
+/// A plain string such as `">=3.1,<4"` only sets the version, except under
+/// `pdf_engine`, where `pdf_engine: lualatex` names the engine.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ToolSetting {
+    /// Path or name of the binary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Version requirement such as `>=3.1,<4` or `3.1`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ToolEntry {
+    Text(String),
+    Full {
+        path: Option<PathBuf>,
+        version: Option<String>,
+    },
+}
+
+impl ToolEntry {
+    fn into_setting(self, tool: &str) -> ToolSetting {
+        match self {
+            ToolEntry::Text(engine) if tool == PDF_ENGINE_KEY => ToolSetting {
+                path: Some(PathBuf::from(engine)),
+                version: None,
+            },
+            ToolEntry::Text(version) => ToolSetting {
+                path: None,
+                version: Some(version),
+            },
+            ToolEntry::Full { path, version } => ToolSetting { path, version },
+        }
+    }
+}
+
+/// The `tools` section of docpilot.yml, by tool name. `pdf_engine` selects
+/// the PDF engine; the other keys are the tools `docpilot check` knows.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct ToolSettings(pub BTreeMap<String, ToolSetting>);
+
+impl<'de> Deserialize<'de> for ToolSettings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = BTreeMap::<String, ToolEntry>::deserialize(deserializer)?;
+        if let Some(tool) = entries
+            .keys()
+            .find(|tool| !CONFIGURABLE_TOOLS.contains(&tool.as_str()))
+        {
+            return Err(D::Error::custom(format!(
+                "tools: docpilot does not run '{}'; configurable tools are {}",
+                tool,
+                CONFIGURABLE_TOOLS.join(", ")
+            )));
+        }
+        Ok(Self(
+            entries
+                .into_iter()
+                .map(|(tool, entry)| {
+                    let setting = entry.into_setting(&tool);
+                    (tool, setting)
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl ToolSettings {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Setting for `tool`. The PDF engine's comes from `pdf_engine` first.
+    pub fn get(&self, tool: &str) -> Option<&ToolSetting> {
+        let engine = (tool == self.pdf_engine_name())
+            .then(|| self.0.get(PDF_ENGINE_KEY))
+            .flatten();
+        engine.or_else(|| self.0.get(tool))
+    }
+
+    /// Binary to run for `tool`
+    pub fn command(&self, tool: &str) -> PathBuf {
+        self.get(tool)
+            .and_then(|setting| setting.path.clone())
+            .unwrap_or_else(|| PathBuf::from(tool))
+    }
+
+    /// Version requirement for `tool`, if the project declares one
+    pub fn version(&self, tool: &str) -> Option<&str> {
+        self.get(tool)
+            .and_then(|setting| setting.version.as_deref())
+    }
+
+    /// The PDF engine's tool name: `xelatex` unless `pdf_engine` names another
+    pub fn pdf_engine_name(&self) -> String {
+        self.0
+            .get(PDF_ENGINE_KEY)
+            .and_then(|setting| setting.path.as_ref())
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| DEFAULT_PDF_ENGINE.to_string())
+    }
+
+    /// Value for pandoc's `--pdf-engine`
+    pub fn pdf_engine(&self) -> PathBuf {
+        self.command(&self.pdf_engine_name())
+    }
+
+    /// Browser for Mermaid diagrams, if the project sets one; otherwise the
+    /// renderer looks for Chrome, Chromium or Edge itself
+    pub fn browser(&self) -> Option<&Path> {
+        self.0
+            .get(BROWSER_KEY)
+            .and_then(|setting| setting.path.as_deref())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ProjectConfig {
     pub name: String,
@@ -93,6 +379,16 @@ pub struct ProjectConfig {
     pub templates_dir: PathBuf,
     pub images_dir: PathBuf,
     pub exclude_files: Vec<String>,
+    pub math: MathSettings,
+    pub templates: TemplateSelection,
+    pub theme: ThemeSettings,
+    /// Milliseconds of quiet after a change before a watch rebuild starts
+    pub debounce_ms: u64,
+    pub hooks: HookSettings,
+    pub tools: ToolSettings,
+    /// Build even if a referenced diagram is missing or fails to render
+    /// (`build --keep-going`)
+    pub keep_going: bool,
 }
 
 impl Default for ProjectConfig {
@@ -103,6 +399,13 @@ impl Default for ProjectConfig {
             templates_dir: PathBuf::from("templates"),
             images_dir: PathBuf::from("images"),
             exclude_files: vec!["README.md".to_string()],
+            math: MathSettings::default(),
+            templates: TemplateSelection::default(),
+            theme: ThemeSettings::default(),
+            debounce_ms: 300,
+            hooks: HookSettings::default(),
+            tools: ToolSettings::default(),
+            keep_going: false,
         }
     }
 }
@@ -138,6 +441,15 @@ mod tests {
         assert_eq!(config.name, "document");
         assert_eq!(config.output_dir, PathBuf::from("output"));
         assert_eq!(config.templates_dir, PathBuf::from("templates"));
+        assert_eq!(config.math.html, MathMethod::Mathml);
+    }
+
+    #[test]
+    fn test_math_method_flags() {
+        assert_eq!(MathMethod::Mathml.pandoc_flag(), Some("--mathml"));
+        assert_eq!(MathMethod::Plain.pandoc_flag(), None);
+        assert!(!MathMethod::Mathml.requires_network());
+        assert!(MathMethod::Katex.requires_network());
     }
 
     #[test]
@@ -1,18 +1,44 @@
-use crate::config::{DocumentMetadata, ProjectConfig};
+use crate::config::{
+    DocumentMetadata, HookSettings, MathSettings, ProjectConfig, TemplateSelection, ThemeSettings,
+    ToolSettings,
+};
 use crate::errors::{DocPilotError, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
+/// Config file names looked up in the project directory, in order
+pub const CONFIG_FILE_NAMES: [&str; 4] = [
+    "docpilot.yml",
+    "docpilot.yaml",
+    ".docpilot.yml",
+    ".docpilot.yaml",
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocPilotConfig {
+    #[serde(default)]
     pub project: ProjectSettings,
+    #[serde(default)]
     pub build: BuildSettings,
+    #[serde(default)]
     pub templates: TemplateSettings,
+    #[serde(default)]
+    pub math: MathSettings,
+    #[serde(default)]
+    pub theme: ThemeSettings,
+    #[serde(default, skip_serializing_if = "is_default_hooks")]
+    pub hooks: HookSettings,
+    #[serde(default, skip_serializing_if = "ToolSettings::is_empty")]
+    pub tools: ToolSettings,
     pub metadata: Option<DocumentMetadata>,
 }
 
+fn is_default_hooks(hooks: &HookSettings) -> bool {
+    *hooks == HookSettings::default()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectSettings {
     pub name: String,
@@ -20,38 +46,55 @@ pub struct ProjectSettings {
     pub source_dir: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            name: "document".to_string(),
+            output_dir: Some(PathBuf::from("output")),
+            source_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BuildSettings {
     pub default_format: Option<String>,
     pub watch: Option<bool>,
     pub clean_before_build: Option<bool>,
+    /// Quiet period after the last change before `--watch` rebuilds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debounce_ms: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TemplateSettings {
     pub pdf_template: Option<String>,
     pub html_template: Option<String>,
     pub docx_template: Option<String>,
+    #[serde(default)]
+    pub epub_template: Option<String>,
 }
 
 impl Default for DocPilotConfig {
     fn default() -> Self {
         Self {
-            project: ProjectSettings {
-                name: "document".to_string(),
-                output_dir: Some(PathBuf::from("output")),
-                source_dir: None,
-            },
+            project: ProjectSettings::default(),
             build: BuildSettings {
                 default_format: Some("pdf".to_string()),
                 watch: Some(false),
                 clean_before_build: Some(false),
+                debounce_ms: None,
             },
             templates: TemplateSettings {
                 pdf_template: None,
                 html_template: None,
                 docx_template: None,
+                epub_template: None,
             },
+            math: MathSettings::default(),
+            theme: ThemeSettings::default(),
+            hooks: HookSettings::default(),
+            tools: ToolSettings::default(),
             metadata: None,
         }
     }
@@ -87,14 +130,7 @@ impl DocPilotConfig {
     }
 
     pub fn find_config_file() -> Option<PathBuf> {
-        let candidates = [
-            "docpilot.yml",
-            "docpilot.yaml",
-            ".docpilot.yml",
-            ".docpilot.yaml",
-        ];
-
-        for candidate in &candidates {
+        for candidate in &CONFIG_FILE_NAMES {
             let path = PathBuf::from(candidate);
             if path.exists() {
                 return Some(path);
@@ -104,16 +140,43 @@ impl DocPilotConfig {
         None
     }
 
-    #[allow(dead_code)]
+    /// Load the config given on the command line, or the first one found in
+    /// the current directory, falling back to defaults
+    pub fn resolve(explicit_path: Option<&Path>) -> Result<Self> {
+        match explicit_path {
+            Some(path) if !path.exists() => Err(DocPilotError::Config {
+                message: format!("Config file not found: {}", path.display()),
+            }),
+            Some(path) => Self::load_from_file(path),
+            None => match Self::find_config_file() {
+                Some(path) => Self::load_from_file(&path),
+                None => Ok(Self::default()),
+            },
+        }
+    }
+
     pub fn to_project_config(&self) -> ProjectConfig {
         let mut config = ProjectConfig {
             name: self.project.name.clone(),
+            math: self.math.clone(),
+            templates: TemplateSelection {
+                pdf: self.templates.pdf_template.clone(),
+                docx: self.templates.docx_template.clone(),
+                html: self.templates.html_template.clone(),
+                epub: self.templates.epub_template.clone(),
+            },
+            theme: self.theme.clone(),
+            hooks: self.hooks.clone(),
+            tools: self.tools.clone(),
             ..Default::default()
         };
 
         if let Some(output_dir) = &self.project.output_dir {
             config.output_dir = output_dir.clone();
         }
+        if let Some(debounce_ms) = self.build.debounce_ms {
+            config.debounce_ms = debounce_ms;
+        }
 
         config
     }
@@ -176,6 +239,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_tools() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("docpilot.yml");
+        fs::write(
+            &config_path,
+            "tools:\n  pandoc: \">=3.1,<4\"\n  pdf_engine:\n    path: /opt/texlive/bin/lualatex\n    version: \">=1.17\"\n  chrome:\n    path: /usr/bin/chromium\n",
+        )
+        .unwrap();
+
+        let tools = DocPilotConfig::load_from_file(&config_path)
+            .unwrap()
+            .to_project_config()
+            .tools;
+        assert_eq!(tools.command("pandoc"), PathBuf::from("pandoc"));
+        assert_eq!(tools.version("pandoc"), Some(">=3.1,<4"));
+        assert_eq!(tools.pdf_engine_name(), "lualatex");
+        assert_eq!(
+            tools.pdf_engine(),
+            PathBuf::from("/opt/texlive/bin/lualatex")
+        );
+        assert_eq!(tools.version("lualatex"), Some(">=1.17"));
+        assert_eq!(tools.browser(), Some(Path::new("/usr/bin/chromium")));
+        assert_eq!(tools.version("xelatex"), None);
+
+        let defaults = ProjectConfig::default().tools;
+        assert_eq!(defaults.pdf_engine(), PathBuf::from("xelatex"));
+
+        // A plain string under pdf_engine names the engine, not a version
+        let engine: ToolSettings = serde_yaml::from_str(
+            "pdf_engine: lualatex
+",
+        )
+        .unwrap();
+        assert_eq!(engine.pdf_engine_name(), "lualatex");
+        assert_eq!(engine.pdf_engine(), PathBuf::from("lualatex"));
+        assert_eq!(engine.version("lualatex"), None);
+
+        // Tools docpilot never runs can't be configured
+        fs::write(
+            &config_path,
+            "tools:\n  dot:\n    path: /usr/local/bin/dot\n",
+        )
+        .unwrap();
+        let error = DocPilotConfig::load_from_file(&config_path).unwrap_err();
+        assert!(error.to_string().contains("does not run 'dot'"));
+    }
+
+    #[test]
+    fn test_config_hooks() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("docpilot.yml");
+        fs::write(
+            &config_path,
+            "hooks:\n  post_build: verapdf \"$DOCPILOT_OUTPUT\"\n  on_failure: ./notify.sh\n",
+        )
+        .unwrap();
+
+        let hooks = DocPilotConfig::load_from_file(&config_path)
+            .unwrap()
+            .to_project_config()
+            .hooks;
+        assert_eq!(
+            hooks.post_build.as_deref(),
+            Some("verapdf \"$DOCPILOT_OUTPUT\"")
+        );
+        assert_eq!(hooks.on_failure.as_deref(), Some("./notify.sh"));
+        assert!(hooks.pre_build.is_none());
+    }
+
     #[test]
     fn test_config_load_missing_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -207,6 +340,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_theme_falls_back_to_frontmatter() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("docpilot.yml");
+        fs::write(
+            &config_path,
+            "theme:\n  heading_color: \"#1F4E79\"\n  body_font: \"Source Serif Pro\"\n",
+        )
+        .unwrap();
+
+        let theme = DocPilotConfig::load_from_file(&config_path)
+            .unwrap()
+            .to_project_config()
+            .theme;
+
+        let metadata = DocumentMetadata {
+            mainfont: Some("Times New Roman".to_string()),
+            monofont: Some("Fira Mono".to_string()),
+            geometry: Some(vec!["a4paper".to_string(), "margin=2cm".to_string()]),
+            ..Default::default()
+        };
+        let resolved = theme.with_metadata_fallbacks(&metadata);
+
+        assert_eq!(resolved.body_font.as_deref(), Some("Source Serif Pro"));
+        assert_eq!(resolved.mono_font.as_deref(), Some("Fira Mono"));
+        assert_eq!(resolved.heading_color.as_deref(), Some("#1F4E79"));
+        assert_eq!(resolved.margin.as_deref(), Some("2cm"));
+    }
+
+    #[test]
+    fn test_config_load_partial_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("docpilot.yml");
+        fs::write(&config_path, "math:\n  html: katex\n  epub: mathml\n").unwrap();
+
+        let config = DocPilotConfig::load_from_file(&config_path).unwrap();
+        assert_eq!(config.project.name, "document");
+
+        let project_config = config.to_project_config();
+        assert_eq!(project_config.math.html, crate::config::MathMethod::Katex);
+    }
+
+    #[test]
+    fn test_resolve_missing_explicit_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let result = DocPilotConfig::resolve(Some(&temp_dir.path().join("missing.yml")));
+
+        assert!(matches!(result, Err(DocPilotError::Config { .. })));
+    }
+
     #[test]
     fn test_find_config_file_none() {
         let temp_dir = TempDir::new().unwrap();
@@ -239,5 +422,20 @@ mod tests {
 
         assert_eq!(project_config.name, "test-project");
         assert_eq!(project_config.output_dir, PathBuf::from("custom-output"));
+        assert_eq!(project_config.debounce_ms, 300);
+
+        config.build.debounce_ms = Some(50);
+        assert_eq!(config.to_project_config().debounce_ms, 50);
+    }
+
+    #[test]
+    fn test_to_project_config_template_selection() {
+        let mut config = DocPilotConfig::default();
+        config.templates.pdf_template = Some("eisvogel".to_string());
+
+        let project_config = config.to_project_config();
+
+        assert_eq!(project_config.templates.for_format("pdf"), Some("eisvogel"));
+        assert_eq!(project_config.templates.for_format("docx"), None);
     }
 }
@@ -0,0 +1,516 @@
+//! Preflight for PDF builds: the LaTeX packages a template needs and the fonts
+//! named in the frontmatter are looked up before pandoc starts, instead of
+//! surfacing as an xelatex error at the end of a long run.
+
+use super::PackageManager;
+use crate::config::DocumentMetadata;
+use crate::errors::{DocPilotError, Result};
+use crate::templates::TemplateManager;
+use regex::Regex;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, info, warn};
+use which::which;
+
+/// Engines that run a local TeX installation; tectonic fetches packages on
+/// demand and typst does not use LaTeX at all
+const LATEX_ENGINES: [&str; 4] = ["xelatex", "lualatex", "pdflatex", "latexmk"];
+
+/// Font files fontspec can load by name instead of a family
+const FONT_EXTENSIONS: [&str; 3] = ["otf", "ttf", "ttc"];
+
+/// A font from the frontmatter, with the variable that names it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontRequirement {
+    pub variable: &'static str,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatexPreflight {
+    /// TeX files such as `mdframed.sty` or `scrartcl.cls` the build cannot
+    /// do without
+    files: BTreeSet<String>,
+    /// Packages read from a template without a manifest. The guess can be
+    /// wrong, so missing ones only warn.
+    detected: BTreeSet<String>,
+    fonts: Vec<FontRequirement>,
+}
+
+impl LatexPreflight {
+    /// Collect what a PDF build with `template` and `metadata` needs. The
+    /// template's `latex_packages` manifest entry is used when present,
+    /// otherwise its unconditional `\usepackage` lines are only warned about.
+    pub fn new(template: Option<&Path>, metadata: &DocumentMetadata) -> Self {
+        // Builds always pass --listings
+        let mut files = BTreeSet::from(["listings.sty".to_string()]);
+        let mut detected = BTreeSet::new();
+
+        if let Some(template) = template {
+            match TemplateManager::manifest_for(template)
+                .map(|manifest| manifest.latex_packages)
+                .filter(|packages| !packages.is_empty())
+            {
+                Some(packages) => {
+                    files.extend(packages.iter().map(|package| tex_file(package, "sty")))
+                }
+                None => detected.extend(
+                    fs::read_to_string(template)
+                        .map(|content| used_packages(&content))
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|package| tex_file(package, "sty")),
+                ),
+            }
+        }
+
+        if let Some(class) = &metadata.documentclass {
+            files.insert(tex_file(class, "cls"));
+        }
+
+        let fonts: Vec<FontRequirement> = [
+            ("mainfont", &metadata.mainfont),
+            ("sansfont", &metadata.sansfont),
+            ("monofont", &metadata.monofont),
+        ]
+        .into_iter()
+        .filter_map(|(variable, name)| {
+            let name = name.as_deref()?.trim();
+            (!name.is_empty()).then(|| FontRequirement {
+                variable,
+                name: name.to_string(),
+            })
+        })
+        .collect();
+
+        if !fonts.is_empty() {
+            files.insert("fontspec.sty".to_string());
+        }
+
+        detected.retain(|file| !files.contains(file));
+
+        Self {
+            files,
+            detected,
+            fonts,
+        }
+    }
+
+    /// Whether the PDF engine `engine` needs the preflight
+    pub fn applies_to(engine: &Path) -> bool {
+        engine
+            .file_stem()
+            .is_some_and(|stem| LATEX_ENGINES.contains(&stem.to_string_lossy().as_ref()))
+    }
+
+    /// Fail with an install hint when packages or fonts are missing. The
+    /// `kpsewhich` next to `engine` is preferred. Lookups are skipped when
+    /// `kpsewhich` or `fc-list` is not installed.
+    pub fn check(&self, engine: &Path) -> Result<()> {
+        info!(
+            "Checking {} LaTeX files and {} fonts",
+            self.files.len(),
+            self.fonts.len()
+        );
+
+        let kpsewhich = kpsewhich_for(engine);
+        let wanted: BTreeSet<String> = self.files.union(&self.detected).cloned().collect();
+        let missing_files = match find_tex_files(&kpsewhich, &wanted)? {
+            Some(found) => {
+                let missing = |files: &BTreeSet<String>| -> Vec<String> {
+                    files
+                        .iter()
+                        .filter(|file| !found.contains(file.as_str()))
+                        .cloned()
+                        .collect()
+                };
+
+                let guessed = missing(&self.detected);
+                if !guessed.is_empty() {
+                    warn!(
+                        "The PDF template may need LaTeX packages that are not installed: {}",
+                        guessed.join(", ")
+                    );
+                }
+                missing(&self.files)
+            }
+            None => {
+                debug!("kpsewhich not found, skipping LaTeX package check");
+                Vec::new()
+            }
+        };
+
+        let missing_fonts: Vec<&FontRequirement> = self
+            .fonts
+            .iter()
+            .filter(|font| font_installed(&kpsewhich, &font.name) == Some(false))
+            .collect();
+
+        match report(PackageManager::detect(), &missing_files, &missing_fonts) {
+            Some(hint) => Err(DocPilotError::Dependency {
+                tool: "latex".to_string(),
+                hint,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Packages a pandoc template always loads with `\usepackage` or
+/// `\RequirePackage`. Packages inside `$if(...)$` or `$for(...)$` blocks
+/// (including their `$else$` branches), `\IfFileExists` guards and pandoc
+/// comments are left out, as are names built from template variables.
+fn used_packages(template: &str) -> Vec<String> {
+    let comment = Regex::new(r"(?m)\$--.*$").unwrap();
+    let tokens = Regex::new(
+        r"(?P<open>\$\{?(?:if|for)\()|(?P<close>\$\{?end(?:if|for)\}?\$?)|\\(?:usepackage|RequirePackage)\s*(?:\[[^\]]*\])?\s*\{(?P<packages>[^}]+)\}",
+    )
+    .unwrap();
+
+    let template = comment.replace_all(template, "");
+    let template = strip_file_guards(&template);
+
+    let mut depth = 0usize;
+    let mut packages = Vec::new();
+    for cap in tokens.captures_iter(&template) {
+        if cap.name("open").is_some() {
+            depth += 1;
+        } else if cap.name("close").is_some() {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 {
+            for package in cap["packages"].split(',').map(str::trim) {
+                if !package.is_empty() && !package.contains('$') {
+                    packages.push(package.to_string());
+                }
+            }
+        }
+    }
+    packages
+}
+
+/// Remove `\IfFileExists{file}{then}{else}`: whatever it loads is optional
+fn strip_file_guards(template: &str) -> String {
+    const GUARD: &str = "\\IfFileExists";
+
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(GUARD) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start + GUARD.len()..];
+        for _ in 0..3 {
+            rest = skip_group(rest);
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Skip whitespace, `%` comments and one balanced `{...}` group
+fn skip_group(text: &str) -> &str {
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(i, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '%' => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+            '{' => {
+                let mut depth = 0usize;
+                let mut escaped = false;
+                for (j, c) in text[i..].char_indices() {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return &text[i + j + 1..];
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                return "";
+            }
+            _ => return &text[i..],
+        }
+    }
+    ""
+}
+
+fn tex_file(name: &str, extension: &str) -> String {
+    if Path::new(name).extension().is_some() {
+        name.to_string()
+    } else {
+        format!("{}.{}", name, extension)
+    }
+}
+
+fn is_font_file(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .is_some_and(|ext| FONT_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+}
+
+/// The `kpsewhich` of the TeX installation `engine` belongs to
+fn kpsewhich_for(engine: &Path) -> PathBuf {
+    engine
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.join("kpsewhich"))
+        .filter(|kpsewhich| which(kpsewhich).is_ok())
+        .unwrap_or_else(|| PathBuf::from("kpsewhich"))
+}
+
+/// File names `kpsewhich` resolves, or `None` without a TeX installation
+fn find_tex_files(kpsewhich: &Path, files: &BTreeSet<String>) -> Result<Option<HashSet<String>>> {
+    if files.is_empty() || which(kpsewhich).is_err() {
+        return Ok(None);
+    }
+
+    // kpsewhich prints one path per file it finds and skips the others
+    let output =
+        Command::new(kpsewhich)
+            .args(files)
+            .output()
+            .map_err(|e| DocPilotError::Build {
+                message: format!("Failed to execute kpsewhich: {}", e),
+            })?;
+
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| Path::new(line.trim()).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect(),
+    ))
+}
+
+/// Whether fontconfig knows the family, or `None` without `fc-list`. Font
+/// files are looked for in the project and then in the TeX tree.
+fn font_installed(kpsewhich: &Path, family: &str) -> Option<bool> {
+    if is_font_file(family) {
+        let path = Path::new(family);
+        if path.is_file() {
+            return Some(true);
+        }
+        let name = path.file_name()?.to_string_lossy().to_string();
+        let found = find_tex_files(kpsewhich, &BTreeSet::from([name.clone()])).ok()??;
+        return Some(found.contains(&name));
+    }
+
+    which("fc-list").ok()?;
+
+    let output = Command::new("fc-list")
+        .arg(format!(":family={}", family))
+        .arg("family")
+        .output()
+        .ok()?;
+    Some(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+}
+
+/// TeX Live package that ships `file`, for the ones not named after it
+fn tlmgr_package(file: &str) -> String {
+    let stem = Path::new(file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| file.to_string());
+
+    match stem.as_str() {
+        "scrartcl" | "scrbook" | "scrreprt" | "scrlayer-scrpage" => "koma-script".to_string(),
+        _ => stem,
+    }
+}
+
+fn report(
+    manager: PackageManager,
+    missing_files: &[String],
+    missing_fonts: &[&FontRequirement],
+) -> Option<String> {
+    if missing_files.is_empty() && missing_fonts.is_empty() {
+        return None;
+    }
+
+    let mut hint = String::from("Missing LaTeX packages or fonts for the PDF build:\n");
+    for file in missing_files {
+        hint.push_str(&format!("  - {}\n", file));
+    }
+    for font in missing_fonts {
+        hint.push_str(&format!("  - font \"{}\" ({})\n", font.name, font.variable));
+    }
+
+    if !missing_files.is_empty() {
+        let packages: BTreeSet<String> = missing_files
+            .iter()
+            .map(|file| tlmgr_package(file))
+            .collect();
+        let packages: Vec<String> = packages.into_iter().collect();
+
+        hint.push_str("Install the packages with one of:\n");
+        for command in manager.tex_hints(&packages, missing_files) {
+            hint.push_str(&format!("  {}\n", command));
+        }
+    }
+    if !missing_fonts.is_empty() {
+        hint.push_str(
+            "Install the fonts or choose installed ones in the frontmatter (`fc-list : family` lists them)\n",
+        );
+    }
+
+    Some(hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::eisvogel_files;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_preflight_collects_template_packages_and_fonts() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut template = None;
+        for (file, content) in eisvogel_files().unwrap() {
+            let path = temp_dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+            if path.extension().is_some_and(|ext| ext == "latex") {
+                template = Some(path);
+            }
+        }
+
+        let metadata = DocumentMetadata {
+            documentclass: Some("scrbook".to_string()),
+            mainfont: Some("Source Serif Pro".to_string()),
+            monofont: Some("fonts/FiraMono.otf".to_string()),
+            ..Default::default()
+        };
+        let preflight = LatexPreflight::new(template.as_deref(), &metadata);
+        let files: Vec<&str> = preflight.files.iter().map(|f| f.as_str()).collect();
+
+        for expected in [
+            "footnotebackref.sty",
+            "mdframed.sty",
+            "sourcesanspro.sty",
+            "listings.sty",
+            "fontspec.sty",
+            "scrbook.cls",
+        ] {
+            assert!(files.contains(&expected), "missing {}", expected);
+        }
+        assert_eq!(preflight.fonts.len(), 2);
+        assert_eq!(preflight.fonts[0].variable, "mainfont");
+    }
+
+    #[test]
+    fn test_preflight_applies_to_latex_engines() {
+        assert!(LatexPreflight::applies_to(Path::new("xelatex")));
+        assert!(LatexPreflight::applies_to(Path::new(
+            "/opt/texlive/bin/lualatex"
+        )));
+        assert!(!LatexPreflight::applies_to(Path::new("tectonic")));
+        assert!(!LatexPreflight::applies_to(Path::new("typst")));
+    }
+
+    #[test]
+    fn test_used_packages_skips_conditional_packages_in_eisvogel() {
+        let (_, template) = eisvogel_files()
+            .unwrap()
+            .into_iter()
+            .find(|(file, _)| file.extension().is_some_and(|ext| ext == "latex"))
+            .unwrap();
+        let packages = used_packages(&template);
+
+        for conditional in [
+            "beamerarticle",
+            "mathspec",
+            "xeCJK",
+            "luatexja-preset",
+            "luatexja-fontspec",
+            "selnolig",
+            "natbib",
+            "biblatex",
+            "svg",
+            "lua-ul",
+            "luacolor",
+            "draftwatermark",
+            "upquote",
+            "microtype",
+            "parskip",
+        ] {
+            assert!(
+                !packages.iter().any(|p| p == conditional),
+                "{} is only loaded conditionally",
+                conditional
+            );
+        }
+        for unconditional in ["xcolor", "amsmath", "iftex"] {
+            assert!(
+                packages.iter().any(|p| p == unconditional),
+                "missing {}",
+                unconditional
+            );
+        }
+    }
+
+    #[test]
+    fn test_loose_template_packages_only_warn() {
+        let temp_dir = TempDir::new().unwrap();
+        let template = temp_dir.path().join("eisvogel.latex");
+        fs::write(
+            &template,
+            "\\usepackage{mdframed}\n\\usepackage{listings}\n",
+        )
+        .unwrap();
+
+        let preflight = LatexPreflight::new(Some(&template), &DocumentMetadata::default());
+        assert!(preflight.detected.contains("mdframed.sty"));
+        assert!(!preflight.files.contains("mdframed.sty"));
+        // Already a hard requirement, so not repeated as a guess
+        assert!(!preflight.detected.contains("listings.sty"));
+    }
+
+    #[test]
+    fn test_used_packages_skips_template_variables() {
+        let template = r"\usepackage[utf8]{inputenc}
+\usepackage{amsmath, amssymb}
+\RequirePackage{xcolor}
+\usepackage{$fontfamily$}
+$if(fontfamily)$\usepackage{fontfamily}$else$\usepackage{lmodern}$endif$
+\IfFileExists{upquote.sty}{\usepackage{upquote}}{}
+$-- \usepackage{commented}";
+
+        assert_eq!(
+            used_packages(template),
+            vec!["inputenc", "amsmath", "amssymb", "xcolor"]
+        );
+    }
+
+    #[test]
+    fn test_report_lists_missing_with_hints() {
+        assert_eq!(report(PackageManager::Apt, &[], &[]), None);
+
+        let font = FontRequirement {
+            variable: "monofont",
+            name: "Fira Mono".to_string(),
+        };
+        let hint = report(
+            PackageManager::Apt,
+            &[
+                "scrlayer-scrpage.sty".to_string(),
+                "mdframed.sty".to_string(),
+            ],
+            &[&font],
+        )
+        .unwrap();
+
+        assert!(hint.contains("  - mdframed.sty\n"));
+        assert!(hint.contains("  - font \"Fira Mono\" (monofont)\n"));
+        assert!(hint.contains("  tlmgr install koma-script mdframed\n"));
+        assert!(hint.contains("  sudo apt install texlive-latex-extra texlive-fonts-extra\n"));
+        assert!(hint.contains("fc-list"));
+    }
+}
@@ -0,0 +1,253 @@
+//! Install hints for the package manager of the machine docpilot runs on.
+
+use serde::Serialize;
+use std::fs;
+
+const OS_RELEASE: &str = "/etc/os-release";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+    Zypper,
+    Apk,
+    Nix,
+    Brew,
+    Unknown,
+}
+
+impl PackageManager {
+    /// Homebrew on macOS, the distribution's package manager on Linux
+    pub fn detect() -> Self {
+        if cfg!(target_os = "macos") {
+            PackageManager::Brew
+        } else if cfg!(target_os = "linux") {
+            fs::read_to_string(OS_RELEASE)
+                .map(|content| Self::from_os_release(&content))
+                .unwrap_or(PackageManager::Unknown)
+        } else {
+            PackageManager::Unknown
+        }
+    }
+
+    /// Pick the package manager from the `ID` of an os-release file, falling
+    /// back to the distributions listed in `ID_LIKE`
+    pub fn from_os_release(content: &str) -> Self {
+        let field = |key: &str| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                .map(|value| value.trim().trim_matches('"').to_lowercase())
+                .unwrap_or_default()
+        };
+
+        let id = field("ID");
+        let id_like = field("ID_LIKE");
+        std::iter::once(id.as_str())
+            .chain(id_like.split_whitespace())
+            .map(Self::from_distribution)
+            .find(|manager| *manager != PackageManager::Unknown)
+            .unwrap_or(PackageManager::Unknown)
+    }
+
+    fn from_distribution(id: &str) -> Self {
+        match id {
+            "debian" | "ubuntu" | "linuxmint" | "pop" | "raspbian" => PackageManager::Apt,
+            "fedora" | "rhel" | "centos" | "rocky" | "almalinux" => PackageManager::Dnf,
+            "arch" | "manjaro" | "endeavouros" => PackageManager::Pacman,
+            "suse" | "opensuse" | "sles" => PackageManager::Zypper,
+            id if id.starts_with("opensuse") => PackageManager::Zypper,
+            "alpine" => PackageManager::Apk,
+            "nixos" => PackageManager::Nix,
+            _ => PackageManager::Unknown,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Pacman => "pacman",
+            PackageManager::Zypper => "zypper",
+            PackageManager::Apk => "apk",
+            PackageManager::Nix => "nix",
+            PackageManager::Brew => "brew",
+            PackageManager::Unknown => "unknown",
+        }
+    }
+
+    /// Command installing the packages
+    fn install(&self, packages: &[&str]) -> Option<String> {
+        let packages = packages.join(" ");
+        let command = match self {
+            PackageManager::Apt => format!("sudo apt install {}", packages),
+            PackageManager::Dnf => format!("sudo dnf install {}", packages),
+            PackageManager::Pacman => format!("sudo pacman -S {}", packages),
+            PackageManager::Zypper => format!("sudo zypper install {}", packages),
+            PackageManager::Apk => format!("sudo apk add {}", packages),
+            PackageManager::Nix => packages
+                .split(' ')
+                .map(|package| format!("nix profile install nixpkgs#{}", package))
+                .collect::<Vec<_>>()
+                .join(" && "),
+            PackageManager::Brew => format!("brew install {}", packages),
+            PackageManager::Unknown => return None,
+        };
+        Some(command)
+    }
+
+    /// This manager's name for one of the packages in
+    /// [`TOOLS`](super::TOOLS), if it ships it
+    fn package_name(&self, package: &str) -> Option<&'static str> {
+        use PackageManager::*;
+
+        let name = match (self, package) {
+            (Pacman, "pandoc") => "pandoc-cli",
+            (_, "pandoc") => "pandoc",
+
+            (Apt | Dnf | Zypper | Pacman | Apk, "texlive") => "texlive-xetex",
+            (Apt | Dnf | Zypper | Pacman | Apk, "texlive-luatex") => "texlive-luatex",
+            (Nix, "texlive" | "texlive-luatex" | "latexmk") => "texliveMedium",
+            (Brew, "texlive" | "texlive-luatex" | "latexmk") => "--cask mactex",
+            (Apt | Dnf, "latexmk") => "latexmk",
+            (Zypper, "latexmk") => "texlive-latexmk",
+            (Pacman, "latexmk") => "texlive-binextra",
+
+            (Dnf | Pacman | Apk | Nix | Brew, "tectonic") => "tectonic",
+            (Pacman | Apk | Nix | Brew, "typst") => "typst",
+            (Pacman | Nix | Brew, "pandoc-crossref") => "pandoc-crossref",
+            (Apt | Dnf | Zypper | Pacman | Nix | Brew, "plantuml") => "plantuml",
+            (_, "graphviz") => "graphviz",
+
+            (Apt, "librsvg") => "librsvg2-bin",
+            (Dnf, "librsvg") => "librsvg2-tools",
+            (Zypper | Apk, "librsvg") => "rsvg-convert",
+            (Pacman | Nix | Brew, "librsvg") => "librsvg",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// How to install `package`, with a fallback where the manager has none
+    pub fn install_hint(&self, package: &str) -> String {
+        if let Some(command) = self
+            .package_name(package)
+            .and_then(|name| self.install(&[name]))
+        {
+            return command;
+        }
+
+        match package {
+            "tectonic" => "cargo install tectonic".to_string(),
+            "typst" => "cargo install --locked typst-cli".to_string(),
+            "pandoc" => "download from https://pandoc.org/installing.html".to_string(),
+            "pandoc-crossref" => {
+                "download from https://github.com/lierdakil/pandoc-crossref/releases".to_string()
+            }
+            "plantuml" => "download plantuml.jar from https://plantuml.com/download".to_string(),
+            _ => format!("Install {} via your package manager", package),
+        }
+    }
+
+    /// How to install TeX files such as `mdframed.sty`. `tlmgr` works for
+    /// TeX Live installed from upstream; distribution builds of TeX Live
+    /// need the distribution's packages instead.
+    pub fn tex_hints(&self, tlmgr_packages: &[String], files: &[String]) -> Vec<String> {
+        let mut hints = vec![format!("tlmgr install {}", tlmgr_packages.join(" "))];
+
+        let provides: Vec<String> = files
+            .iter()
+            .map(|file| format!("'tex({})'", file))
+            .collect();
+        let provides: Vec<&str> = provides.iter().map(|p| p.as_str()).collect();
+        let distribution = match self {
+            // Fedora and openSUSE package every TeX file as `tex(<file>)`
+            PackageManager::Dnf | PackageManager::Zypper => self.install(&provides),
+            PackageManager::Apt => self.install(&["texlive-latex-extra", "texlive-fonts-extra"]),
+            PackageManager::Pacman => self.install(&["texlive-latexextra", "texlive-fontsextra"]),
+            PackageManager::Apk => {
+                self.install(&["texmf-dist-latexextra", "texmf-dist-fontsextra"])
+            }
+            PackageManager::Nix => self.install(&["texliveFull"]),
+            PackageManager::Brew | PackageManager::Unknown => None,
+        };
+        hints.extend(distribution);
+        hints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_manager_from_os_release() {
+        let detect = PackageManager::from_os_release;
+
+        assert_eq!(
+            detect("NAME=\"Ubuntu\"\nID=ubuntu\nID_LIKE=debian\n"),
+            PackageManager::Apt
+        );
+        assert_eq!(detect("ID=fedora\nVERSION_ID=40\n"), PackageManager::Dnf);
+        assert_eq!(
+            detect("ID=\"opensuse-tumbleweed\"\nID_LIKE=\"opensuse suse\"\n"),
+            PackageManager::Zypper
+        );
+        assert_eq!(
+            detect("ID=endeavouros\nID_LIKE=arch\n"),
+            PackageManager::Pacman
+        );
+        assert_eq!(detect("ID=alpine\n"), PackageManager::Apk);
+        assert_eq!(detect("ID=nixos\n"), PackageManager::Nix);
+        // Unknown derivatives fall back to ID_LIKE
+        assert_eq!(
+            detect("ID=pika\nID_LIKE=\"ubuntu debian\"\n"),
+            PackageManager::Apt
+        );
+        assert_eq!(detect("ID=haiku\n"), PackageManager::Unknown);
+    }
+
+    #[test]
+    fn test_install_hints_per_package_manager() {
+        assert_eq!(
+            PackageManager::Apt.install_hint("librsvg"),
+            "sudo apt install librsvg2-bin"
+        );
+        assert_eq!(
+            PackageManager::Dnf.install_hint("texlive"),
+            "sudo dnf install texlive-xetex"
+        );
+        assert_eq!(
+            PackageManager::Pacman.install_hint("pandoc"),
+            "sudo pacman -S pandoc-cli"
+        );
+        assert_eq!(
+            PackageManager::Nix.install_hint("graphviz"),
+            "nix profile install nixpkgs#graphviz"
+        );
+        assert_eq!(
+            PackageManager::Brew.install_hint("texlive"),
+            "brew install --cask mactex"
+        );
+        assert_eq!(
+            PackageManager::Apt.install_hint("typst"),
+            "cargo install --locked typst-cli"
+        );
+        assert_eq!(
+            PackageManager::Unknown.install_hint("graphviz"),
+            "Install graphviz via your package manager"
+        );
+
+        let hints =
+            PackageManager::Dnf.tex_hints(&["mdframed".to_string()], &["mdframed.sty".to_string()]);
+        assert_eq!(
+            hints,
+            vec![
+                "tlmgr install mdframed",
+                "sudo dnf install 'tex(mdframed.sty)'"
+            ]
+        );
+    }
+}
@@ -1,11 +1,12 @@
-use crate::config::ProjectConfig;
+use crate::config::{MarkdownFile, ProjectConfig};
 use crate::errors::{DocPilotError, Result};
 // TODO: Consider migrating from mermaid-rs to alternative that uses ab_glyph instead of rusttype
 // The current mermaid-rs dependency pulls in rusttype which is unmaintained (RUSTSEC-2021-0140)
 // Recommended migration: rusttype -> ab_glyph
 use mermaid_rs::Mermaid;
+use std::fmt;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use tracing::{debug, info, warn};
 
 pub struct DiagramProcessor {
@@ -13,16 +14,128 @@ pub struct DiagramProcessor {
     mermaid: Option<Mermaid>,
 }
 
+/// Rendered artifact flavour for a Mermaid diagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Svg,
+    Png,
+}
+
+impl DiagramFormat {
+    /// Pick the artifact that works best for the given output format.
+    /// HTML embeds SVG directly; LaTeX and Word need a raster image.
+    pub fn for_output(format: &str) -> Self {
+        match format {
+            "html" => DiagramFormat::Svg,
+            _ => DiagramFormat::Png,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DiagramFormat::Svg => "svg",
+            DiagramFormat::Png => "png",
+        }
+    }
+}
+
+/// Result of rendering one diagram, either from a `.mmd` file or an inline block
+#[derive(Debug, Clone)]
+pub struct DiagramOutcome {
+    pub source: PathBuf,
+    /// Line of the opening fence for inline blocks
+    pub line: Option<usize>,
+    pub result: std::result::Result<PathBuf, String>,
+}
+
+impl DiagramOutcome {
+    pub fn location(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}", self.source.display(), line),
+            None => self.source.display().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for DiagramOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.result {
+            Ok(artifact) => write!(f, "{} -> {}", self.location(), artifact.display()),
+            Err(message) => write!(f, "{}: {}", self.location(), message),
+        }
+    }
+}
+
+/// Collected outcomes of a diagram run
+#[derive(Debug, Default)]
+pub struct DiagramReport {
+    pub outcomes: Vec<DiagramOutcome>,
+}
+
+impl DiagramReport {
+    pub fn failures(&self) -> impl Iterator<Item = &DiagramOutcome> {
+        self.outcomes.iter().filter(|o| o.result.is_err())
+    }
+
+    pub fn failure_count(&self) -> usize {
+        self.failures().count()
+    }
+
+    pub fn print_summary(&self) {
+        let width = self
+            .outcomes
+            .iter()
+            .map(|o| o.location().chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Source".len());
+
+        println!();
+        println!("📊 Diagram summary");
+        println!("   {:<width$}  Result", "Source", width = width);
+        for outcome in &self.outcomes {
+            let (icon, detail) = match &outcome.result {
+                Ok(artifact) => ("✅", artifact.display().to_string()),
+                Err(message) => ("❌", message.lines().next().unwrap_or_default().to_string()),
+            };
+            println!(
+                "{} {:<width$}  {}",
+                icon,
+                outcome.location(),
+                detail,
+                width = width
+            );
+        }
+        println!(
+            "   {} rendered, {} failed",
+            self.outcomes.len() - self.failure_count(),
+            self.failure_count()
+        );
+    }
+}
+
+/// A fenced ```` ```mermaid ```` block found in a Markdown file
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineDiagram {
+    /// 1-based line of the opening fence
+    pub line: usize,
+    /// 1-based line of the closing fence
+    pub end_line: usize,
+    pub code: String,
+}
+
 impl DiagramProcessor {
     pub fn new(config: ProjectConfig) -> Self {
-        let mermaid = match Mermaid::new() {
+        let mermaid = match Self::start_renderer(&config) {
             Ok(m) => {
                 info!("✅ Native Mermaid renderer initialized");
                 Some(m)
             }
             Err(e) => {
                 warn!("Failed to initialize native Mermaid renderer: {}", e);
-                warn!("Diagram processing will be skipped");
+                warn!(
+                    "Mermaid diagrams will fail to render; pass --keep-going to build without them"
+                );
                 None
             }
         };
@@ -30,22 +143,247 @@ impl DiagramProcessor {
         Self { config, mermaid }
     }
 
-    pub async fn process_all(&self, mermaid_files: &[PathBuf]) -> Result<()> {
-        if self.mermaid.is_none() {
-            return Err(DocPilotError::Build {
-                message: "Native Mermaid renderer not available".to_string(),
-            });
+    fn start_renderer(config: &ProjectConfig) -> std::result::Result<Mermaid, String> {
+        if let Some(browser) = config.tools.browser() {
+            let browser = which::which(browser).map_err(|_| {
+                format!("browser {} from docpilot.yml not found", browser.display())
+            })?;
+            // mermaid-rs starts the browser named by $CHROME before searching PATH
+            std::env::set_var("CHROME", browser);
         }
+        Mermaid::new().map_err(|e| e.to_string())
+    }
+
+    /// Render every `.mmd` file and every inline block in the given Markdown files.
+    /// Failures are collected in the report instead of aborting the run.
+    pub async fn process_all(
+        &self,
+        mermaid_files: &[PathBuf],
+        markdown_files: &[MarkdownFile],
+    ) -> Result<DiagramReport> {
+        let Some(mermaid) = self.mermaid.as_ref() else {
+            return Ok(Self::unavailable_report(mermaid_files, markdown_files));
+        };
 
-        let mermaid = self.mermaid.as_ref().unwrap();
         let output_dir = self.config.output_dir.join("diagrams");
         fs::create_dir_all(&output_dir)?;
 
+        let mut report = DiagramReport::default();
+
         for file_path in mermaid_files {
-            self.process_file(mermaid, file_path, &output_dir).await?;
+            info!("Processing diagram: {}", file_path.display());
+
+            let result = match fs::read_to_string(file_path) {
+                Ok(content) => {
+                    let file_stem = file_path
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let artifact_dir = Self::artifact_dir(&self.config, file_path);
+                    match fs::create_dir_all(&artifact_dir) {
+                        Ok(()) => {
+                            self.render_to_files(mermaid, &content, &artifact_dir, &file_stem)
+                                .await
+                        }
+                        Err(e) => Err(format!(
+                            "Failed to create {}: {}",
+                            artifact_dir.display(),
+                            e
+                        )),
+                    }
+                }
+                Err(e) => Err(format!("Failed to read file: {}", e)),
+            };
+
+            if let Err(message) = &result {
+                warn!("{}: {}", file_path.display(), message);
+            }
+            report.outcomes.push(DiagramOutcome {
+                source: file_path.clone(),
+                line: None,
+                result,
+            });
         }
 
-        Ok(())
+        for file in markdown_files.iter().filter(|f| f.has_inline_mermaid) {
+            // Re-read the file so line numbers include the frontmatter
+            match fs::read_to_string(&file.path) {
+                Ok(content) => {
+                    let outcomes = self
+                        .process_inline_mermaid(mermaid, &file.path, &content)
+                        .await;
+                    report.outcomes.extend(outcomes);
+                }
+                Err(e) => report.outcomes.push(DiagramOutcome {
+                    source: file.path.clone(),
+                    line: None,
+                    result: Err(format!("Failed to read file: {}", e)),
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// A report failing every diagram, for when the renderer could not start
+    fn unavailable_report(
+        mermaid_files: &[PathBuf],
+        markdown_files: &[MarkdownFile],
+    ) -> DiagramReport {
+        let failed = |source: &Path, line: Option<usize>| DiagramOutcome {
+            source: source.to_path_buf(),
+            line,
+            result: Err("Native Mermaid renderer not available".to_string()),
+        };
+
+        let mut report = DiagramReport::default();
+        report
+            .outcomes
+            .extend(mermaid_files.iter().map(|file| failed(file, None)));
+        for file in markdown_files.iter().filter(|f| f.has_inline_mermaid) {
+            let content = fs::read_to_string(&file.path).unwrap_or_else(|_| file.content.clone());
+            report.outcomes.extend(
+                Self::find_inline_diagrams(&content)
+                    .iter()
+                    .map(|diagram| failed(&file.path, Some(diagram.line))),
+            );
+        }
+        report
+    }
+
+    /// Directory the artifacts of a `.mmd` file are written to. It mirrors
+    /// the source's directory below `<output_dir>/diagrams`, so
+    /// `ch1/flow.mmd` and `ch2/flow.mmd` don't overwrite each other.
+    pub fn artifact_dir(config: &ProjectConfig, source: &Path) -> PathBuf {
+        let current_dir = std::env::current_dir().unwrap_or_default();
+        let relative = source.strip_prefix(&current_dir).unwrap_or(source);
+
+        let mut dir = config.output_dir.join("diagrams");
+        for component in relative.parent().into_iter().flat_map(Path::components) {
+            match component {
+                Component::Normal(part) => dir.push(part),
+                Component::ParentDir => dir.push("_up"),
+                Component::RootDir | Component::Prefix(_) | Component::CurDir => {}
+            }
+        }
+        dir
+    }
+
+    /// Path of the rendered artifact for a `.mmd` source file
+    pub fn artifact_path(config: &ProjectConfig, source: &Path, format: DiagramFormat) -> PathBuf {
+        let file_stem = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Self::artifact_dir(config, source).join(format!("{}.{}", file_stem, format.extension()))
+    }
+
+    /// Path of the rendered artifact for the `index`th (0-based) inline
+    /// block of a Markdown file
+    pub fn inline_artifact_path(
+        config: &ProjectConfig,
+        source: &Path,
+        index: usize,
+        format: DiagramFormat,
+    ) -> PathBuf {
+        let file_stem = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Self::artifact_dir(config, source).join(format!(
+            "{}-diagram-{}.{}",
+            file_stem,
+            index + 1,
+            format.extension()
+        ))
+    }
+
+    /// Render an inline block unless its artifact is newer than the
+    /// Markdown file it is written in
+    pub async fn ensure_inline_rendered(
+        &self,
+        source: &Path,
+        index: usize,
+        code: &str,
+        format: DiagramFormat,
+    ) -> Result<PathBuf> {
+        let artifact = Self::inline_artifact_path(&self.config, source, index, format);
+
+        if Self::is_up_to_date(source, &artifact) {
+            debug!("Diagram up to date: {}", artifact.display());
+            return Ok(artifact);
+        }
+
+        let mermaid = self.mermaid.as_ref().ok_or_else(|| DocPilotError::Build {
+            message: "Native Mermaid renderer not available".to_string(),
+        })?;
+        self.render_inline(mermaid, &artifact, code)
+            .await
+            .map_err(|message| DocPilotError::Build { message })?;
+
+        if !artifact.exists() {
+            return Err(DocPilotError::Build {
+                message: format!("Diagram artifact was not produced: {}", artifact.display()),
+            });
+        }
+
+        Ok(artifact)
+    }
+
+    /// Render `code` next to `artifact`, which names the files to write
+    async fn render_inline(
+        &self,
+        mermaid: &Mermaid,
+        artifact: &Path,
+        code: &str,
+    ) -> std::result::Result<PathBuf, String> {
+        let output_dir = artifact.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(output_dir)
+            .map_err(|e| format!("Failed to create {}: {}", output_dir.display(), e))?;
+        let stem = artifact
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        self.render_to_files(mermaid, code, output_dir, &stem).await
+    }
+
+    /// Whether an artifact exists and is newer than its source
+    pub fn is_up_to_date(source: &Path, artifact: &Path) -> bool {
+        let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        match (modified(source), modified(artifact)) {
+            (Some(source_time), Some(artifact_time)) => artifact_time >= source_time,
+            _ => false,
+        }
+    }
+
+    /// Render a single `.mmd` file unless its artifact is already current
+    pub async fn ensure_rendered(&self, source: &Path, format: DiagramFormat) -> Result<PathBuf> {
+        let artifact = Self::artifact_path(&self.config, source, format);
+
+        if Self::is_up_to_date(source, &artifact) {
+            debug!("Diagram up to date: {}", artifact.display());
+            return Ok(artifact);
+        }
+
+        let mermaid = self.mermaid.as_ref().ok_or_else(|| DocPilotError::Build {
+            message: "Native Mermaid renderer not available".to_string(),
+        })?;
+
+        let output_dir = Self::artifact_dir(&self.config, source);
+        fs::create_dir_all(&output_dir)?;
+        self.process_file(mermaid, source, &output_dir).await?;
+
+        if !artifact.exists() {
+            return Err(DocPilotError::Build {
+                message: format!("Diagram artifact was not produced: {}", artifact.display()),
+            });
+        }
+
+        Ok(artifact)
     }
 
     async fn process_file(
@@ -64,14 +402,35 @@ impl DiagramProcessor {
                 message: format!("Invalid filename: {}", file_path.display()),
             })?;
 
+        self.render_to_files(mermaid, &content, output_dir, file_stem)
+            .await
+            .map_err(|message| DocPilotError::Build {
+                message: format!(
+                    "Failed to render Mermaid diagram {}: {}",
+                    file_path.display(),
+                    message
+                ),
+            })?;
+
+        Ok(())
+    }
+
+    /// Render diagram source to `<stem>.svg` and `<stem>.png`, returning the SVG path.
+    /// Errors carry the renderer's own message.
+    async fn render_to_files(
+        &self,
+        mermaid: &Mermaid,
+        content: &str,
+        output_dir: &Path,
+        file_stem: &str,
+    ) -> std::result::Result<PathBuf, String> {
         // Render to SVG using native mermaid-rs
-        let svg_content = mermaid.render(&content).map_err(|e| DocPilotError::Build {
-            message: format!("Failed to render Mermaid diagram: {}", e),
-        })?;
+        let svg_content = mermaid.render(content).map_err(|e| e.to_string())?;
 
         // Save SVG
         let svg_path = output_dir.join(format!("{}.svg", file_stem));
-        fs::write(&svg_path, &svg_content)?;
+        fs::write(&svg_path, &svg_content)
+            .map_err(|e| format!("Failed to write {}: {}", svg_path.display(), e))?;
         debug!("Generated SVG: {}", svg_path.display());
 
         // Convert SVG to PNG using resvg
@@ -84,52 +443,70 @@ impl DiagramProcessor {
             debug!("Generated PNG: {}/{}.png", output_dir.display(), file_stem);
         }
 
-        Ok(())
+        Ok(svg_path)
     }
 
-    #[allow(dead_code)]
-    pub fn process_inline_mermaid(&self, content: &str) -> Result<String> {
-        if self.mermaid.is_none() {
-            return Ok(content.to_string());
+    /// Find fenced Mermaid blocks together with the line of their opening fence
+    pub fn find_inline_diagrams(content: &str) -> Vec<InlineDiagram> {
+        let mut diagrams = Vec::new();
+        let mut current: Option<InlineDiagram> = None;
+
+        for (index, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+
+            match current.as_mut() {
+                None if trimmed.starts_with("```mermaid") => {
+                    current = Some(InlineDiagram {
+                        line: index + 1,
+                        end_line: index + 1,
+                        code: String::new(),
+                    });
+                }
+                Some(diagram) if trimmed.starts_with("```") => {
+                    diagram.end_line = index + 1;
+                    diagrams.extend(current.take());
+                }
+                Some(diagram) => {
+                    diagram.code.push_str(line);
+                    diagram.code.push('\n');
+                }
+                None => {}
+            }
         }
 
-        let mermaid = self.mermaid.as_ref().unwrap();
-        let mut processed_content = content.to_string();
+        diagrams
+    }
 
-        // Find and replace ```mermaid blocks
-        let mermaid_regex =
-            regex::Regex::new(r"```mermaid\n(.*?)\n```").map_err(|e| DocPilotError::Build {
-                message: format!("Regex error: {}", e),
-            })?;
+    /// Render the inline blocks of one Markdown file to
+    /// `<stem>-diagram-<n>.svg`, reporting each block with its location
+    pub async fn process_inline_mermaid(
+        &self,
+        mermaid: &Mermaid,
+        source: &Path,
+        content: &str,
+    ) -> Vec<DiagramOutcome> {
+        let mut outcomes = Vec::new();
+        for (i, diagram) in Self::find_inline_diagrams(content).iter().enumerate() {
+            let artifact = Self::inline_artifact_path(&self.config, source, i, DiagramFormat::Svg);
+            let result = self.render_inline(mermaid, &artifact, &diagram.code).await;
 
-        for (i, captures) in mermaid_regex.captures_iter(content).enumerate() {
-            if let Some(diagram_code) = captures.get(1) {
-                match mermaid.render(diagram_code.as_str()) {
-                    Ok(svg) => {
-                        // Create inline SVG or save to file and reference
-                        let diagram_filename = format!("inline-diagram-{}.svg", i);
-                        let diagram_path = self
-                            .config
-                            .output_dir
-                            .join("diagrams")
-                            .join(&diagram_filename);
-
-                        if fs::create_dir_all(diagram_path.parent().unwrap()).is_ok()
-                            && fs::write(&diagram_path, &svg).is_ok()
-                        {
-                            let replacement = format!("![Diagram]({})", diagram_path.display());
-                            processed_content =
-                                processed_content.replace(&captures[0], &replacement);
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Failed to render inline Mermaid diagram: {}", e);
-                    }
-                }
+            if let Err(message) = &result {
+                warn!(
+                    "{}:{}: Failed to render inline Mermaid diagram: {}",
+                    source.display(),
+                    diagram.line,
+                    message
+                );
             }
+
+            outcomes.push(DiagramOutcome {
+                source: source.to_path_buf(),
+                line: Some(diagram.line),
+                result,
+            });
         }
 
-        Ok(processed_content)
+        outcomes
     }
 
     async fn convert_svg_to_png(
@@ -171,3 +548,94 @@ impl DiagramProcessor {
         Ok(())
     }
 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_inline_diagrams_reports_fence_lines() {
+        let content = "---\ntitle: Test\n---\n\n# Flow\n\n```mermaid\ngraph TD\n    A --> B\n```\n\n```rust\nfn main() {}\n```\n\n```mermaid\nsequenceDiagram\n```\n";
+
+        let diagrams = DiagramProcessor::find_inline_diagrams(content);
+
+        assert_eq!(diagrams.len(), 2);
+        assert_eq!(diagrams[0].line, 7);
+        assert_eq!(diagrams[0].code, "graph TD\n    A --> B\n");
+        assert_eq!(diagrams[1].line, 16);
+    }
+
+    #[test]
+    fn test_diagram_report_counts_failures() {
+        let report = DiagramReport {
+            outcomes: vec![
+                DiagramOutcome {
+                    source: PathBuf::from("flow.mmd"),
+                    line: None,
+                    result: Ok(PathBuf::from("output/diagrams/flow.svg")),
+                },
+                DiagramOutcome {
+                    source: PathBuf::from("02-design.md"),
+                    line: Some(14),
+                    result: Err("Parse error on line 2".to_string()),
+                },
+            ],
+        };
+
+        assert_eq!(report.failure_count(), 1);
+        let failure = report.failures().next().unwrap();
+        assert_eq!(
+            failure.to_string(),
+            "02-design.md:14: Parse error on line 2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_all_reports_every_diagram_without_renderer() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let processor = DiagramProcessor {
+            config: ProjectConfig {
+                output_dir: temp_dir.path().join("output"),
+                ..Default::default()
+            },
+            mermaid: None,
+        };
+
+        let diagram = temp_dir.path().join("flow.mmd");
+        fs::write(&diagram, "graph TD\n    A --> B").unwrap();
+        let md_path = temp_dir.path().join("01-intro.md");
+        fs::write(&md_path, "# Intro\n\n```mermaid\ngraph LR\n```\n").unwrap();
+        let markdown = crate::discovery::MetadataParser::parse_file(&md_path).unwrap();
+
+        let report = processor
+            .process_all(&[diagram], &[markdown])
+            .await
+            .unwrap();
+
+        assert_eq!(report.failure_count(), 2);
+        assert_eq!(report.outcomes[1].line, Some(3));
+    }
+
+    #[test]
+    fn test_artifact_paths_mirror_source_directories() {
+        let config = ProjectConfig::default();
+        let first = DiagramProcessor::artifact_path(
+            &config,
+            Path::new("chapters/one/flow.mmd"),
+            DiagramFormat::Svg,
+        );
+        let second = DiagramProcessor::artifact_path(
+            &config,
+            Path::new("./chapters/two/flow.mmd"),
+            DiagramFormat::Svg,
+        );
+
+        assert_eq!(
+            first,
+            PathBuf::from("output/diagrams/chapters/one/flow.svg")
+        );
+        assert_eq!(
+            second,
+            PathBuf::from("output/diagrams/chapters/two/flow.svg")
+        );
+    }
+}
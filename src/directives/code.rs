@@ -0,0 +1,284 @@
+//! ```` ```{.rust include="src/lib.rs" region="setup"} ```` — pull a snippet
+//! from a source file into a code block.
+//!
+//! Supported attributes:
+//! - `include`: source file, relative to the Markdown file
+//! - `lines`: line range such as `10-20`, `10-` or `7`
+//! - `region`: named region between `region: <name>` and `endregion`
+//!   comment markers (`//`, `#`, `--`, `/*`, `<!--` comments all work)
+//! - `tab-width`: spaces per tab, defaults to 4
+//! - `caption`: caption text, defaults to the source path
+//!
+//! All other classes and attributes are kept on the generated block. The
+//! block is wrapped in a `::: {.listing}` Div whose first paragraph is the
+//! caption, so every output format shows it.
+
+use super::Attributes;
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+const DIRECTIVE_KEYS: [&str; 5] = ["include", "lines", "region", "tab-width", "caption"];
+
+pub(super) fn render(attributes: &Attributes, base_dir: &Path) -> Result<String, String> {
+    let include = attributes
+        .get("include")
+        .ok_or_else(|| "Code directive needs an include attribute".to_string())?;
+    let path = base_dir.join(include);
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read included file {}: {}", path.display(), e))?;
+    let lines: Vec<&str> = content.lines().collect();
+
+    let mut snippet: Vec<&str> = match attributes.get("lines") {
+        Some(range) => {
+            let (start, end) = parse_line_range(range, lines.len())?;
+            lines[start - 1..end].to_vec()
+        }
+        None => lines,
+    };
+
+    if let Some(region) = attributes.get("region") {
+        snippet = extract_region(&snippet, region)
+            .ok_or_else(|| format!("Region '{}' not found in {}", region, path.display()))?;
+    }
+
+    let tab_width = match attributes.get("tab-width") {
+        Some(width) => width
+            .parse::<usize>()
+            .ok()
+            .filter(|w| *w > 0)
+            .ok_or_else(|| format!("Invalid tab-width: {}", width))?,
+        None => 4,
+    };
+
+    let body = normalize_indentation(&snippet, tab_width);
+    let caption = attributes.get("caption").unwrap_or(include);
+
+    Ok(code_block(attributes, caption, &body))
+}
+
+fn parse_line_range(range: &str, line_count: usize) -> Result<(usize, usize), String> {
+    let invalid = || format!("Invalid line range: {}", range);
+    let parse = |value: &str, default: usize| {
+        let value = value.trim();
+        if value.is_empty() {
+            Ok(default)
+        } else {
+            value.parse::<usize>().map_err(|_| invalid())
+        }
+    };
+
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (parse(start, 1)?, parse(end, line_count)?),
+        None => {
+            let line = parse(range, 0)?;
+            (line, line)
+        }
+    };
+
+    if start == 0 || start > end || end > line_count {
+        return Err(format!(
+            "Line range {} is outside the file ({} lines)",
+            range, line_count
+        ));
+    }
+
+    Ok((start, end))
+}
+
+fn region_marker_regex() -> Regex {
+    Regex::new(r"^\s*(?://|#|--|;|%|/\*|<!--|\(\*)\s*#?(end)?region\b:?\s*([A-Za-z0-9_.-]*)")
+        .unwrap()
+}
+
+/// Lines between `region: <name>` and its matching `endregion`, with any
+/// nested region markers removed
+fn extract_region<'a>(lines: &[&'a str], name: &str) -> Option<Vec<&'a str>> {
+    let marker = region_marker_regex();
+    let start = lines.iter().position(|line| {
+        marker
+            .captures(line)
+            .is_some_and(|cap| cap.get(1).is_none() && &cap[2] == name)
+    })?;
+
+    let mut depth = 0;
+    let mut region = Vec::new();
+
+    for line in &lines[start + 1..] {
+        match marker.captures(line) {
+            Some(cap) if cap.get(1).is_some() => {
+                if depth == 0 {
+                    return Some(region);
+                }
+                depth -= 1;
+            }
+            Some(_) => depth += 1,
+            None => region.push(*line),
+        }
+    }
+
+    // An unterminated region runs to the end of the file
+    Some(region)
+}
+
+/// Expand tabs, strip trailing whitespace and remove common indentation
+fn normalize_indentation(lines: &[&str], tab_width: usize) -> String {
+    let expanded: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            let mut result = String::new();
+            for c in line.chars() {
+                if c == '\t' {
+                    let spaces = tab_width - (result.chars().count() % tab_width);
+                    result.push_str(&" ".repeat(spaces));
+                } else {
+                    result.push(c);
+                }
+            }
+            result.trim_end().to_string()
+        })
+        .collect();
+
+    let indent = expanded
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut body: Vec<&str> = expanded
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or(""))
+        .collect();
+
+    while body.first().is_some_and(|line| line.is_empty()) {
+        body.remove(0);
+    }
+    while body.last().is_some_and(|line| line.is_empty()) {
+        body.pop();
+    }
+
+    body.join("\n")
+}
+
+fn code_block(attributes: &Attributes, caption: &str, body: &str) -> String {
+    let mut info: Vec<String> = attributes
+        .classes
+        .iter()
+        .map(|class| format!(".{}", class))
+        .collect();
+
+    info.extend(
+        attributes
+            .pairs
+            .iter()
+            .filter(|(key, _)| !DIRECTIVE_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| format!("{}=\"{}\"", key, value.replace('"', "'"))),
+    );
+
+    // The fence must be longer than any backtick run inside the snippet
+    let longest_run = body
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    format!(
+        "::: {{.listing}}\n*{}*\n\n{}{{{}}}\n{}\n{}\n:::",
+        escape_markdown(caption),
+        fence,
+        info.join(" "),
+        body,
+        fence
+    )
+}
+
+/// Backslash-escape characters that would turn a caption into markup
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<>#$@~^|".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SOURCE: &str = "fn main() {\n\t// region: setup\n\tlet config = Config::load();\n\t\tconfig.validate();\n\t// endregion\n\trun(config);\n}\n";
+
+    #[test]
+    fn test_render_region_normalizes_indentation() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), SOURCE).unwrap();
+
+        let attributes = Attributes::parse(r#".rust include="main.rs" region="setup""#);
+        let block = render(&attributes, temp_dir.path()).unwrap();
+
+        assert_eq!(
+            block,
+            "::: {.listing}\n*main.rs*\n\n```{.rust}\nlet config = Config::load();\n    config.validate();\n```\n:::"
+        );
+    }
+
+    #[test]
+    fn test_render_line_range() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), SOURCE).unwrap();
+
+        let attributes =
+            Attributes::parse(r#".rust .numberLines include="main.rs" lines="6-7" caption="Run""#);
+        let block = render(&attributes, temp_dir.path()).unwrap();
+
+        assert_eq!(
+            block,
+            "::: {.listing}\n*Run*\n\n```{.rust .numberLines}\n    run(config);\n}\n```\n:::"
+        );
+    }
+
+    #[test]
+    fn test_caption_is_escaped_markdown() {
+        let attributes = Attributes::parse(r#".python include="src/my_module.py""#);
+        let block = code_block(&attributes, "src/my_module.py", "x = 1");
+
+        assert_eq!(
+            block,
+            "::: {.listing}\n*src/my\\_module.py*\n\n```{.python}\nx = 1\n```\n:::"
+        );
+    }
+
+    #[test]
+    fn test_parse_line_range_bounds() {
+        assert_eq!(parse_line_range("3-", 10), Ok((3, 10)));
+        assert_eq!(parse_line_range("-4", 10), Ok((1, 4)));
+        assert_eq!(parse_line_range("7", 10), Ok((7, 7)));
+        assert!(parse_line_range("8-20", 10).is_err());
+        assert!(parse_line_range("a-b", 10).is_err());
+    }
+
+    #[test]
+    fn test_extract_nested_region() {
+        let lines = vec![
+            "# region: outer",
+            "a = 1",
+            "# region: inner",
+            "b = 2",
+            "# endregion",
+            "# endregion",
+            "c = 3",
+        ];
+
+        assert_eq!(
+            extract_region(&lines, "outer"),
+            Some(vec!["a = 1", "b = 2"])
+        );
+        assert_eq!(extract_region(&lines, "missing"), None);
+    }
+}
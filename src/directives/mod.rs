@@ -0,0 +1,306 @@
+//! Fenced-block directives that are expanded before pandoc runs.
+//!
+//! A directive is a fenced code block whose info string is a pandoc
+//! attribute list, e.g. ```` ```{.table source="data/results.csv"} ```` or
+//! ```` ```{.rust include="src/lib.rs" region="setup"} ````.
+
+mod code;
+mod table;
+
+use crate::errors::{DocPilotError, Result};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// Attributes parsed from a `{.class key="value"}` info string
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes {
+    pub classes: Vec<String>,
+    pub pairs: Vec<(String, String)>,
+}
+
+impl Attributes {
+    pub fn parse(info: &str) -> Self {
+        let attr_regex =
+            Regex::new(r#"([.#]?)([A-Za-z0-9_:-]+)(?:=(?:"([^"]*)"|'([^']*)'|([^\s}]+)))?"#)
+                .unwrap();
+        let mut attributes = Attributes::default();
+
+        for cap in attr_regex.captures_iter(info) {
+            let key = cap[2].to_string();
+            let value = cap
+                .get(3)
+                .or_else(|| cap.get(4))
+                .or_else(|| cap.get(5))
+                .map(|m| m.as_str().to_string());
+
+            match (&cap[1], value) {
+                (".", _) => attributes.classes.push(key),
+                ("#", _) => {}
+                (_, Some(value)) => attributes.pairs.push((key, value)),
+                (_, None) => attributes.classes.push(key),
+            }
+        }
+
+        attributes
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| c == class)
+    }
+}
+
+/// A directive block located in a Markdown file
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectiveBlock {
+    /// 1-based line of the opening fence
+    pub line: usize,
+    pub attributes: Attributes,
+    /// Line range of the whole block, fences included (0-based, exclusive end)
+    first_line: usize,
+    end_line: usize,
+}
+
+impl DirectiveBlock {
+    fn kind(&self) -> Option<DirectiveKind> {
+        if self.attributes.get("include").is_some() {
+            Some(DirectiveKind::Code)
+        } else if self.attributes.has_class("table") && self.attributes.get("source").is_some() {
+            Some(DirectiveKind::Table)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why a directive could not be expanded; [`expand`] adds its location
+#[derive(Debug, Clone, PartialEq)]
+enum DirectiveError {
+    /// Reading or converting the referenced data failed
+    Build(String),
+    /// The directive asks for something that can't be produced
+    Config(String),
+}
+
+impl From<String> for DirectiveError {
+    fn from(message: String) -> Self {
+        DirectiveError::Build(message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DirectiveKind {
+    Table,
+    Code,
+}
+
+/// Find fenced blocks with an attribute info string
+pub fn find_blocks(content: &str) -> Vec<DirectiveBlock> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut blocks = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let trimmed = lines[index].trim_start();
+        let fence_len = trimmed.chars().take_while(|c| *c == '`').count();
+
+        if fence_len < 3 {
+            index += 1;
+            continue;
+        }
+
+        let info = trimmed[fence_len..].trim();
+        let closing = lines[index + 1..].iter().position(|line| {
+            let line = line.trim();
+            line.len() >= fence_len && line.chars().all(|c| c == '`')
+        });
+
+        let end_line = closing.map_or(lines.len(), |offset| index + offset + 2);
+
+        if info.starts_with('{') && info.ends_with('}') {
+            blocks.push(DirectiveBlock {
+                line: index + 1,
+                attributes: Attributes::parse(&info[1..info.len() - 1]),
+                first_line: index,
+                end_line,
+            });
+        }
+
+        index = end_line;
+    }
+
+    blocks
+}
+
+/// Files referenced by directives, resolved against `base_dir`
+pub fn referenced_files(content: &str, base_dir: &Path) -> Vec<PathBuf> {
+    find_blocks(content)
+        .iter()
+        .filter_map(|block| match block.kind()? {
+            DirectiveKind::Table => block.attributes.get("source"),
+            DirectiveKind::Code => block.attributes.get("include"),
+        })
+        .map(|source| base_dir.join(source))
+        .collect()
+}
+
+/// Replace every directive block with its generated Markdown.
+/// `source` names the file in error messages.
+pub fn expand(content: &str, base_dir: &Path, source: &Path) -> Result<String> {
+    let blocks: Vec<DirectiveBlock> = find_blocks(content)
+        .into_iter()
+        .filter(|block| block.kind().is_some())
+        .collect();
+
+    if blocks.is_empty() {
+        return Ok(content.to_string());
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut output = Vec::new();
+    let mut cursor = 0;
+
+    for block in &blocks {
+        output.extend(
+            lines[cursor..block.first_line]
+                .iter()
+                .map(|l| l.to_string()),
+        );
+
+        let generated = match block.kind() {
+            Some(DirectiveKind::Table) => table::render(&block.attributes, base_dir),
+            Some(DirectiveKind::Code) => {
+                code::render(&block.attributes, base_dir).map_err(DirectiveError::from)
+            }
+            None => unreachable!("non-directive blocks are filtered out"),
+        }
+        .map_err(|error| {
+            let location = format!("{}:{}", source.display(), block.line);
+            match error {
+                DirectiveError::Build(message) => DocPilotError::Build {
+                    message: format!("{}: {}", location, message),
+                },
+                DirectiveError::Config(message) => DocPilotError::Config {
+                    message: format!("{}: {}", location, message),
+                },
+            }
+        })?;
+
+        output.push(generated);
+        cursor = block.end_line;
+    }
+
+    output.extend(lines[cursor..].iter().map(|l| l.to_string()));
+
+    let mut expanded = output.join("\n");
+    if content.ends_with('\n') {
+        expanded.push('\n');
+    }
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_attributes_parse() {
+        let attributes = Attributes::parse(
+            r#".table #results source="data/results.csv" sort=-score style='grid'"#,
+        );
+
+        assert!(attributes.has_class("table"));
+        assert_eq!(attributes.get("source"), Some("data/results.csv"));
+        assert_eq!(attributes.get("sort"), Some("-score"));
+        assert_eq!(attributes.get("style"), Some("grid"));
+    }
+
+    #[test]
+    fn test_find_blocks_skips_plain_code() {
+        let content = "```rust\nlet x = 1;\n```\n\n```{.table source=\"a.csv\"}\n```\n";
+
+        let blocks = find_blocks(content);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].line, 5);
+    }
+
+    #[test]
+    fn test_expand_table_directive() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("data")).unwrap();
+        fs::write(
+            temp_dir.path().join("data/results.csv"),
+            "name,score\nalpha,1.5\nbeta,12.34\n",
+        )
+        .unwrap();
+
+        let content = "# Results\n\n```{.table source=\"data/results.csv\" sort=\"-score\" decimals=\"1\"}\n```\n\nDone.\n";
+        let expanded = expand(content, temp_dir.path(), Path::new("02-results.md")).unwrap();
+
+        assert_eq!(
+            expanded,
+            "# Results\n\n| name  | score |\n|-------|------:|\n| beta  |  12.3 |\n| alpha |   1.5 |\n\nDone.\n"
+        );
+        assert_eq!(
+            referenced_files(content, temp_dir.path()),
+            vec![temp_dir.path().join("data/results.csv")]
+        );
+    }
+
+    #[test]
+    fn test_expand_code_include() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "pub fn answer() -> u32 {\n    42\n}\n",
+        )
+        .unwrap();
+
+        let content = "Example:\n\n```{.rust include=\"lib.rs\" lines=\"2\"}\n```\n";
+        let expanded = expand(content, temp_dir.path(), Path::new("04-api.md")).unwrap();
+
+        assert_eq!(
+            expanded,
+            "Example:\n\n::: {.listing}\n*lib.rs*\n\n```{.rust}\n42\n```\n:::\n"
+        );
+        assert_eq!(
+            referenced_files(content, temp_dir.path()),
+            vec![temp_dir.path().join("lib.rs")]
+        );
+    }
+
+    #[test]
+    fn test_expand_reports_location() {
+        let temp_dir = TempDir::new().unwrap();
+        let content = "Intro\n\n```{.table source=\"missing.csv\"}\n```\n";
+
+        let error = expand(content, temp_dir.path(), Path::new("03-data.md")).unwrap_err();
+        assert!(error.to_string().contains("03-data.md:3"));
+    }
+
+    #[test]
+    fn test_expand_rejects_table_without_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("empty.json"), "[]").unwrap();
+        fs::write(temp_dir.path().join("data.csv"), "name,score\nalpha,1\n").unwrap();
+
+        let content = "Intro\n\n```{.table source=\"empty.json\"}\n```\n";
+        let error = expand(content, temp_dir.path(), Path::new("03-data.md")).unwrap_err();
+        assert!(matches!(error, DocPilotError::Config { .. }));
+        assert!(error.to_string().contains("03-data.md:3"));
+        assert!(error.to_string().contains("empty.json has no columns"));
+
+        let content = "```{.table source=\"data.csv\" columns=\",\"}\n```\n";
+        let error = expand(content, temp_dir.path(), Path::new("03-data.md")).unwrap_err();
+        assert!(matches!(error, DocPilotError::Config { .. }));
+        assert!(error.to_string().contains("03-data.md:1"));
+    }
+}
@@ -0,0 +1,515 @@
+//! `{.table source="..."}` — render CSV, TSV, JSON or spreadsheet data as a
+//! Markdown table.
+//!
+//! Supported attributes:
+//! - `source`: data file, relative to the Markdown file
+//! - `sheet`: worksheet name for spreadsheets (defaults to the first sheet)
+//! - `columns`: comma-separated list of columns to keep, in output order
+//! - `sort`: column to sort by, prefixed with `-` for descending order
+//! - `decimals`: digits after the decimal point, either for every numeric
+//!   column (`2`) or per column (`score=1,ratio=3`)
+//! - `rename`: header renames as `old=New` pairs, comma-separated
+//! - `style`: `pipe` (default) or `grid`
+//! - `caption`: table caption
+
+use super::{Attributes, DirectiveError};
+use calamine::{open_workbook_auto, Data, Reader};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+
+type CellResult<T> = std::result::Result<T, String>;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+pub(super) fn render(
+    attributes: &Attributes,
+    base_dir: &Path,
+) -> std::result::Result<String, DirectiveError> {
+    let source = attributes
+        .get("source")
+        .ok_or_else(|| "Table directive needs a source attribute".to_string())?;
+    let path = base_dir.join(source);
+
+    if !path.exists() {
+        return Err(format!("Table source not found: {}", path.display()).into());
+    }
+
+    let mut table = load(&path, attributes.get("sheet"))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    if let Some(columns) = attributes.get("columns") {
+        table = select_columns(table, &split_list(columns))?;
+    }
+
+    // Pandoc can't parse a pipe or grid table without columns
+    if table.headers.is_empty() {
+        return Err(DirectiveError::Config(format!(
+            "Table source {} has no columns{}",
+            path.display(),
+            if attributes.get("columns").is_some() {
+                " after applying columns="
+            } else {
+                ""
+            }
+        )));
+    }
+
+    if let Some(sort) = attributes.get("sort") {
+        sort_rows(&mut table, sort)?;
+    }
+
+    if let Some(decimals) = attributes.get("decimals") {
+        format_numbers(&mut table, decimals)?;
+    }
+
+    if let Some(rename) = attributes.get("rename") {
+        rename_headers(&mut table, rename)?;
+    }
+
+    let mut markdown = match attributes.get("style").unwrap_or("pipe") {
+        "pipe" => pipe_table(&table),
+        "grid" => grid_table(&table),
+        other => return Err(format!("Unknown table style: {}", other).into()),
+    };
+
+    if let Some(caption) = attributes.get("caption") {
+        markdown.push_str(&format!("\n\nTable: {}", caption));
+    }
+
+    Ok(markdown)
+}
+
+fn load(path: &Path, sheet: Option<&str>) -> CellResult<Table> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "csv" => load_delimited(path, b','),
+        "tsv" | "tab" => load_delimited(path, b'\t'),
+        "json" => load_json(path),
+        "xlsx" | "xlsm" | "xls" | "ods" => load_spreadsheet(path, sheet),
+        _ => Err(format!("Unsupported table format: .{}", extension)),
+    }
+}
+
+fn load_delimited(path: &Path, delimiter: u8) -> CellResult<Table> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| e.to_string())?;
+
+    let headers = reader
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect::<Vec<_>>();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        rows.push(record.iter().map(|c| c.trim().to_string()).collect());
+    }
+
+    Ok(normalize(Table { headers, rows }))
+}
+
+fn load_json(path: &Path) -> CellResult<Table> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+
+    let items = value
+        .as_array()
+        .ok_or_else(|| "Expected a JSON array of objects or arrays".to_string())?;
+
+    let cell = |value: &serde_json::Value| match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    };
+
+    match items.first() {
+        None => Ok(Table {
+            headers: Vec::new(),
+            rows: Vec::new(),
+        }),
+        Some(serde_json::Value::Object(_)) => {
+            let mut headers: Vec<String> = Vec::new();
+            for item in items {
+                for key in item.as_object().into_iter().flat_map(|o| o.keys()) {
+                    if !headers.contains(key) {
+                        headers.push(key.clone());
+                    }
+                }
+            }
+
+            let rows = items
+                .iter()
+                .map(|item| {
+                    headers
+                        .iter()
+                        .map(|h| item.get(h).map(cell).unwrap_or_default())
+                        .collect()
+                })
+                .collect();
+
+            Ok(Table { headers, rows })
+        }
+        Some(serde_json::Value::Array(_)) => {
+            let mut rows = items
+                .iter()
+                .map(|item| {
+                    item.as_array()
+                        .map(|row| row.iter().map(cell).collect())
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<Vec<String>>>();
+            let headers = rows.remove(0);
+
+            Ok(normalize(Table { headers, rows }))
+        }
+        Some(_) => Err("Expected a JSON array of objects or arrays".to_string()),
+    }
+}
+
+fn load_spreadsheet(path: &Path, sheet: Option<&str>) -> CellResult<Table> {
+    let mut workbook = open_workbook_auto(path).map_err(|e| e.to_string())?;
+
+    let sheet_name = match sheet {
+        Some(name) => name.to_string(),
+        None => workbook
+            .sheet_names()
+            .first()
+            .cloned()
+            .ok_or_else(|| "Workbook has no sheets".to_string())?,
+    };
+
+    let range = workbook
+        .worksheet_range(&sheet_name)
+        .map_err(|e| format!("sheet '{}': {}", sheet_name, e))?;
+
+    let mut rows = range
+        .rows()
+        .map(|row| {
+            row.iter()
+                .map(|cell| match cell {
+                    Data::Empty => String::new(),
+                    other => other.to_string(),
+                })
+                .collect()
+        })
+        .collect::<Vec<Vec<String>>>();
+
+    if rows.is_empty() {
+        return Err(format!("Sheet '{}' is empty", sheet_name));
+    }
+    let headers = rows.remove(0);
+
+    Ok(normalize(Table { headers, rows }))
+}
+
+/// Pad or truncate rows to the header width
+fn normalize(mut table: Table) -> Table {
+    let width = table.headers.len();
+    for row in &mut table.rows {
+        row.resize(width, String::new());
+    }
+    table
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn column_index(table: &Table, column: &str) -> CellResult<usize> {
+    table
+        .headers
+        .iter()
+        .position(|h| h == column)
+        .ok_or_else(|| {
+            format!(
+                "Unknown column '{}' (available: {})",
+                column,
+                table.headers.join(", ")
+            )
+        })
+}
+
+fn select_columns(table: Table, columns: &[String]) -> CellResult<Table> {
+    let indices = columns
+        .iter()
+        .map(|c| column_index(&table, c))
+        .collect::<CellResult<Vec<_>>>()?;
+
+    Ok(Table {
+        headers: columns.to_vec(),
+        rows: table
+            .rows
+            .iter()
+            .map(|row| indices.iter().map(|&i| row[i].clone()).collect())
+            .collect(),
+    })
+}
+
+fn sort_rows(table: &mut Table, sort: &str) -> CellResult<()> {
+    let (column, descending) = match sort.strip_prefix('-') {
+        Some(column) => (column, true),
+        None => (sort.trim_start_matches('+'), false),
+    };
+    let index = column_index(table, column)?;
+
+    table.rows.sort_by(|a, b| {
+        let ordering = match (parse_number(&a[index]), parse_number(&b[index])) {
+            (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            _ => natord::compare(&a[index], &b[index]),
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    Ok(())
+}
+
+fn format_numbers(table: &mut Table, decimals: &str) -> CellResult<()> {
+    let parse_digits = |value: &str| {
+        value
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid decimals value: {}", value))
+    };
+
+    let targets: Vec<(usize, usize)> = if decimals.contains('=') {
+        split_list(decimals)
+            .iter()
+            .map(|pair| {
+                let (column, digits) = pair
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid decimals entry: {}", pair))?;
+                Ok((column_index(table, column.trim())?, parse_digits(digits)?))
+            })
+            .collect::<CellResult<_>>()?
+    } else {
+        let digits = parse_digits(decimals)?;
+        (0..table.headers.len()).map(|i| (i, digits)).collect()
+    };
+
+    for row in &mut table.rows {
+        for &(index, digits) in &targets {
+            if let Some(number) = parse_number(&row[index]) {
+                row[index] = format!("{:.*}", digits, number);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn rename_headers(table: &mut Table, rename: &str) -> CellResult<()> {
+    for pair in split_list(rename) {
+        let (from, to) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid rename entry: {}", pair))?;
+        let index = column_index(table, from.trim())?;
+        table.headers[index] = to.trim().to_string();
+    }
+    Ok(())
+}
+
+fn parse_number(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// Columns where every non-empty cell is numeric are right-aligned
+fn numeric_columns(table: &Table) -> Vec<bool> {
+    (0..table.headers.len())
+        .map(|i| {
+            let mut cells = table
+                .rows
+                .iter()
+                .map(|r| r[i].as_str())
+                .filter(|c| !c.is_empty());
+            let mut any = false;
+            let all_numeric = cells.all(|c| {
+                any = true;
+                parse_number(c).is_some()
+            });
+            any && all_numeric
+        })
+        .collect()
+}
+
+fn clean_cell(cell: &str) -> String {
+    cell.replace(['\r', '\n'], " ")
+}
+
+fn column_widths(table: &Table, escape: fn(&str) -> String) -> Vec<usize> {
+    (0..table.headers.len())
+        .map(|i| {
+            std::iter::once(&table.headers[i])
+                .chain(table.rows.iter().map(|r| &r[i]))
+                .map(|c| escape(c).chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect()
+}
+
+fn pad(cell: &str, width: usize, right: bool) -> String {
+    if right {
+        format!("{:>width$}", cell, width = width)
+    } else {
+        format!("{:<width$}", cell, width = width)
+    }
+}
+
+fn pipe_escape(cell: &str) -> String {
+    clean_cell(cell).replace('|', "\\|")
+}
+
+fn pipe_table(table: &Table) -> String {
+    let widths = column_widths(table, pipe_escape);
+    let numeric = numeric_columns(table);
+
+    let row_line = |cells: &[String]| {
+        let cells = cells
+            .iter()
+            .enumerate()
+            .map(|(i, c)| pad(&pipe_escape(c), widths[i], numeric[i]))
+            .collect::<Vec<_>>();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let separator = widths
+        .iter()
+        .zip(&numeric)
+        .map(|(&w, &right)| {
+            if right {
+                format!("{}:", "-".repeat(w + 1))
+            } else {
+                "-".repeat(w + 2)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![
+        row_line(&table.headers),
+        format!("|{}|", separator.join("|")),
+    ];
+    lines.extend(table.rows.iter().map(|r| row_line(r)));
+    lines.join("\n")
+}
+
+fn grid_table(table: &Table) -> String {
+    let widths = column_widths(table, clean_cell);
+    let numeric = numeric_columns(table);
+
+    let border = |fill: &str, align: bool| {
+        let parts = widths
+            .iter()
+            .zip(&numeric)
+            .map(|(&w, &right)| {
+                if align && right {
+                    format!("{}:", fill.repeat(w + 1))
+                } else {
+                    fill.repeat(w + 2)
+                }
+            })
+            .collect::<Vec<_>>();
+        format!("+{}+", parts.join("+"))
+    };
+
+    let row_line = |cells: &[String]| {
+        let cells = cells
+            .iter()
+            .enumerate()
+            .map(|(i, c)| pad(&clean_cell(c), widths[i], numeric[i]))
+            .collect::<Vec<_>>();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![
+        border("-", false),
+        row_line(&table.headers),
+        border("=", true),
+    ];
+    for row in &table.rows {
+        lines.push(row_line(row));
+        lines.push(border("-", false));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample() -> Table {
+        Table {
+            headers: vec!["name".to_string(), "score".to_string()],
+            rows: vec![
+                vec!["beta".to_string(), "7".to_string()],
+                vec!["alpha".to_string(), "12.5".to_string()],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_grid_table() {
+        let table = sample();
+
+        assert_eq!(
+            grid_table(&table),
+            "+-------+-------+\n| name  | score |\n+=======+======:+\n| beta  |     7 |\n+-------+-------+\n| alpha |  12.5 |\n+-------+-------+"
+        );
+    }
+
+    #[test]
+    fn test_select_and_rename() {
+        let mut table = select_columns(sample(), &["score".to_string()]).unwrap();
+        rename_headers(&mut table, "score=Score (%)").unwrap();
+
+        assert_eq!(table.headers, vec!["Score (%)"]);
+        assert_eq!(table.rows, vec![vec!["7"], vec!["12.5"]]);
+        assert!(select_columns(sample(), &["missing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_load_json_objects_keeps_key_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.json");
+        fs::write(&path, r#"[{"zeta": 1, "alpha": "x"}, {"zeta": 2}]"#).unwrap();
+
+        let table = load(&path, None).unwrap();
+        assert_eq!(table.headers, vec!["zeta", "alpha"]);
+        assert_eq!(table.rows[1], vec!["2", ""]);
+    }
+
+    #[test]
+    fn test_load_tsv() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.tsv");
+        fs::write(&path, "a\tb\n1\tx|y\n").unwrap();
+
+        let table = load(&path, None).unwrap();
+        assert_eq!(
+            pipe_table(&table),
+            "|   a | b    |\n|----:|------|\n|   1 | x\\|y |"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::config::{DiscoveredFiles, DocumentMetadata, MarkdownFile, ProjectConfig};
+use crate::directives;
 use crate::errors::{DocPilotError, Result};
 use regex::Regex;
 use std::fs;
@@ -140,7 +141,7 @@ impl FileDiscovery {
         Ok(files)
     }
 
-    fn should_exclude(&self, path: &Path) -> bool {
+    pub fn should_exclude(&self, path: &Path) -> bool {
         let filename = path.file_name().unwrap().to_string_lossy();
 
         // Check custom exclusions
@@ -200,9 +201,10 @@ impl MetadataParser {
         let image_regex = Regex::new(r"!\[.*?\]\(([^)]+)\)").unwrap();
         let link_regex = Regex::new(r"\[.*?\]\(([^)]+)\)").unwrap();
 
+        // Image targets include `.mmd` diagrams, which are rendered at build time
         for cap in image_regex.captures_iter(content) {
             if let Some(url) = cap.get(1) {
-                let url_str = url.as_str();
+                let url_str = url.as_str().split_whitespace().next().unwrap_or_default();
                 if !url_str.starts_with("http") && !url_str.starts_with("mailto:") {
                     if let Some(base) = base_dir {
                         let path = base.join(url_str);
@@ -216,8 +218,19 @@ impl MetadataParser {
 
         for cap in link_regex.captures_iter(content) {
             if let Some(url) = cap.get(1) {
-                let url_str = url.as_str();
-                if !url_str.starts_with("http") && !url_str.starts_with("mailto:") {
+                // `chapter.md#section` still depends on `chapter.md`
+                let url_str = url
+                    .as_str()
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .split('#')
+                    .next()
+                    .unwrap_or_default();
+                if !url_str.is_empty()
+                    && !url_str.starts_with("http")
+                    && !url_str.starts_with("mailto:")
+                {
                     if let Some(base) = base_dir {
                         let path = base.join(url_str);
                         if path.exists() && path.extension().is_some_and(|ext| ext == "md") {
@@ -228,6 +241,15 @@ impl MetadataParser {
             }
         }
 
+        // Data files and other sources pulled in by fenced directives
+        if let Some(base) = base_dir {
+            dependencies.extend(
+                directives::referenced_files(content, base)
+                    .into_iter()
+                    .filter(|path| path.exists()),
+            );
+        }
+
         Ok(dependencies)
     }
 
@@ -330,6 +352,9 @@ impl MetadataParser {
         if target.sansfont.is_none() {
             target.sansfont = source.sansfont.clone();
         }
+        if target.monofont.is_none() {
+            target.monofont = source.monofont.clone();
+        }
     }
 }
 #[cfg(test)]
@@ -367,6 +392,7 @@ mod tests {
             templates_dir: temp_dir.path().join("templates"),
             exclude_files: vec![],
             images_dir: temp_dir.path().join("images"),
+            ..Default::default()
         };
 
         let _guard = DirectoryGuard::new(temp_dir.path()).unwrap();
@@ -391,6 +417,7 @@ mod tests {
             templates_dir: temp_dir.path().join("templates"),
             exclude_files: vec![],
             images_dir: temp_dir.path().join("images"),
+            ..Default::default()
         };
 
         // Change to temp directory for discovery
@@ -417,6 +444,7 @@ mod tests {
             templates_dir: temp_dir.path().join("templates"),
             exclude_files: vec![],
             images_dir: temp_dir.path().join("images"),
+            ..Default::default()
         };
 
         let _guard = DirectoryGuard::new(temp_dir.path()).unwrap();
@@ -440,6 +468,22 @@ mod tests {
         assert_eq!(metadata.numbersections, Some(true));
     }
 
+    #[test]
+    fn test_extract_dependencies_includes_mermaid_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("diagrams")).unwrap();
+        fs::write(temp_dir.path().join("diagrams/deploy.mmd"), "graph TD").unwrap();
+
+        let content = "![Deployment](diagrams/deploy.mmd \"Deployment\")";
+        let dependencies =
+            MetadataParser::extract_dependencies(content, Some(temp_dir.path())).unwrap();
+
+        assert_eq!(
+            dependencies,
+            vec![temp_dir.path().join("diagrams/deploy.mmd")]
+        );
+    }
+
     #[test]
     fn test_metadata_parser_with_frontmatter() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,244 @@
+//! Shell commands from the `hooks` section of docpilot.yml, run around each
+//! format build.
+
+use crate::config::{HookSettings, ProjectConfig};
+use crate::errors::{DocPilotError, Result};
+use std::future::Future;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PreBuild,
+    PostBuild,
+    OnSuccess,
+    OnFailure,
+}
+
+impl Hook {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hook::PreBuild => "pre_build",
+            Hook::PostBuild => "post_build",
+            Hook::OnSuccess => "on_success",
+            Hook::OnFailure => "on_failure",
+        }
+    }
+
+    fn command<'a>(&self, settings: &'a HookSettings) -> Option<&'a str> {
+        match self {
+            Hook::PreBuild => settings.pre_build.as_deref(),
+            Hook::PostBuild => settings.post_build.as_deref(),
+            Hook::OnSuccess => settings.on_success.as_deref(),
+            Hook::OnFailure => settings.on_failure.as_deref(),
+        }
+        .filter(|command| !command.trim().is_empty())
+    }
+}
+
+/// What a hook is told about the build through `DOCPILOT_*` variables
+#[derive(Debug, Clone)]
+pub struct HookContext<'a> {
+    pub project: &'a str,
+    pub format: &'a str,
+    pub output: &'a Path,
+    /// Set once the build has finished
+    pub duration: Option<Duration>,
+    pub error: Option<String>,
+}
+
+impl HookContext<'_> {
+    fn variables(&self, hook: Hook) -> Vec<(&'static str, String)> {
+        let mut variables = vec![
+            ("DOCPILOT_HOOK", hook.name().to_string()),
+            ("DOCPILOT_PROJECT", self.project.to_string()),
+            ("DOCPILOT_FORMAT", self.format.to_string()),
+            ("DOCPILOT_OUTPUT", self.output.display().to_string()),
+        ];
+
+        if let Some(duration) = self.duration {
+            let status = if self.error.is_some() {
+                "failure"
+            } else {
+                "success"
+            };
+            variables.push(("DOCPILOT_STATUS", status.to_string()));
+            variables.push(("DOCPILOT_DURATION_MS", duration.as_millis().to_string()));
+        }
+        if let Some(error) = &self.error {
+            variables.push(("DOCPILOT_ERROR", error.clone()));
+        }
+
+        variables
+    }
+}
+
+/// Run `hook` if it is configured. Its output goes straight to the terminal.
+pub async fn run(settings: &HookSettings, hook: Hook, context: &HookContext<'_>) -> Result<()> {
+    let Some(command) = hook.command(settings) else {
+        return Ok(());
+    };
+
+    info!(
+        "🪝 Running {} hook for {}: {}",
+        hook.name(),
+        context.format,
+        command
+    );
+
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    let status = shell
+        .arg(command)
+        .envs(context.variables(hook))
+        .kill_on_drop(true)
+        .status()
+        .await
+        .map_err(|e| DocPilotError::Build {
+            message: format!("Failed to run {} hook: {}", hook.name(), e),
+        })?;
+
+    if !status.success() {
+        return Err(DocPilotError::Build {
+            message: format!("{} hook failed ({}): {}", hook.name(), status, command),
+        });
+    }
+
+    Ok(())
+}
+
+/// Run `build` for `format` wrapped in the configured hooks: `pre_build`,
+/// the build, `post_build`, then `on_success` or `on_failure`.
+///
+/// A failing `pre_build` skips the build and a failing `post_build` fails
+/// it. `on_success` and `on_failure` only warn when they fail.
+pub async fn build_with_hooks<F>(
+    config: &ProjectConfig,
+    format: &str,
+    output: &Path,
+    build: F,
+) -> Result<()>
+where
+    F: Future<Output = Result<()>>,
+{
+    let hooks = &config.hooks;
+    let mut context = HookContext {
+        project: &config.name,
+        format,
+        output,
+        duration: None,
+        error: None,
+    };
+
+    let started = Instant::now();
+    let mut result = match run(hooks, Hook::PreBuild, &context).await {
+        Ok(()) => build.await,
+        Err(e) => Err(e),
+    };
+
+    context.duration = Some(started.elapsed());
+    context.error = result.as_ref().err().map(|e| e.to_string());
+    if let Err(e) = run(hooks, Hook::PostBuild, &context).await {
+        context.error.get_or_insert_with(|| e.to_string());
+        if result.is_ok() {
+            result = Err(e);
+        }
+    }
+
+    let hook = if result.is_ok() {
+        Hook::OnSuccess
+    } else {
+        Hook::OnFailure
+    };
+    if let Err(e) = run(hooks, hook, &context).await {
+        warn!("{}", e);
+    }
+
+    result
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn config(temp_dir: &TempDir, hooks: HookSettings) -> ProjectConfig {
+        let log = temp_dir.path().join("hooks.log");
+        let append = |line: &str| Some(format!("echo \"{}\" >> '{}'", line, log.display()));
+        ProjectConfig {
+            name: "manual".to_string(),
+            hooks: HookSettings {
+                pre_build: hooks
+                    .pre_build
+                    .or_else(|| append("pre $DOCPILOT_FORMAT $DOCPILOT_OUTPUT")),
+                post_build: append("post $DOCPILOT_STATUS"),
+                on_success: append("success $DOCPILOT_PROJECT"),
+                on_failure: append("failure $DOCPILOT_ERROR"),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn log(temp_dir: &TempDir) -> String {
+        fs::read_to_string(temp_dir.path().join("hooks.log")).unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_around_build() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config(&temp_dir, HookSettings::default());
+        let output = PathBuf::from("output/manual.pdf");
+
+        build_with_hooks(&config, "pdf", &output, async { Ok(()) })
+            .await
+            .unwrap();
+        assert_eq!(
+            log(&temp_dir),
+            "pre pdf output/manual.pdf\npost success\nsuccess manual\n"
+        );
+
+        fs::remove_file(temp_dir.path().join("hooks.log")).unwrap();
+        let failed = build_with_hooks(&config, "pdf", &output, async {
+            Err(DocPilotError::Build {
+                message: "Pandoc failed".to_string(),
+            })
+        })
+        .await;
+        assert!(failed.is_err());
+        assert_eq!(
+            log(&temp_dir),
+            "pre pdf output/manual.pdf\npost failure\nfailure Build error: Pandoc failed\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failing_pre_build_skips_build() {
+        let temp_dir = TempDir::new().unwrap();
+        let hooks = HookSettings {
+            pre_build: Some("exit 3".to_string()),
+            ..Default::default()
+        };
+        let config = config(&temp_dir, hooks);
+
+        let result = build_with_hooks(&config, "html", Path::new("out.html"), async {
+            panic!("build must not run")
+        })
+        .await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("pre_build hook failed"));
+        assert!(log(&temp_dir).starts_with("post failure\nfailure "));
+    }
+}
@@ -0,0 +1,389 @@
+//! mdBook import: chapters in `SUMMARY.md` order, nested chapters folded
+//! into their parent's file with their headings shifted down.
+
+use super::{heading, setup_file, strip_attributes, Chapter, MediaCollector};
+use crate::errors::{DocPilotError, Result};
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// A chapter link from `SUMMARY.md`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryEntry {
+    pub title: String,
+    /// Relative to the source directory
+    pub path: PathBuf,
+    /// 0 for top-level chapters
+    pub depth: usize,
+}
+
+pub struct Book {
+    summary: PathBuf,
+    source_dir: PathBuf,
+    entries: Vec<SummaryEntry>,
+}
+
+impl Book {
+    pub fn load(summary: &Path) -> Result<Self> {
+        let content = fs::read_to_string(summary).map_err(|e| DocPilotError::Config {
+            message: format!("Cannot read {}: {}", summary.display(), e),
+        })?;
+        let source_dir = summary
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+
+        Ok(Self {
+            summary: summary.to_path_buf(),
+            source_dir,
+            entries: parse_summary(&content),
+        })
+    }
+
+    pub fn source_dir(&self) -> &Path {
+        &self.source_dir
+    }
+
+    /// One chapter per top-level entry, nested entries appended to their parent
+    pub fn chapters(&self, media: &mut MediaCollector) -> Result<Vec<Chapter>> {
+        let mut sources = Vec::new();
+        for entry in &self.entries {
+            let path = self.source_dir.join(&entry.path);
+            let content = fs::read_to_string(&path).map_err(|e| DocPilotError::Config {
+                message: format!(
+                    "Chapter '{}' listed in {} cannot be read: {}",
+                    entry.title,
+                    self.summary.display(),
+                    e
+                ),
+            })?;
+            if content.contains("{{#") {
+                warn!(
+                    "{} uses mdBook preprocessor syntax ({{{{#...}}}}), which is kept as text",
+                    entry.path.display()
+                );
+            }
+            sources.push(content);
+        }
+
+        // Links between chapter files become links to the chapter's first heading
+        let anchors: BTreeMap<PathBuf, String> = self
+            .entries
+            .iter()
+            .zip(&sources)
+            .filter_map(|(entry, content)| {
+                first_heading_id(content).map(|id| (normalize(&entry.path), id))
+            })
+            .collect();
+
+        let mut chapters: Vec<Chapter> = Vec::new();
+        for (entry, content) in self.entries.iter().zip(&sources) {
+            let chapter_dir = entry.path.parent().unwrap_or(Path::new(""));
+            let content = shift_headings(content, entry.depth);
+            let content = rewrite_chapter_links(&content, chapter_dir, &anchors);
+            let content = media.rewrite_from(&content, &self.source_dir.join(chapter_dir));
+            debug!("Imported chapter {}", entry.path.display());
+
+            match chapters.last_mut() {
+                Some(parent) if entry.depth > 0 => {
+                    parent.content = format!("{}\n\n{}", parent.content.trim_end(), content.trim());
+                }
+                _ => chapters.push(Chapter {
+                    title: entry.title.clone(),
+                    content: content.trim().to_string(),
+                }),
+            }
+        }
+
+        for chapter in &mut chapters {
+            chapter.content.push('\n');
+        }
+        Ok(chapters)
+    }
+
+    /// Metadata from `book.toml` next to the source directory
+    pub fn setup_file(&self) -> Result<String> {
+        let book_toml = self
+            .source_dir
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("book.toml");
+        let settings = fs::read_to_string(&book_toml)
+            .map(|content| book_settings(&content))
+            .unwrap_or_default();
+
+        let mut metadata = serde_yaml::Mapping::new();
+        if let Some(title) = settings.get("title") {
+            metadata.insert("title".into(), title.clone().into());
+        }
+        if let Some(description) = settings.get("description") {
+            metadata.insert("subtitle".into(), description.clone().into());
+        }
+        if let Some(authors) = settings.get("authors") {
+            let authors: Vec<serde_yaml::Value> = Regex::new(r#""([^"]*)""#)
+                .unwrap()
+                .captures_iter(authors)
+                .map(|c| c[1].to_string().into())
+                .collect();
+            metadata.insert("author".into(), authors.into());
+        }
+        if let Some(language) = settings.get("language") {
+            metadata.insert("lang".into(), language.clone().into());
+        }
+        metadata.insert("top-level-division".into(), "chapter".into());
+
+        let fallback_title = self
+            .source_dir
+            .canonicalize()
+            .ok()
+            .and_then(|dir| {
+                dir.parent()
+                    .and_then(|p| p.file_name())
+                    .map(|n| n.to_owned())
+            })
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Book".to_string());
+
+        setup_file(Some(&serde_yaml::to_string(&metadata)?), &fallback_title)
+    }
+}
+
+/// Chapter links of `SUMMARY.md`; part titles, separators and draft
+/// chapters (empty links) are skipped
+pub fn parse_summary(summary: &str) -> Vec<SummaryEntry> {
+    let link = Regex::new(r"^(\s*)(?:[-*+]\s+)?\[([^\]]+)\]\(([^)]*)\)").unwrap();
+    let mut entries = Vec::new();
+    let mut indents: Vec<usize> = Vec::new();
+
+    for line in summary.lines() {
+        let Some(captures) = link.captures(line) else {
+            continue;
+        };
+        let indent = captures[1].replace('\t', "    ").len();
+        let target = captures[3].trim();
+        if target.is_empty() {
+            debug!("Skipping draft chapter: {}", &captures[2]);
+            continue;
+        }
+
+        while indents.last().is_some_and(|last| *last > indent) {
+            indents.pop();
+        }
+        if indents.last() != Some(&indent) {
+            indents.push(indent);
+        }
+
+        entries.push(SummaryEntry {
+            title: captures[2].trim().to_string(),
+            path: PathBuf::from(target),
+            depth: indents.len() - 1,
+        });
+    }
+
+    entries
+}
+
+/// `key = value` pairs of the `[book]` table of `book.toml`
+fn book_settings(toml: &str) -> BTreeMap<String, String> {
+    let mut settings = BTreeMap::new();
+    let mut in_book = false;
+    let pair = Regex::new(r#"^\s*([A-Za-z_-]+)\s*=\s*(.+?)\s*$"#).unwrap();
+
+    for line in toml.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_book = trimmed == "[book]";
+        } else if in_book {
+            if let Some(captures) = pair.captures(line) {
+                let value = captures[2].trim();
+                let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                    Some(unquoted) => unquoted.to_string(),
+                    None => value.to_string(),
+                };
+                settings.insert(captures[1].to_string(), value);
+            }
+        }
+    }
+
+    settings
+}
+
+/// Push every heading `depth` levels down, outside fenced code
+fn shift_headings(markdown: &str, depth: usize) -> String {
+    let mut output = String::new();
+    let mut fence: Option<&str> = None;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        let mut shifted = None;
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+        } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+        } else if let Some((level, _)) = heading(line).filter(|_| depth > 0) {
+            let level = (level + depth).min(6);
+            shifted = Some(format!(
+                "{} {}",
+                "#".repeat(level),
+                line.trim_start_matches('#').trim_start()
+            ));
+        }
+
+        output.push_str(shifted.as_deref().unwrap_or(line));
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Identifier pandoc gives the first heading of a chapter
+fn first_heading_id(markdown: &str) -> Option<String> {
+    let (_, text) = markdown.lines().find_map(heading)?;
+    if let Some(start) = text.rfind("{#") {
+        let id = text[start + 2..]
+            .split(['}', ' '])
+            .next()
+            .unwrap_or_default();
+        if !id.is_empty() {
+            return Some(id.to_string());
+        }
+    }
+
+    let text = strip_attributes(text).replace(['*', '_', '`'], "");
+    let mut id = String::new();
+    for c in text.to_lowercase().chars() {
+        if c.is_alphanumeric() || c == '-' || c == '.' {
+            id.push(c);
+        } else if c.is_whitespace() {
+            id.push('-');
+        }
+    }
+    let id = id
+        .trim_start_matches(|c: char| !c.is_alphabetic())
+        .to_string();
+    Some(if id.is_empty() {
+        "section".to_string()
+    } else {
+        id
+    })
+}
+
+/// Rewrite `[text](other.md#anchor)` links to in-document anchors
+fn rewrite_chapter_links(
+    markdown: &str,
+    chapter_dir: &Path,
+    anchors: &BTreeMap<PathBuf, String>,
+) -> String {
+    let link = Regex::new(r"(\]\()([^)\s#]+\.md)(#[^)\s]*)?\)").unwrap();
+
+    link.replace_all(markdown, |captures: &Captures| {
+        if captures[2].contains("://") {
+            return captures[0].to_string();
+        }
+        if let Some(fragment) = captures.get(3) {
+            return format!("{}{})", &captures[1], fragment.as_str());
+        }
+        match anchors.get(&normalize(&chapter_dir.join(&captures[2]))) {
+            Some(id) => format!("{}#{})", &captures[1], id),
+            None => captures[0].to_string(),
+        }
+    })
+    .into_owned()
+}
+
+/// Resolve `.` and `..` without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_summary() {
+        let summary = "# Summary\n\n[Introduction](README.md)\n\n# User Guide\n\n- [Install](guide/install.md)\n  - [Linux](guide/linux.md)\n    - [Arch](guide/arch.md)\n  - [macOS](guide/macos.md)\n- [Draft]()\n\n---\n\n[Contributors](misc/contributors.md)\n";
+
+        let entries = parse_summary(summary);
+        let outline: Vec<(&str, usize)> = entries
+            .iter()
+            .map(|e| (e.title.as_str(), e.depth))
+            .collect();
+
+        assert_eq!(
+            outline,
+            vec![
+                ("Introduction", 0),
+                ("Install", 0),
+                ("Linux", 1),
+                ("Arch", 2),
+                ("macOS", 1),
+                ("Contributors", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_book() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(src.join("guide/img")).unwrap();
+        fs::write(
+            temp_dir.path().join("book.toml"),
+            "[book]\ntitle = \"Handbook\"\nauthors = [\"Ada\", \"Grace\"]\nlanguage = \"en\"\n\n[output.html]\ntitle = \"ignored\"\n",
+        )
+        .unwrap();
+        fs::write(
+            src.join("SUMMARY.md"),
+            "# Summary\n\n- [Intro](intro.md)\n- [Guide](guide/index.md)\n  - [Setup](guide/setup.md)\n",
+        )
+        .unwrap();
+        fs::write(
+            src.join("intro.md"),
+            "# Welcome\n\nSee [setup](guide/setup.md).\n",
+        )
+        .unwrap();
+        fs::write(src.join("guide/index.md"), "# Guide\n").unwrap();
+        fs::write(
+            src.join("guide/setup.md"),
+            "# Setup Steps\n\n![Screen](img/screen.png)\n\n```sh\n# comment\n```\n\nBack to [intro](../intro.md#welcome).\n",
+        )
+        .unwrap();
+        fs::write(src.join("guide/img/screen.png"), "png").unwrap();
+
+        let book = Book::load(&src.join("SUMMARY.md")).unwrap();
+        let mut media = MediaCollector::new(book.source_dir());
+        let chapters = book.chapters(&mut media).unwrap();
+
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(
+            chapters[0].content,
+            "# Welcome\n\nSee [setup](#setup-steps).\n"
+        );
+        assert_eq!(
+            chapters[1].content,
+            "# Guide\n\n## Setup Steps\n\n![Screen](images/screen.png)\n\n```sh\n# comment\n```\n\nBack to [intro](#welcome).\n"
+        );
+
+        let setup = book.setup_file().unwrap();
+        assert!(setup.contains("title: Handbook"));
+        assert!(setup.contains("author:\n- Ada\n- Grace"));
+        assert!(setup.contains("lang: en"));
+    }
+}
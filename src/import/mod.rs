@@ -0,0 +1,466 @@
+//! `docpilot import`: turn an existing document into numbered chapter files.
+//!
+//! Single documents go through pandoc and are split at a heading level;
+//! mdBook projects are read from their `SUMMARY.md` (see [`mdbook`]).
+
+mod mdbook;
+
+use crate::config::ToolSettings;
+use crate::dependencies::DependencyChecker;
+use crate::errors::{DocPilotError, Result};
+use crate::init::InitPlan;
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+use tracing::{debug, info, warn};
+
+/// Directory imported media is collected in
+const IMAGES_DIR: &str = "images";
+
+/// Input formats pandoc can read, by file extension
+fn pandoc_format(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "md" | "markdown" | "mdown" => Some("markdown"),
+        "docx" => Some("docx"),
+        "odt" => Some("odt"),
+        "html" | "htm" => Some("html"),
+        "tex" | "latex" => Some("latex"),
+        _ => None,
+    }
+}
+
+/// One numbered Markdown file of the imported project
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub title: String,
+    pub content: String,
+}
+
+pub struct Importer {
+    /// Project directory the files are written to
+    target: PathBuf,
+    /// Heading level that starts a new chapter file
+    split_level: usize,
+    force: bool,
+    dry_run: bool,
+    /// Where pandoc lives and which versions the project accepts
+    tools: ToolSettings,
+}
+
+impl Importer {
+    pub fn new(target: PathBuf, split_level: usize) -> Self {
+        Self {
+            target,
+            split_level,
+            force: false,
+            dry_run: false,
+            tools: ToolSettings::default(),
+        }
+    }
+
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_tools(mut self, tools: ToolSettings) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Convert a Markdown, DOCX, ODT, HTML or LaTeX file. Returns the
+    /// number of chapter files.
+    pub fn import_file(&self, source: &Path) -> Result<usize> {
+        let format = pandoc_format(source).ok_or_else(|| DocPilotError::Config {
+            message: format!(
+                "Cannot import {}: expected a .md, .docx, .odt, .html or .tex file",
+                source.display()
+            ),
+        })?;
+        if !source.is_file() {
+            return Err(DocPilotError::Config {
+                message: format!("File not found: {}", source.display()),
+            });
+        }
+        info!("Importing {} ({})", source.display(), format);
+
+        let staging = TempDir::new()?;
+        let media_dir = staging.path().join("media");
+        let markdown = convert(source, format, &media_dir, &self.tools)?;
+
+        let (frontmatter, body) = split_frontmatter(&markdown);
+        let fallback_title = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut plan = InitPlan::new(self.target.clone());
+        let mut media = MediaCollector::new(source.parent().unwrap_or(Path::new(".")));
+        let body = media.rewrite(&body);
+        let chapters = split_chapters(&body, self.split_level);
+
+        plan.add_file("00-setup.md", setup_file(frontmatter, &fallback_title)?);
+        media.plan_into(&mut plan)?;
+        let count = plan_chapters(&mut plan, &chapters);
+
+        self.finish(plan, count)
+    }
+
+    /// Import an mdBook from its `SUMMARY.md`
+    pub fn import_mdbook(&self, summary: &Path) -> Result<usize> {
+        info!("Importing mdBook {}", summary.display());
+
+        let book = mdbook::Book::load(summary)?;
+        let mut plan = InitPlan::new(self.target.clone());
+        let mut media = MediaCollector::new(book.source_dir());
+
+        let chapters = book.chapters(&mut media)?;
+        plan.add_file("00-setup.md", book.setup_file()?);
+        media.plan_into(&mut plan)?;
+        let count = plan_chapters(&mut plan, &chapters);
+
+        self.finish(plan, count)
+    }
+
+    fn finish(&self, plan: InitPlan, count: usize) -> Result<usize> {
+        if self.dry_run {
+            plan.print(self.force);
+        } else {
+            plan.apply(self.force)?;
+        }
+        Ok(count)
+    }
+}
+
+/// Run pandoc, extracting embedded media below `media_dir`
+fn convert(source: &Path, format: &str, media_dir: &Path, tools: &ToolSettings) -> Result<String> {
+    DependencyChecker::require_version(
+        "pandoc",
+        "2.11.2",
+        "Importing (--markdown-headings=atx)",
+        tools,
+    )?;
+
+    let mut command = Command::new(tools.command("pandoc"));
+    command
+        .arg(source)
+        .args(["--from", format, "--to", "markdown", "--standalone"])
+        .args(["--wrap=none", "--markdown-headings=atx"])
+        .arg(format!("--extract-media={}", media_dir.display()));
+    // Relative links in HTML, LaTeX and Markdown sources resolve from their directory
+    if let Some(parent) = source.parent().filter(|p| !p.as_os_str().is_empty()) {
+        command.arg(format!("--resource-path={}", parent.display()));
+    }
+    debug!("Pandoc command: {:?}", command);
+
+    let output = command.output().map_err(|_| DocPilotError::Dependency {
+        tool: "pandoc".to_string(),
+        hint: "pandoc converts the document to Markdown; install it from https://pandoc.org"
+            .to_string(),
+    })?;
+
+    if !output.status.success() {
+        return Err(DocPilotError::Build {
+            message: format!(
+                "Pandoc failed to convert {}: {}",
+                source.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Split a leading `---` YAML block from the body
+fn split_frontmatter(markdown: &str) -> (Option<&str>, String) {
+    if let Some(rest) = markdown.strip_prefix("---\n") {
+        for end in ["\n---\n", "\n...\n"] {
+            if let Some(index) = rest.find(end) {
+                return (Some(&rest[..index]), rest[index + end.len()..].to_string());
+            }
+        }
+    }
+    (None, markdown.to_string())
+}
+
+/// `00-setup.md` holding the imported metadata; the title falls back to the file name
+fn setup_file(frontmatter: Option<&str>, fallback_title: &str) -> Result<String> {
+    let mut metadata = match frontmatter {
+        Some(yaml) => match serde_yaml::from_str(yaml)? {
+            serde_yaml::Value::Mapping(mapping) => mapping,
+            _ => serde_yaml::Mapping::new(),
+        },
+        None => serde_yaml::Mapping::new(),
+    };
+
+    let title = serde_yaml::Value::from("title");
+    if !metadata.contains_key(&title) {
+        metadata.insert(title, serde_yaml::Value::from(fallback_title));
+    }
+
+    Ok(format!("---\n{}---\n", serde_yaml::to_string(&metadata)?))
+}
+
+/// Add chapter files numbered `01-`, `02-`, ... to `plan`
+fn plan_chapters(plan: &mut InitPlan, chapters: &[Chapter]) -> usize {
+    let width = chapters.len().to_string().len().max(2);
+    for (index, chapter) in chapters.iter().enumerate() {
+        let name = format!("{:0width$}-{}.md", index + 1, slugify(&chapter.title));
+        plan.add_file(name, chapter.content.clone());
+    }
+    chapters.len()
+}
+
+/// Heading level and text of an ATX heading line
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')))
+        .then(|| (level, rest.trim().trim_end_matches('#').trim()))
+}
+
+/// Split Markdown before every heading of `level` or higher. Text before the
+/// first heading stays with the first chapter; fenced code is never split.
+pub fn split_chapters(markdown: &str, level: usize) -> Vec<Chapter> {
+    let mut chapters = Vec::new();
+    // Until the first heading, `current` collects the untitled preamble
+    let mut current = Chapter {
+        title: String::new(),
+        content: String::new(),
+    };
+    let mut fence: Option<&str> = None;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+        } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+        } else if let Some((heading_level, text)) = heading(line) {
+            if heading_level <= level {
+                if !current.title.is_empty() {
+                    chapters.push(std::mem::replace(
+                        &mut current,
+                        Chapter {
+                            title: String::new(),
+                            content: String::new(),
+                        },
+                    ));
+                }
+                current.title = strip_attributes(text).to_string();
+            }
+        }
+
+        current.content.push_str(line);
+        current.content.push('\n');
+    }
+
+    if !current.content.trim().is_empty() {
+        if current.title.is_empty() {
+            current.title = "content".to_string();
+        }
+        chapters.push(current);
+    }
+
+    for chapter in &mut chapters {
+        chapter.content = chapter.content.trim().to_string() + "\n";
+    }
+    chapters
+}
+
+/// Drop a trailing pandoc attribute block such as `{#intro .unnumbered}`
+fn strip_attributes(text: &str) -> &str {
+    match text.rfind(" {") {
+        Some(index) if text.ends_with('}') => text[..index].trim(),
+        _ => text,
+    }
+}
+
+/// File name part for a chapter heading
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug: String = slug.trim_end_matches('-').chars().take(48).collect();
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "chapter".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Gathers images referenced by imported Markdown into `images/` and
+/// rewrites the links to match
+pub struct MediaCollector {
+    base: PathBuf,
+    /// Source file for each name in `images/`
+    files: BTreeMap<String, PathBuf>,
+}
+
+impl MediaCollector {
+    pub fn new(base: &Path) -> Self {
+        Self {
+            base: base.to_path_buf(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Rewrite image links relative to the collector's base directory
+    pub fn rewrite(&mut self, markdown: &str) -> String {
+        let base = self.base.clone();
+        self.rewrite_from(markdown, &base)
+    }
+
+    /// Rewrite image links in Markdown whose relative links start at `base`
+    pub fn rewrite_from(&mut self, markdown: &str, base: &Path) -> String {
+        let image = Regex::new(r#"(!\[[^\]]*\]\()(<[^>]+>|[^)\s]+)"#).unwrap();
+        let html_image = Regex::new(r#"(<img\b[^>]*\bsrc=")([^"]+)"#).unwrap();
+
+        let rewritten = image.replace_all(markdown, |captures: &Captures| {
+            let target = captures[2].trim_start_matches('<').trim_end_matches('>');
+            match self.collect(target, base) {
+                Some(name) => format!("{}{}/{}", &captures[1], IMAGES_DIR, name),
+                None => captures[0].to_string(),
+            }
+        });
+        html_image
+            .replace_all(&rewritten, |captures: &Captures| {
+                match self.collect(&captures[2], base) {
+                    Some(name) => format!("{}{}/{}", &captures[1], IMAGES_DIR, name),
+                    None => captures[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+
+    /// Register a local image and return its name in `images/`
+    fn collect(&mut self, target: &str, base: &Path) -> Option<String> {
+        if target.contains("://") || target.starts_with("data:") || target.starts_with('#') {
+            return None;
+        }
+
+        let path = Path::new(target);
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            base.join(path)
+        };
+        if !path.is_file() {
+            warn!("Image not found, keeping link: {}", target);
+            return None;
+        }
+
+        if let Some((name, _)) = self.files.iter().find(|(_, source)| **source == path) {
+            return Some(name.clone());
+        }
+
+        let file_name = path.file_name()?.to_string_lossy().to_string();
+        let (stem, extension) = match file_name.rsplit_once('.') {
+            Some((stem, extension)) => (stem.to_string(), format!(".{}", extension)),
+            None => (file_name.clone(), String::new()),
+        };
+        let mut name = file_name;
+        let mut counter = 2;
+        while self.files.contains_key(&name) {
+            name = format!("{}-{}{}", stem, counter, extension);
+            counter += 1;
+        }
+
+        self.files.insert(name.clone(), path);
+        Some(name)
+    }
+
+    pub fn plan_into(&self, plan: &mut InitPlan) -> Result<()> {
+        if !self.files.is_empty() {
+            plan.add_directory(IMAGES_DIR);
+        }
+        for (name, source) in &self.files {
+            plan.add_file(Path::new(IMAGES_DIR).join(name), fs::read(source)?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_chapters() {
+        let markdown = "Preface text.\n\n# Intro {#intro}\n\nHello.\n\n## Detail\n\n```\n# not a heading\n```\n\n# Second Part\n\nBye.\n";
+
+        let chapters = split_chapters(markdown, 1);
+
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title, "Intro");
+        assert!(chapters[0].content.starts_with("Preface text."));
+        assert!(chapters[0].content.contains("# not a heading"));
+        assert_eq!(chapters[1].title, "Second Part");
+        assert_eq!(chapters[1].content, "# Second Part\n\nBye.\n");
+
+        let sections = split_chapters(markdown, 2);
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[1].title, "Detail");
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("1.2 Scope & Goals!"), "1-2-scope-goals");
+        assert_eq!(slugify("Über Größe"), "über-größe");
+        assert_eq!(slugify("???"), "chapter");
+    }
+
+    #[test]
+    fn test_split_frontmatter_and_setup() {
+        let (frontmatter, body) = split_frontmatter("---\nauthor:\n- Ada\n---\n\n# Intro\n");
+        assert_eq!(frontmatter, Some("author:\n- Ada"));
+        assert_eq!(body, "\n# Intro\n");
+
+        let setup = setup_file(frontmatter, "spec").unwrap();
+        assert_eq!(setup, "---\nauthor:\n- Ada\ntitle: spec\n---\n");
+    }
+
+    #[test]
+    fn test_media_collector_rewrites_links() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let media = temp_dir.path().join("media");
+        fs::create_dir_all(media.join("a")).unwrap();
+        fs::write(media.join("image1.png"), "one").unwrap();
+        fs::write(media.join("a/image1.png"), "two").unwrap();
+
+        let mut collector = MediaCollector::new(temp_dir.path());
+        let markdown = format!(
+            "![Chart]({}){{width=\"5cm\"}}\n![](media/a/image1.png)\n![](https://x.org/y.png)\n<img src=\"media/image1.png\">\n",
+            media.join("image1.png").display()
+        );
+        let rewritten = collector.rewrite(&markdown);
+
+        assert_eq!(
+            rewritten,
+            "![Chart](images/image1.png){width=\"5cm\"}\n![](images/image1-2.png)\n![](https://x.org/y.png)\n<img src=\"images/image1.png\">\n"
+        );
+
+        let mut plan = InitPlan::new(temp_dir.path().join("project"));
+        collector.plan_into(&mut plan).unwrap();
+        assert!(plan.has_file(Path::new("images/image1-2.png")));
+    }
+}
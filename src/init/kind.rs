@@ -0,0 +1,325 @@
+use clap::ValueEnum;
+
+/// What the project is for; decides frontmatter defaults, sample files and layout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DocumentKind {
+    /// Technical or business report with sections
+    #[default]
+    Report,
+    /// Thesis or dissertation with chapters, abstract and appendix
+    Thesis,
+    /// Book with chapters
+    Book,
+    /// Short article or paper
+    Article,
+    /// Single-page letter
+    Letter,
+    /// Slide deck, one `##` heading per slide
+    Slides,
+}
+
+impl DocumentKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DocumentKind::Report => "report",
+            DocumentKind::Thesis => "thesis",
+            DocumentKind::Book => "book",
+            DocumentKind::Article => "article",
+            DocumentKind::Letter => "letter",
+            DocumentKind::Slides => "slides",
+        }
+    }
+
+    /// Directories created next to `output/` and `templates/`
+    pub fn directories(&self) -> &'static [&'static str] {
+        match self {
+            DocumentKind::Report => &["images"],
+            DocumentKind::Thesis => &["images", "diagrams", "data"],
+            DocumentKind::Book => &["images"],
+            DocumentKind::Article => &["images", "data"],
+            DocumentKind::Letter => &[],
+            DocumentKind::Slides => &["images"],
+        }
+    }
+
+    /// Template to use for PDF output, if docPilot has a fitting one
+    pub fn recommended_template(&self) -> Option<&'static str> {
+        match self {
+            DocumentKind::Slides => None,
+            _ => Some("eisvogel"),
+        }
+    }
+
+    /// Whether the project starts with a `references.bib` stub
+    pub fn has_bibliography(&self) -> bool {
+        matches!(self, DocumentKind::Thesis | DocumentKind::Article)
+    }
+
+    /// Document structure and layout block of `00-setup.md`
+    pub fn structure_settings(&self) -> &'static str {
+        match self {
+            DocumentKind::Report => {
+                r#"# Document Structure
+top-level-division: "section"
+numbersections: true
+# secnumdepth: 3
+# toc: true
+# toc-depth: 3
+# lof: true
+# lot: true
+
+# Document Class and Layout
+# documentclass: "article"
+# classoption: ["11pt", "a4paper"]
+# geometry: ["margin=2.5cm"]
+# fontsize: "11pt"
+# linestretch: 1.2"#
+            }
+            DocumentKind::Thesis => {
+                r#"# Document Structure
+top-level-division: "chapter"
+numbersections: true
+toc: true
+toc-own-page: true
+lof: true
+lot: true
+# secnumdepth: 3
+# toc-depth: 3
+
+# Document Class and Layout
+documentclass: "scrbook"
+book: true
+titlepage: true
+classoption: ["oneside", "12pt"]
+bibliography: ["references.bib"]
+linestretch: 1.5
+# geometry: ["margin=3cm"]"#
+            }
+            DocumentKind::Book => {
+                r#"# Document Structure
+top-level-division: "chapter"
+numbersections: true
+toc: true
+toc-own-page: true
+# secnumdepth: 2
+# toc-depth: 2
+
+# Document Class and Layout
+documentclass: "scrbook"
+book: true
+titlepage: true
+classoption: ["twoside"]
+# fontsize: "11pt"
+# linestretch: 1.2"#
+            }
+            DocumentKind::Article => {
+                r#"# Document Structure
+top-level-division: "section"
+numbersections: true
+# toc: true
+# secnumdepth: 3
+
+# Document Class and Layout
+documentclass: "scrartcl"
+titlepage: false
+abstract: "One paragraph summarising the article."
+bibliography: ["references.bib"]
+# classoption: ["twocolumn"]
+# geometry: ["margin=2cm"]"#
+            }
+            DocumentKind::Letter => {
+                r#"# Document Structure
+numbersections: false
+# toc: false
+
+# Document Class and Layout
+documentclass: "scrartcl"
+titlepage: false
+disable-header-and-footer: true
+# geometry: ["margin=2.5cm"]"#
+            }
+            DocumentKind::Slides => {
+                r#"# Document Structure
+# Each second-level heading starts a new slide
+numbersections: false"#
+            }
+        }
+    }
+
+    /// Starter Markdown files (name and content) for this kind
+    pub fn sample_files(&self, project_name: &str) -> Vec<(&'static str, String)> {
+        match self {
+            DocumentKind::Report => vec![("01-introduction.md", introduction(project_name))],
+            DocumentKind::Thesis => vec![
+                ("01-abstract.md", ABSTRACT.to_string()),
+                (
+                    "02-introduction.md",
+                    "# Introduction\n\nMotivate the problem and state the research questions [@example2024].\n"
+                        .to_string(),
+                ),
+                (
+                    "03-background.md",
+                    "# Background\n\nSummarise related work and the concepts the thesis builds on.\n"
+                        .to_string(),
+                ),
+                (
+                    "04-conclusion.md",
+                    "# Conclusion\n\nAnswer the research questions and outline future work.\n"
+                        .to_string(),
+                ),
+                ("90-appendix.md", APPENDIX.to_string()),
+                ("99-references.md", REFERENCES.to_string()),
+            ],
+            DocumentKind::Book => vec![
+                (
+                    "01-preface.md",
+                    "# Preface {.unnumbered}\n\nWhy this book exists and who it is for.\n"
+                        .to_string(),
+                ),
+                (
+                    "02-first-chapter.md",
+                    format!(
+                        "# The First Chapter\n\nThe story of **{}** begins here.\n",
+                        project_name
+                    ),
+                ),
+                (
+                    "03-second-chapter.md",
+                    "# The Second Chapter\n\nAdd one numbered file per chapter.\n".to_string(),
+                ),
+            ],
+            DocumentKind::Article => vec![
+                (
+                    "01-introduction.md",
+                    "# Introduction\n\nState the contribution of the article [@example2024].\n"
+                        .to_string(),
+                ),
+                (
+                    "02-discussion.md",
+                    "# Discussion\n\nDiscuss the results and their limitations.\n".to_string(),
+                ),
+                ("99-references.md", REFERENCES.to_string()),
+            ],
+            DocumentKind::Letter => vec![(
+                "01-letter.md",
+                "Recipient Name  \nStreet 1  \n12345 City\n\n**Subject: Your subject**\n\nDear Recipient,\n\nThe body of the letter.\n\nKind regards,\n\nYour Name\n"
+                    .to_string(),
+            )],
+            DocumentKind::Slides => vec![(
+                "01-slides.md",
+                format!(
+                    "# {}\n\n## First slide\n\n- One point per bullet\n- Keep it short\n\n## Second slide\n\nPut pictures in `images/` and show them with `![Caption](images/picture.png)`.\n",
+                    project_name
+                ),
+            )],
+        }
+    }
+}
+
+fn introduction(project_name: &str) -> String {
+    format!(
+        r#"# Introduction
+
+Welcome to your new docPilot project: **{}**!
+
+This is a sample introduction file. You can edit this content or create additional numbered markdown files to build your document.
+
+## Getting Started
+
+1. Edit the metadata in 00-setup.md
+2. Add your content in numbered markdown files
+3. Run docpilot build pdf to generate your document
+
+## Features
+
+docPilot provides:
+- Professional PDF generation with LaTeX
+- Multi-format output (PDF, DOCX, HTML)
+- Template management
+- Dependency validation
+- Multi-language support
+
+Happy writing! 📝
+"#,
+        project_name
+    )
+}
+
+const ABSTRACT: &str = r#"# Abstract {.unnumbered}
+
+Summarise the problem, the approach and the main results in one page.
+"#;
+
+const APPENDIX: &str = r#"\appendix
+
+# Appendix
+
+Supplementary material such as raw data, proofs or long listings.
+"#;
+
+const REFERENCES: &str = r#"# References {.unnumbered}
+
+::: {#refs}
+:::
+"#;
+
+pub const BIBLIOGRAPHY_STUB: &str = r#"@article{example2024,
+  author  = {Doe, Jane and Smith, John},
+  title   = {An Example Reference},
+  journal = {Journal of Examples},
+  year    = {2024},
+  volume  = {1},
+  pages   = {1--10}
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_book_kinds_use_chapters() {
+        for kind in [DocumentKind::Thesis, DocumentKind::Book] {
+            assert!(kind
+                .structure_settings()
+                .contains("top-level-division: \"chapter\""));
+            assert!(kind.structure_settings().contains("book: true"));
+        }
+        assert!(DocumentKind::Report
+            .structure_settings()
+            .contains("top-level-division: \"section\""));
+    }
+
+    #[test]
+    fn test_bibliography_kinds_cite_the_stub() {
+        for kind in [
+            DocumentKind::Report,
+            DocumentKind::Thesis,
+            DocumentKind::Article,
+            DocumentKind::Book,
+            DocumentKind::Letter,
+            DocumentKind::Slides,
+        ] {
+            assert_eq!(
+                kind.structure_settings()
+                    .contains("bibliography: [\"references.bib\"]"),
+                kind.has_bibliography(),
+                "{}",
+                kind.label()
+            );
+        }
+    }
+
+    #[test]
+    fn test_slides_link_no_missing_files() {
+        let link = regex::Regex::new(r"!\[[^\]]*\]\(([^)]+)\)").unwrap();
+        for (_, content) in DocumentKind::Slides.sample_files("Deck") {
+            // Inline code is fine, real image links would need the file
+            let prose: String = content.split('`').step_by(2).collect();
+            assert!(!link.is_match(&prose), "{}", content);
+        }
+        assert!(!DocumentKind::Slides
+            .structure_settings()
+            .contains("aspectratio"));
+    }
+}
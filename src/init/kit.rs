@@ -0,0 +1,364 @@
+//! Starter kits: project skeletons teams keep in a directory or git
+//! repository, rendered by `docpilot init --from <source>`.
+
+use super::plan::InitPlan;
+use crate::errors::{DocPilotError, Result};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, warn};
+use walkdir::WalkDir;
+
+/// Manifest file at the root of a starter kit; not copied into the project
+pub const KIT_MANIFEST_FILE: &str = "docpilot-kit.yml";
+
+/// Placeholder used for `author` when neither the kit nor the user sets one
+pub(super) const DEFAULT_AUTHOR: &str = "Your Name";
+
+/// Contents of `docpilot-kit.yml`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct KitManifest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Variables asked for when the kit is rendered
+    pub prompts: Vec<KitPrompt>,
+}
+
+/// One variable the kit asks for
+#[derive(Debug, Clone, Deserialize)]
+pub struct KitPrompt {
+    pub name: String,
+    /// Question shown to the user, defaults to the variable name
+    pub prompt: Option<String>,
+    pub default: Option<String>,
+}
+
+impl KitPrompt {
+    fn question(&self) -> &str {
+        self.prompt.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// A directory tree with `{{variable}}` placeholders in file contents and names
+pub struct StarterKit {
+    root: PathBuf,
+    manifest: KitManifest,
+}
+
+impl StarterKit {
+    pub fn load(root: &Path) -> Result<Self> {
+        if !root.is_dir() {
+            return Err(DocPilotError::Config {
+                message: format!("Starter kit not found: {}", root.display()),
+            });
+        }
+
+        let manifest_path = root.join(KIT_MANIFEST_FILE);
+        let manifest = if manifest_path.exists() {
+            serde_yaml::from_str(&fs::read_to_string(&manifest_path)?)?
+        } else {
+            debug!("Starter kit has no {}", KIT_MANIFEST_FILE);
+            KitManifest::default()
+        };
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            manifest,
+        })
+    }
+
+    pub fn manifest(&self) -> &KitManifest {
+        &self.manifest
+    }
+
+    /// Values for every placeholder: built-ins first, then the kit's prompts.
+    /// `provided` (from `--var`) wins over prompting; without a terminal the
+    /// prompt defaults are used.
+    pub fn resolve_variables(
+        &self,
+        project_name: &str,
+        provided: &BTreeMap<String, String>,
+        interactive: bool,
+    ) -> Result<BTreeMap<String, String>> {
+        let mut variables = BTreeMap::from([
+            ("project_name".to_string(), project_name.to_string()),
+            (
+                "date".to_string(),
+                chrono::Utc::now().format("%Y-%m-%d").to_string(),
+            ),
+            ("author".to_string(), DEFAULT_AUTHOR.to_string()),
+        ]);
+
+        let stdin = std::io::stdin();
+        let mut input = stdin.lock();
+
+        for prompt in &self.manifest.prompts {
+            if provided.contains_key(&prompt.name) {
+                continue;
+            }
+            // Defaults may refer to earlier variables, e.g. "{{project_name}} Team"
+            let default = prompt
+                .default
+                .as_deref()
+                .map(|d| render(d, &variables).0)
+                .or_else(|| variables.get(&prompt.name).cloned());
+
+            let value = if interactive {
+                ask(prompt.question(), default.as_deref(), &mut input)?
+            } else {
+                default
+            };
+
+            match value {
+                Some(value) => {
+                    variables.insert(prompt.name.clone(), value);
+                }
+                None => {
+                    return Err(DocPilotError::Config {
+                        message: format!(
+                            "Starter kit variable '{}' has no default; pass --var {}=<value>",
+                            prompt.name, prompt.name
+                        ),
+                    })
+                }
+            }
+        }
+
+        variables.extend(provided.clone());
+        Ok(variables)
+    }
+
+    /// Add the kit's files to `plan`, substituting placeholders in file
+    /// contents and names
+    pub fn plan_into(
+        &self,
+        plan: &mut InitPlan,
+        variables: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        let mut unknown = BTreeSet::new();
+
+        let walker = WalkDir::new(&self.root)
+            .min_depth(1)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != ".git");
+
+        for entry in walker {
+            let entry = entry?;
+            let relative = entry
+                .path()
+                .strip_prefix(&self.root)
+                .unwrap_or(entry.path());
+            if relative == Path::new(KIT_MANIFEST_FILE) {
+                continue;
+            }
+
+            let (rendered, missing) = render(&relative.to_string_lossy(), variables);
+            unknown.extend(missing);
+            let relative = rendered_path(relative, rendered)?;
+
+            if entry.file_type().is_dir() {
+                plan.add_directory(relative);
+                continue;
+            }
+
+            let bytes = fs::read(entry.path())?;
+            match String::from_utf8(bytes) {
+                Ok(text) => {
+                    let (content, missing) = render(&text, variables);
+                    unknown.extend(missing);
+                    plan.add_file(relative, content);
+                }
+                // Images, fonts and DOCX references are copied as they are
+                Err(binary) => plan.add_file(relative, binary.into_bytes()),
+            }
+        }
+
+        for name in unknown {
+            warn!(
+                "Starter kit placeholder '{{{{{}}}}}' has no value; pass --var {}=<value>",
+                name, name
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// A kit path after placeholder substitution. Variables must not move files
+/// out of the project, so only plain relative components are accepted.
+fn rendered_path(original: &Path, rendered: String) -> Result<PathBuf> {
+    let path = PathBuf::from(rendered);
+    let mut components = path.components().peekable();
+    let plain = components.peek().is_some()
+        && components.all(|component| matches!(component, Component::Normal(_)));
+
+    if plain {
+        Ok(path)
+    } else {
+        Err(DocPilotError::Config {
+            message: format!(
+                "Starter kit path '{}' renders to '{}', which is not inside the project",
+                original.display(),
+                path.display()
+            ),
+        })
+    }
+}
+
+/// Substitute `{{ name }}` placeholders; unknown ones are kept and returned
+fn render(text: &str, variables: &BTreeMap<String, String>) -> (String, Vec<String>) {
+    let placeholder = Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_-]*)\s*\}\}").unwrap();
+    let mut unknown = Vec::new();
+
+    let rendered = placeholder.replace_all(text, |captures: &Captures| {
+        match variables.get(&captures[1]) {
+            Some(value) => value.clone(),
+            None => {
+                unknown.push(captures[1].to_string());
+                captures[0].to_string()
+            }
+        }
+    });
+
+    (rendered.into_owned(), unknown)
+}
+
+pub(super) fn ask(
+    question: &str,
+    default: Option<&str>,
+    input: &mut impl BufRead,
+) -> Result<Option<String>> {
+    match default {
+        Some(default) => print!("{} [{}]: ", question, default),
+        None => print!("{}: ", question),
+    }
+    std::io::stdout().flush()?;
+
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    let answer = line.trim();
+
+    Ok(if answer.is_empty() {
+        default.map(|d| d.to_string())
+    } else {
+        Some(answer.to_string())
+    })
+}
+
+/// Parse `--var key=value` arguments
+pub fn parse_variables(pairs: &[String]) -> Result<BTreeMap<String, String>> {
+    pairs
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.to_string()))
+            }
+            _ => Err(DocPilotError::Config {
+                message: format!("Invalid --var '{}', expected key=value", pair),
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_kit(root: &Path) {
+        fs::create_dir_all(root.join("images")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+        fs::write(
+            root.join(KIT_MANIFEST_FILE),
+            "name: company-report\nprompts:\n  - name: department\n    prompt: Department\n    default: \"{{project_name}} Team\"\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("00-setup.md"),
+            "---\ntitle: \"{{project_name}}\"\nauthor: [\"{{ author }}\"]\nfooter-left: \"{{department}} · {{legal}}\"\n---\n",
+        )
+        .unwrap();
+        fs::write(root.join("{{project_name}}-notes.md"), "# Notes\n").unwrap();
+        fs::write(root.join("images/logo.png"), [0x89, 0x50, 0xff, 0xfe]).unwrap();
+    }
+
+    #[test]
+    fn test_render_kit() {
+        let temp_dir = TempDir::new().unwrap();
+        let kit_dir = temp_dir.path().join("kit");
+        let target = temp_dir.path().join("project");
+        write_kit(&kit_dir);
+
+        let kit = StarterKit::load(&kit_dir).unwrap();
+        assert_eq!(kit.manifest().name.as_deref(), Some("company-report"));
+
+        let provided = parse_variables(&["author=Ada".to_string()]).unwrap();
+        let variables = kit.resolve_variables("Annual", &provided, false).unwrap();
+        assert_eq!(variables["department"], "Annual Team");
+
+        let mut plan = InitPlan::new(target.clone());
+        kit.plan_into(&mut plan, &variables).unwrap();
+        assert!(plan.has_file(Path::new("00-setup.md")));
+        assert!(plan.has_file(Path::new("Annual-notes.md")));
+        assert!(!plan.has_file(Path::new(KIT_MANIFEST_FILE)));
+        plan.apply(false).unwrap();
+
+        let setup = fs::read_to_string(target.join("00-setup.md")).unwrap();
+        assert!(setup.contains("title: \"Annual\""));
+        assert!(setup.contains("author: [\"Ada\"]"));
+        // Unknown placeholders are left for the user to fill in
+        assert!(setup.contains("Annual Team · {{legal}}"));
+
+        assert!(target.join("Annual-notes.md").exists());
+        assert_eq!(
+            fs::read(target.join("images/logo.png")).unwrap(),
+            vec![0x89, 0x50, 0xff, 0xfe]
+        );
+        assert!(!target.join(KIT_MANIFEST_FILE).exists());
+        assert!(!target.join(".git").exists());
+    }
+
+    #[test]
+    fn test_prompt_without_default_needs_var() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(KIT_MANIFEST_FILE),
+            "prompts:\n  - name: client\n",
+        )
+        .unwrap();
+        let kit = StarterKit::load(temp_dir.path()).unwrap();
+
+        assert!(kit.resolve_variables("p", &BTreeMap::new(), false).is_err());
+        let provided = parse_variables(&["client=ACME".to_string()]).unwrap();
+        assert_eq!(
+            kit.resolve_variables("p", &provided, false).unwrap()["client"],
+            "ACME"
+        );
+        assert!(parse_variables(&["client".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_rendered_paths_stay_inside_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let chapters = temp_dir.path().join("{{project_name}}");
+        fs::create_dir_all(&chapters).unwrap();
+        fs::write(chapters.join("notes.md"), "# Notes\n").unwrap();
+        let kit = StarterKit::load(temp_dir.path()).unwrap();
+
+        for name in ["..", "../escape", "/tmp/escape", "", "."] {
+            let variables = BTreeMap::from([("project_name".to_string(), name.to_string())]);
+            let mut plan = InitPlan::new(temp_dir.path().join("project"));
+            assert!(
+                kit.plan_into(&mut plan, &variables).is_err(),
+                "{:?} was accepted",
+                name
+            );
+        }
+    }
+}
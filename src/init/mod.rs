@@ -0,0 +1,422 @@
+mod kind;
+mod kit;
+mod plan;
+
+pub use kind::DocumentKind;
+pub use kit::{parse_variables, StarterKit};
+pub use plan::InitPlan;
+
+use crate::errors::Result;
+use crate::templates::{eisvogel_files, fetch_directory, TemplateSource, EISVOGEL_NAME};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use tracing::info;
+
+pub struct ProjectInitializer {
+    project_name: String,
+    /// Directory the project is created in
+    root: PathBuf,
+    title: String,
+    /// Author given in the wizard; kits fall back to their own default
+    author: Option<String>,
+    lang: String,
+    kind: DocumentKind,
+    /// Starter kit directory, archive or git URL to render instead of the built-in scaffold
+    kit: Option<String>,
+    /// Kit variables from `--var key=value`
+    variables: BTreeMap<String, String>,
+    /// Overwrite existing files
+    force: bool,
+    /// Only print what would be created
+    dry_run: bool,
+}
+
+impl ProjectInitializer {
+    pub fn new(project_name: String) -> Self {
+        Self {
+            title: project_name.clone(),
+            project_name,
+            root: PathBuf::from("."),
+            author: None,
+            lang: "en".to_string(),
+            kind: DocumentKind::default(),
+            kit: None,
+            variables: BTreeMap::new(),
+            force: false,
+            dry_run: false,
+        }
+    }
+
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.root = root;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: DocumentKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_kit(mut self, source: String, variables: BTreeMap<String, String>) -> Self {
+        self.kit = Some(source);
+        self.variables = variables;
+        self
+    }
+
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Ask for title, author, language and kind on the terminal
+    pub fn run_wizard(mut self) -> Result<Self> {
+        let stdin = std::io::stdin();
+        let mut input = stdin.lock();
+        let mut ask = |question: &str, default: &str| -> Result<String> {
+            Ok(kit::ask(question, Some(default), &mut input)?.unwrap_or_default())
+        };
+
+        println!("🧭 New docPilot project");
+        self.title = ask("Title", &self.title)?;
+        let author = ask("Author", self.author())?;
+        if author != self.author() {
+            self.author = Some(author);
+        }
+        self.lang = ask("Language", &self.lang)?;
+
+        let kinds: Vec<&str> = DocumentKind::value_variants()
+            .iter()
+            .map(|kind| kind.label())
+            .collect();
+        let question = format!("Kind ({})", kinds.join(", "));
+        loop {
+            let answer = ask(&question, self.kind.label())?;
+            match DocumentKind::from_str(&answer, true) {
+                Ok(kind) => {
+                    self.kind = kind;
+                    break;
+                }
+                Err(_) => println!("Unknown kind '{}'", answer),
+            }
+        }
+
+        Ok(self)
+    }
+
+    pub async fn initialize(&self) -> Result<()> {
+        info!(
+            "Initializing docPilot project: {} ({}) in {}",
+            self.project_name,
+            self.kind.label(),
+            self.root.display()
+        );
+
+        let staging = TempDir::new()?;
+        let (plan, kit) = match &self.kit {
+            Some(source) => {
+                let (plan, kit) = self.kit_plan(source, staging.path()).await?;
+                (plan, Some(kit))
+            }
+            None => (self.scaffold_plan()?, None),
+        };
+
+        if self.dry_run {
+            plan.print(self.force);
+            return Ok(());
+        }
+
+        let written = plan.apply(self.force)?;
+        info!("Wrote {} files", written);
+
+        info!("✅ Project initialized successfully!");
+        match &kit {
+            Some(kit) => println!(
+                "🚀 Project '{}' initialized from {}!",
+                self.project_name,
+                kit.manifest()
+                    .name
+                    .as_deref()
+                    .or(self.kit.as_deref())
+                    .unwrap_or_default()
+            ),
+            None => println!(
+                "🚀 Project '{}' initialized ({})!",
+                self.project_name,
+                self.kind.label()
+            ),
+        }
+        if let Some(description) = kit.as_ref().and_then(|k| k.manifest().description.as_ref()) {
+            println!("   {}", description);
+        }
+
+        println!();
+        println!("Next steps:");
+        let mut step = 1;
+        if self.root != Path::new(".") {
+            println!("  {}. cd {}", step, self.root.display());
+            step += 1;
+        }
+        println!("  {}. Edit 00-setup.md to configure your document", step);
+        println!(
+            "  {}. Add your content in numbered markdown files",
+            step + 1
+        );
+        match self.kind.recommended_template() {
+            Some(template) => println!(
+                "  {}. Run 'docpilot build pdf' to generate your document (template: {})",
+                step + 2,
+                template
+            ),
+            None => println!(
+                "  {}. docPilot has no slide builder yet; render with 'pandoc -t beamer' or 'pandoc -t revealjs'",
+                step + 2
+            ),
+        }
+
+        Ok(())
+    }
+
+    /// The built-in scaffold for the selected kind
+    fn scaffold_plan(&self) -> Result<InitPlan> {
+        let mut plan = InitPlan::new(self.root.clone());
+
+        for dir in ["output", "templates"]
+            .into_iter()
+            .chain(self.kind.directories().iter().copied())
+        {
+            plan.add_directory(dir);
+        }
+
+        // The bundled Eisvogel template; no network access needed
+        self.add_eisvogel(&mut plan)?;
+
+        plan.add_file("00-setup.md", self.setup_file());
+        if self.kind.has_bibliography() {
+            plan.add_file("references.bib", kind::BIBLIOGRAPHY_STUB);
+        }
+        for (name, content) in self.kind.sample_files(&self.project_name) {
+            plan.add_file(name, content);
+        }
+
+        Ok(plan)
+    }
+
+    async fn kit_plan(&self, source: &str, staging: &Path) -> Result<(InitPlan, StarterKit)> {
+        info!("Rendering starter kit {}", source);
+
+        let root = fetch_directory(&TemplateSource::parse(source, None), staging).await?;
+        let kit = StarterKit::load(&root)?;
+
+        let interactive = std::io::stdin().is_terminal() && !self.dry_run;
+        let mut provided = self.variables.clone();
+        if let Some(author) = &self.author {
+            provided
+                .entry("author".to_string())
+                .or_insert_with(|| author.clone());
+        }
+        let variables = kit.resolve_variables(&self.project_name, &provided, interactive)?;
+
+        let mut plan = InitPlan::new(self.root.clone());
+        plan.add_directory("output");
+        kit.plan_into(&mut plan, &variables)?;
+
+        // Kits may ship their own Eisvogel or setup file
+        if !plan.has_files_in(&Path::new("templates").join(EISVOGEL_NAME)) {
+            self.add_eisvogel(&mut plan)?;
+        }
+        if !plan.has_file(Path::new("00-setup.md")) {
+            plan.add_file("00-setup.md", self.setup_file());
+        }
+
+        Ok((plan, kit))
+    }
+
+    fn author(&self) -> &str {
+        self.author.as_deref().unwrap_or(kit::DEFAULT_AUTHOR)
+    }
+
+    fn add_eisvogel(&self, plan: &mut InitPlan) -> Result<()> {
+        for (path, content) in eisvogel_files()? {
+            plan.add_file(Path::new("templates").join(path), content);
+        }
+        Ok(())
+    }
+
+    fn setup_file(&self) -> String {
+        let current_date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+
+        format!(
+            r#"---
+# Document Metadata
+title: "{}"
+author: ["{}"]
+date: "{}"
+# subtitle: "Document Subtitle"
+
+# Language and Localization
+lang: "{}"
+# babel-lang: "ngerman"
+
+{}
+
+# Fonts (requires XeLaTeX)
+# mainfont: "Times New Roman"
+# sansfont: "Arial"
+# monofont: "Courier New"
+# mathfont: "Latin Modern Math"
+
+# Headers and Footers
+# header-left: "Document Title"
+# header-center: ""
+# header-right: "\\today"
+# footer-left: "Author Name"
+# footer-center: ""
+# footer-right: "\\thepage"
+
+# Bibliography and Citations
+# bibliography: ["references.bib"]
+# csl: "ieee.csl"
+# link-citations: true
+# reference-section-title: "References"
+
+# Code Highlighting
+# highlight-style: "github"
+# listings: true
+
+# Links and Cross-references
+# linkcolor: "blue"
+# urlcolor: "blue"
+# citecolor: "blue"
+
+# PDF-specific Options
+# colorlinks: true
+# bookmarks: true
+# bookmarksnumbered: true
+# pdfcreator: "docPilot"
+# pdfproducer: "Pandoc with XeLaTeX"
+
+# Eisvogel Template Options
+# titlepage: true
+# titlepage-color: "FFFFFF"
+# titlepage-text-color: "000000"
+# titlepage-rule-color: "000000"
+# titlepage-rule-height: 2
+# logo: "images/logo.png"
+# logo-width: "100"
+# disable-header-and-footer: false
+
+# Table Options
+# table-use-row-colors: true
+
+# Figure Options
+# fig-caption-location: "bottom"
+# tbl-caption-location: "top"
+
+# Custom Variables
+# company: "Your Company"
+# department: "Your Department"
+# version: "1.0"
+# status: "Draft"
+---
+
+# Project Setup
+
+This document serves as the main configuration file for your docPilot project.
+All document settings are defined in the YAML frontmatter above.
+
+## Configuration Guide
+
+### Essential Settings
+- **title**: Document title (appears on title page)
+- **author**: List of authors (use array format)
+- **date**: Document date (use YYYY-MM-DD format)
+- **lang**: Document language (en, de, fr, es, etc.)
+
+### Layout Options
+- **geometry**: Page margins and layout
+- **fontsize**: Base font size (10pt, 11pt, 12pt)
+- **numbersections**: Enable section numbering
+- **toc**: Enable table of contents
+
+### Advanced Features
+- **bibliography**: Reference file for citations
+- **highlight-style**: Code syntax highlighting theme
+- **titlepage**: Enable custom title page (Eisvogel)
+
+## Next Steps
+
+1. Customize the frontmatter above for your document
+2. Add content in numbered markdown files
+3. Place images in the images/ directory
+4. Run docpilot build pdf to generate your document
+
+For more information, visit: https://github.com/metaneutrons/docpilot
+"#,
+            yaml_escape(&self.title),
+            yaml_escape(self.author()),
+            current_date,
+            yaml_escape(&self.lang),
+            self.kind.structure_settings()
+        )
+    }
+}
+
+/// Escape a value for a double-quoted YAML string
+fn yaml_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[allow(dead_code)]
+pub fn initialize_project(name: &str) -> Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let initializer = ProjectInitializer::new(name.to_string());
+    rt.block_on(initializer.initialize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_kit_author_default_applies_without_author() {
+        let temp_dir = TempDir::new().unwrap();
+        let kit_dir = temp_dir.path().join("kit");
+        fs::create_dir_all(&kit_dir).unwrap();
+        fs::write(
+            kit_dir.join(kit::KIT_MANIFEST_FILE),
+            "prompts:\n  - name: author\n    default: Kit Author\n",
+        )
+        .unwrap();
+        fs::write(kit_dir.join("01-a.md"), "by {{author}}\n").unwrap();
+
+        let source = kit_dir.to_string_lossy().to_string();
+        let staging = TempDir::new().unwrap();
+        let initializer = ProjectInitializer::new("report".to_string())
+            .with_root(temp_dir.path().join("project"))
+            .with_kit(source.clone(), BTreeMap::new());
+        let (plan, _) = initializer.kit_plan(&source, staging.path()).await.unwrap();
+        plan.apply(false).unwrap();
+
+        let rendered = fs::read_to_string(temp_dir.path().join("project/01-a.md")).unwrap();
+        assert_eq!(rendered, "by Kit Author\n");
+
+        // --var still wins over the kit's default
+        let variables = parse_variables(&["author=Ada".to_string()]).unwrap();
+        let initializer = ProjectInitializer::new("report".to_string())
+            .with_root(temp_dir.path().join("other"))
+            .with_kit(source.clone(), variables);
+        let (plan, _) = initializer.kit_plan(&source, staging.path()).await.unwrap();
+        plan.apply(false).unwrap();
+        let rendered = fs::read_to_string(temp_dir.path().join("other/01-a.md")).unwrap();
+        assert_eq!(rendered, "by Ada\n");
+    }
+}
@@ -0,0 +1,199 @@
+//! Everything `init` is about to create, computed up front so it can be
+//! shown with `--dry-run` and checked for conflicts before anything is written.
+
+use crate::errors::{DocPilotError, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// A file `init` will write, relative to the project root
+#[derive(Debug, Clone)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub content: Vec<u8>,
+}
+
+/// What happens to a planned file given the current state of the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    Create,
+    /// The file exists with the same content
+    Unchanged,
+    /// The file exists and `--force` was given
+    Overwrite,
+    /// The file exists and would be overwritten without `--force`
+    Conflict,
+}
+
+impl FileAction {
+    fn label(&self) -> &'static str {
+        match self {
+            FileAction::Create => "create",
+            FileAction::Unchanged => "unchanged",
+            FileAction::Overwrite => "overwrite",
+            FileAction::Conflict => "exists",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InitPlan {
+    root: PathBuf,
+    directories: BTreeSet<PathBuf>,
+    files: Vec<PlannedFile>,
+}
+
+impl InitPlan {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            directories: BTreeSet::new(),
+            files: Vec::new(),
+        }
+    }
+
+    pub fn add_directory(&mut self, path: impl Into<PathBuf>) {
+        self.directories.insert(path.into());
+    }
+
+    /// Plan a file; a later file for the same path replaces the earlier one
+    pub fn add_file(&mut self, path: impl Into<PathBuf>, content: impl Into<Vec<u8>>) {
+        let path = path.into();
+        self.files.retain(|file| file.path != path);
+        self.files.push(PlannedFile {
+            path,
+            content: content.into(),
+        });
+    }
+
+    pub fn has_file(&self, path: &Path) -> bool {
+        self.files.iter().any(|file| file.path == path)
+    }
+
+    /// Whether any planned file lives below `dir`
+    pub fn has_files_in(&self, dir: &Path) -> bool {
+        self.files.iter().any(|file| file.path.starts_with(dir))
+    }
+
+    pub fn action(&self, file: &PlannedFile, force: bool) -> FileAction {
+        match fs::read(self.root.join(&file.path)) {
+            Err(_) => FileAction::Create,
+            Ok(existing) if existing == file.content => FileAction::Unchanged,
+            Ok(_) if force => FileAction::Overwrite,
+            Ok(_) => FileAction::Conflict,
+        }
+    }
+
+    /// Existing files with different content
+    pub fn conflicts(&self) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|file| self.action(file, false) == FileAction::Conflict)
+            .map(|file| file.path.clone())
+            .collect()
+    }
+
+    /// Print the plan, one line per directory and file
+    pub fn print(&self, force: bool) {
+        println!("📋 Plan for {}:", self.root.display());
+        for dir in &self.directories {
+            let action = if self.root.join(dir).is_dir() {
+                "unchanged"
+            } else {
+                "create"
+            };
+            println!("  {:<10} {}/", action, dir.display());
+        }
+        for file in &self.files {
+            println!(
+                "  {:<10} {}",
+                self.action(file, force).label(),
+                file.path.display()
+            );
+        }
+
+        let conflicts = self.conflicts();
+        if !conflicts.is_empty() && !force {
+            println!();
+            println!(
+                "⚠️  {} existing file(s) differ; init will stop unless --force is given",
+                conflicts.len()
+            );
+        }
+    }
+
+    /// Write the plan to disk. Refuses to touch existing files with other
+    /// content unless `force` is set. Returns the number of files written.
+    pub fn apply(&self, force: bool) -> Result<usize> {
+        let conflicts = self.conflicts();
+        if !force && !conflicts.is_empty() {
+            let names: Vec<String> = conflicts
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            return Err(DocPilotError::Config {
+                message: format!(
+                    "Refusing to overwrite existing files in {}: {} (use --force to overwrite, --dry-run to preview)",
+                    self.root.display(),
+                    names.join(", ")
+                ),
+            });
+        }
+
+        fs::create_dir_all(&self.root)?;
+        for dir in &self.directories {
+            fs::create_dir_all(self.root.join(dir))?;
+            debug!("Created directory: {}", dir.display());
+        }
+
+        let mut written = 0;
+        for file in &self.files {
+            if self.action(file, force) == FileAction::Unchanged {
+                continue;
+            }
+            let path = self.root.join(&file.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &file.content)?;
+            debug!("Wrote {}", path.display());
+            written += 1;
+        }
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_apply_refuses_to_overwrite_without_force() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("doc");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("00-setup.md"), "mine").unwrap();
+        fs::write(root.join("01-intro.md"), "same").unwrap();
+
+        let mut plan = InitPlan::new(root.clone());
+        plan.add_directory("images");
+        plan.add_file("00-setup.md", "generated");
+        plan.add_file("01-intro.md", "same");
+        plan.add_file("02-next.md", "new");
+
+        assert_eq!(plan.conflicts(), vec![PathBuf::from("00-setup.md")]);
+        assert!(plan.apply(false).is_err());
+        assert!(!root.join("02-next.md").exists());
+        assert!(!root.join("images").exists());
+
+        assert_eq!(plan.apply(true).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(root.join("00-setup.md")).unwrap(),
+            "generated"
+        );
+        assert!(root.join("images").is_dir());
+    }
+}
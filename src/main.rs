@@ -1,17 +1,24 @@
 #![allow(clippy::uninlined_format_args)]
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 use tracing::{error, info};
 
 mod builders;
+mod chapters;
 mod config;
 mod config_file;
 mod dependencies;
 mod diagrams;
+mod directives;
 mod discovery;
 mod errors;
+mod hooks;
+mod import;
 mod init;
+mod preprocess;
+mod preview;
+mod serve;
 mod templates;
 mod watcher;
 
@@ -38,18 +45,116 @@ struct Cli {
 enum Commands {
     /// Initialize a new project
     Init {
+        /// Directory to create the project in (default: current directory)
+        directory: Option<PathBuf>,
+
+        /// Project name (default: directory name)
         #[arg(short, long)]
         name: Option<String>,
+
+        /// Kind of document the project is for
+        #[arg(long, value_enum, default_value_t = init::DocumentKind::Report)]
+        kind: init::DocumentKind,
+
+        /// Starter kit to render: a directory, archive or git URL
+        #[arg(long, value_name = "SOURCE", conflicts_with = "kind")]
+        from: Option<String>,
+
+        /// Starter kit variable, skips its prompt (repeatable)
+        #[arg(long = "var", value_name = "KEY=VALUE", requires = "from")]
+        vars: Vec<String>,
+
+        /// Overwrite existing files
+        #[arg(long)]
+        force: bool,
+
+        /// Print the files that would be created without writing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Ask for title, author, language and kind
+        #[arg(short, long)]
+        interactive: bool,
+    },
+
+    /// Import a document or mdBook as numbered Markdown files
+    Import {
+        /// Markdown, DOCX, ODT, HTML or LaTeX file, or an mdBook SUMMARY.md
+        source: PathBuf,
+
+        /// Read SOURCE as an mdBook SUMMARY.md (default for files named SUMMARY.md)
+        #[arg(long)]
+        mdbook: bool,
+
+        /// Heading level that starts a new chapter file
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=6))]
+        split_level: u8,
+
+        /// Project directory to write to
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Overwrite existing files
+        #[arg(long)]
+        force: bool,
+
+        /// Print the files that would be created without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Add, move, renumber and remove numbered chapter files
+    Chapter {
+        #[command(subcommand)]
+        action: ChapterCommands,
+
+        /// Print the changes without touching any file
+        #[arg(long, global = true)]
+        dry_run: bool,
     },
 
     /// Build documents
+    #[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
     Build {
         #[command(subcommand)]
-        format: BuildFormat,
+        format: Option<BuildFormat>,
+
+        /// Build several formats side by side, e.g. `--formats pdf,html`
+        #[arg(long, value_enum, value_delimiter = ',')]
+        formats: Vec<builders::OutputFormat>,
+
+        /// Rebuild the formats whenever a source changes
+        #[arg(long, requires = "formats")]
+        watch: bool,
+
+        /// Build even if a referenced diagram is missing or fails to render
+        #[arg(long, global = true)]
+        keep_going: bool,
+    },
+
+    /// Serve a live-reloading HTML preview while watching for changes
+    Serve {
+        /// Port on 127.0.0.1 to listen on
+        #[arg(short, long, default_value_t = 3000)]
+        port: u16,
+
+        /// Also keep the PDF up to date and serve it
+        #[arg(long)]
+        pdf: bool,
     },
 
     /// Check dependencies
-    Check,
+    Check {
+        /// Print the result as JSON; exits with 3 if a required tool is
+        /// missing or too old, 4 if an optional tool the project uses is,
+        /// and 1 on other errors
+        #[arg(long)]
+        json: bool,
+
+        /// Use the exit codes of --json with the normal output
+        #[arg(long)]
+        strict: bool,
+    },
 
     /// Show project status
     Status,
@@ -64,7 +169,11 @@ enum Commands {
     },
 
     /// Generate diagrams
-    Diagrams,
+    Diagrams {
+        /// Succeed even if some diagrams fail to render
+        #[arg(long)]
+        keep_going: bool,
+    },
 
     /// Manage configuration
     Config {
@@ -73,6 +182,52 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum ChapterCommands {
+    /// List chapters in build order
+    List,
+
+    /// Create a chapter file, renumbering later chapters as needed
+    Add {
+        /// Chapter title, also used for the file name
+        title: String,
+
+        /// Insert after this chapter (number or file name)
+        #[arg(long, conflicts_with = "before")]
+        after: Option<String>,
+
+        /// Insert before this chapter (number or file name)
+        #[arg(long)]
+        before: Option<String>,
+    },
+
+    /// Move a chapter to the position of another chapter number
+    Move {
+        /// Chapter to move (number or file name)
+        chapter: String,
+
+        /// Chapter number it should get
+        position: String,
+    },
+
+    /// Number all chapters consecutively
+    Renumber {
+        /// First chapter number
+        #[arg(long, default_value_t = 1)]
+        start: u32,
+
+        /// Increment between chapter numbers
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        step: u32,
+    },
+
+    /// Delete a chapter file
+    Remove {
+        /// Chapter to remove (number or file name)
+        chapter: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum ConfigCommands {
     /// Initialize default config file
@@ -99,8 +254,16 @@ enum BuildFormat {
         #[arg(long)]
         watch: bool,
     },
+    /// Build EPUB output
+    Epub {
+        #[arg(long)]
+        watch: bool,
+    },
     /// Build all formats
-    All,
+    All {
+        #[arg(long)]
+        watch: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -108,11 +271,105 @@ enum TemplateCommands {
     /// List available templates
     List,
 
-    /// Download Eisvogel template
+    /// Show details about a template
+    Info { name: String },
+
+    /// List the variables a template reads
+    Vars { name: String },
+
+    /// Generate a themed template from pandoc's defaults and the `theme:` config
+    New {
+        /// Kind of template to generate
+        kind: GeneratedTemplate,
+
+        /// Template package to create or extend
+        #[arg(long, default_value = "theme")]
+        name: String,
+    },
+
+    /// Build a sample (or the project) with every template and write a contact sheet
+    Preview {
+        /// Use the bundled sample document even inside a project
+        #[arg(long)]
+        sample: bool,
+
+        /// Formats to preview, comma separated
+        #[arg(long, value_delimiter = ',', default_value = "pdf,html")]
+        formats: Vec<String>,
+    },
+
+    /// Fetch newer template versions over the network and update templates.lock
+    Update {
+        /// Only update this template (defaults to all pinned templates)
+        name: Option<String>,
+    },
+
+    /// Fetch the latest Eisvogel release (same as `update eisvogel`)
     DownloadEisvogel,
 
-    /// Install custom template
-    Install { path: PathBuf },
+    /// Install a template from a file, directory, archive, git repository or URL
+    Install {
+        /// Path, archive, git URL (`repo.git#tag`) or HTTP URL; omit with --locked
+        /// to install everything pinned in templates.lock
+        source: Option<String>,
+
+        /// Name to install the template under
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Git branch, tag or commit (git sources only)
+        #[arg(long = "ref")]
+        reference: Option<String>,
+
+        /// Expected SHA-256 checksum
+        #[arg(long)]
+        sha256: Option<String>,
+
+        /// Verify against templates.lock instead of updating it
+        #[arg(long)]
+        locked: bool,
+    },
+}
+
+async fn update_templates(manager: &templates::TemplateManager, name: Option<&str>) -> Result<()> {
+    match manager.update(name).await {
+        Ok(updated) => {
+            for template in updated {
+                println!(
+                    "📥 Updated template {} ({})",
+                    template.name,
+                    template.path.display()
+                );
+                println!("   SHA-256: {}", template.sha256);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to update templates: {}", e);
+            Err(e)
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GeneratedTemplate {
+    /// reference.docx with theme fonts, colors, margins and logo
+    Docx,
+    /// HTML template and stylesheet
+    Html,
+}
+
+impl GeneratedTemplate {
+    fn format(self) -> &'static str {
+        match self {
+            GeneratedTemplate::Docx => "docx",
+            GeneratedTemplate::Html => "html",
+        }
+    }
+}
+
+fn load_project_config(config_path: Option<&std::path::Path>) -> Result<config::ProjectConfig> {
+    Ok(config_file::DocPilotConfig::resolve(config_path)?.to_project_config())
 }
 
 #[tokio::main]
@@ -121,42 +378,173 @@ async fn main() -> Result<()> {
 
     // Initialize logging
     let level = if cli.verbose { "debug" } else { "info" };
-    tracing_subscriber::fmt()
-        .with_env_filter(format!("docpilot={}", level))
-        .init();
+    let logging = tracing_subscriber::fmt().with_env_filter(format!("docpilot={}", level));
+    // Keep stdout parseable for `check --json`
+    if matches!(cli.command, Commands::Check { json: true, .. }) {
+        logging.with_writer(std::io::stderr).init();
+    } else {
+        logging.init();
+    }
 
     info!("docPilot starting...");
 
     match cli.command {
-        Commands::Init { name } => {
+        Commands::Init {
+            directory,
+            name,
+            kind,
+            from,
+            vars,
+            force,
+            dry_run,
+            interactive,
+        } => {
+            let root = directory.unwrap_or_else(|| PathBuf::from("."));
             let project_name = name.unwrap_or_else(|| {
                 std::env::current_dir()
                     .unwrap()
-                    .file_name()
-                    .unwrap()
+                    .join(&root)
+                    .components()
+                    .filter_map(|c| match c {
+                        std::path::Component::Normal(part) => Some(part),
+                        _ => None,
+                    })
+                    .next_back()
+                    .unwrap_or_default()
                     .to_string_lossy()
                     .to_string()
             });
 
             info!("Initializing project: {}", project_name);
 
-            let initializer = init::ProjectInitializer::new(project_name);
+            let mut initializer = init::ProjectInitializer::new(project_name)
+                .with_root(root)
+                .with_kind(kind)
+                .with_force(force)
+                .with_dry_run(dry_run);
+            if let Some(source) = from {
+                initializer = initializer.with_kit(source, init::parse_variables(&vars)?);
+            }
+            if interactive {
+                initializer = initializer.run_wizard()?;
+            }
             initializer.initialize().await?;
         }
 
-        Commands::Build { format } => {
-            let config = config::ProjectConfig::default();
+        Commands::Import {
+            source,
+            mdbook,
+            split_level,
+            dir,
+            force,
+            dry_run,
+        } => {
+            let config = load_project_config(cli.config.as_deref())?;
+            let importer = import::Importer::new(dir.clone(), split_level as usize)
+                .with_force(force)
+                .with_dry_run(dry_run)
+                .with_tools(config.tools);
+
+            let is_summary = source.file_name().is_some_and(|name| name == "SUMMARY.md");
+            let chapters = if mdbook || is_summary {
+                importer.import_mdbook(&source)?
+            } else {
+                importer.import_file(&source)?
+            };
 
-            let (format_str, watch) = match &format {
-                BuildFormat::Pdf { watch } => ("pdf", *watch),
-                BuildFormat::Docx { watch } => ("docx", *watch),
-                BuildFormat::Html { watch } => ("html", *watch),
-                BuildFormat::All => ("all", false),
+            if !dry_run {
+                println!(
+                    "📥 Imported {} into {} chapter files in {}",
+                    source.display(),
+                    chapters,
+                    dir.display()
+                );
+            }
+        }
+
+        Commands::Chapter { action, dry_run } => {
+            let manager = chapters::ChapterManager::new(PathBuf::from("."));
+
+            let plan = match action {
+                ChapterCommands::List => {
+                    let chapters = manager.chapters()?;
+                    if chapters.is_empty() {
+                        println!("No numbered chapter files found");
+                    }
+                    for chapter in chapters {
+                        println!("  {:>4}  {}", chapter.number, chapter.file_name);
+                    }
+                    return Ok(());
+                }
+                ChapterCommands::Add {
+                    title,
+                    after,
+                    before,
+                } => manager.plan_add(&title, after.as_deref(), before.as_deref())?,
+                ChapterCommands::Move { chapter, position } => {
+                    manager.plan_move(&chapter, &position)?
+                }
+                ChapterCommands::Renumber { start, step } => manager.plan_renumber(start, step)?,
+                ChapterCommands::Remove { chapter } => manager.plan_remove(&chapter)?,
             };
 
+            if plan.is_empty() {
+                println!("✨ Chapters are already in order");
+                return Ok(());
+            }
+
+            let prefix = if dry_run { "Would " } else { "" };
+            for (old, new) in &plan.renames {
+                println!("🔀 {}rename {} → {}", prefix, old, new);
+            }
+            if let Some((file_name, _)) = &plan.create {
+                println!("📝 {}create {}", prefix, file_name);
+            }
+            if let Some(file_name) = &plan.remove {
+                println!("🗑️  {}remove {}", prefix, file_name);
+            }
+            if dry_run {
+                return Ok(());
+            }
+
+            let report = manager.apply(&plan)?;
+            for file in &report.updated_links {
+                println!("🔗 Updated links in {}", file);
+            }
+            for link in &report.dangling_links {
+                println!("⚠️  Link to removed chapter: {}", link);
+            }
+            if report.used_git {
+                println!("📚 Changes staged with git, history is kept");
+            }
+        }
+
+        Commands::Build {
+            format,
+            formats,
+            watch,
+            keep_going,
+        } => {
+            let mut config = load_project_config(cli.config.as_deref())?;
+            config.keep_going = keep_going;
+
+            let (formats, watch): (Vec<&str>, bool) = match &format {
+                Some(BuildFormat::Pdf { watch }) => (vec!["pdf"], *watch),
+                Some(BuildFormat::Docx { watch }) => (vec!["docx"], *watch),
+                Some(BuildFormat::Html { watch }) => (vec!["html"], *watch),
+                Some(BuildFormat::Epub { watch }) => (vec!["epub"], *watch),
+                Some(BuildFormat::All { watch }) => (vec!["pdf", "docx", "html"], *watch),
+                None => (
+                    formats.iter().map(|format| format.as_str()).collect(),
+                    watch,
+                ),
+            };
+            let formats: Vec<String> = formats.into_iter().map(String::from).collect();
+
             if watch {
-                let watcher = watcher::FileWatcher::new(config);
-                return watcher.watch_and_build(format_str).await;
+                let watcher =
+                    watcher::FileWatcher::new(config).with_config_file(cli.config.clone());
+                return watcher.watch_and_build(&formats).await;
             }
 
             let discovery = discovery::FileDiscovery::new(config.clone());
@@ -170,110 +558,253 @@ async fn main() -> Result<()> {
             }
 
             match format {
-                BuildFormat::Pdf { .. } => {
-                    dependencies::DependencyChecker::validate_for_build("pdf")?;
+                Some(BuildFormat::Pdf { .. }) => {
+                    dependencies::DependencyChecker::validate_for_build("pdf", &config.tools)?;
                     let builder = builders::PdfBuilder::new(config.clone());
                     builder.ensure_output_dir()?;
                     let output_path = config.output_dir.join(format!("{}.pdf", config.name));
-                    builder.build(&files.markdown_files, &output_path).await?;
+                    hooks::build_with_hooks(
+                        &config,
+                        "pdf",
+                        &output_path,
+                        builder.build(&files.markdown_files, &output_path),
+                    )
+                    .await?;
                     println!("📄 PDF built successfully: {}", output_path.display());
                 }
-                BuildFormat::Docx { .. } => {
-                    dependencies::DependencyChecker::validate_for_build("docx")?;
+                Some(BuildFormat::Docx { .. }) => {
+                    dependencies::DependencyChecker::validate_for_build("docx", &config.tools)?;
                     let builder = builders::DocxBuilder::new(config.clone());
                     builder.ensure_output_dir()?;
                     let output_path = config.output_dir.join(format!("{}.docx", config.name));
-                    builder.build(&files.markdown_files, &output_path).await?;
+                    hooks::build_with_hooks(
+                        &config,
+                        "docx",
+                        &output_path,
+                        builder.build(&files.markdown_files, &output_path),
+                    )
+                    .await?;
                     println!("📄 DOCX built successfully: {}", output_path.display());
                 }
-                BuildFormat::Html { .. } => {
-                    dependencies::DependencyChecker::validate_for_build("html")?;
+                Some(BuildFormat::Html { .. }) => {
+                    dependencies::DependencyChecker::validate_for_build("html", &config.tools)?;
                     let builder = builders::HtmlBuilder::new(config.clone());
                     builder.ensure_output_dir()?;
                     let output_path = config.output_dir.join(format!("{}.html", config.name));
-                    builder.build(&files.markdown_files, &output_path).await?;
+                    hooks::build_with_hooks(
+                        &config,
+                        "html",
+                        &output_path,
+                        builder.build(&files.markdown_files, &output_path),
+                    )
+                    .await?;
                     println!("🌐 HTML built successfully: {}", output_path.display());
                 }
-                BuildFormat::All => {
-                    dependencies::DependencyChecker::validate_for_build("all")?;
+                Some(BuildFormat::Epub { .. }) => {
+                    dependencies::DependencyChecker::validate_for_build("epub", &config.tools)?;
+                    let builder = builders::EpubBuilder::new(config.clone());
+                    builder.ensure_output_dir()?;
+                    let output_path = config.output_dir.join(format!("{}.epub", config.name));
+                    hooks::build_with_hooks(
+                        &config,
+                        "epub",
+                        &output_path,
+                        builder.build(&files.markdown_files, &output_path),
+                    )
+                    .await?;
+                    println!("📚 EPUB built successfully: {}", output_path.display());
+                }
+                Some(BuildFormat::All { .. }) => {
+                    dependencies::DependencyChecker::validate_for_build("all", &config.tools)?;
 
                     let pdf_builder = builders::PdfBuilder::new(config.clone());
                     pdf_builder.ensure_output_dir()?;
                     let pdf_path = config.output_dir.join(format!("{}.pdf", config.name));
-                    pdf_builder.build(&files.markdown_files, &pdf_path).await?;
+                    hooks::build_with_hooks(
+                        &config,
+                        "pdf",
+                        &pdf_path,
+                        pdf_builder.build(&files.markdown_files, &pdf_path),
+                    )
+                    .await?;
 
                     let docx_builder = builders::DocxBuilder::new(config.clone());
                     let docx_path = config.output_dir.join(format!("{}.docx", config.name));
-                    docx_builder
-                        .build(&files.markdown_files, &docx_path)
-                        .await?;
+                    hooks::build_with_hooks(
+                        &config,
+                        "docx",
+                        &docx_path,
+                        docx_builder.build(&files.markdown_files, &docx_path),
+                    )
+                    .await?;
 
                     let html_builder = builders::HtmlBuilder::new(config.clone());
                     let html_path = config.output_dir.join(format!("{}.html", config.name));
-                    html_builder
-                        .build(&files.markdown_files, &html_path)
-                        .await?;
+                    hooks::build_with_hooks(
+                        &config,
+                        "html",
+                        &html_path,
+                        html_builder.build(&files.markdown_files, &html_path),
+                    )
+                    .await?;
 
                     println!("📄 All formats built successfully:");
                     println!("  PDF:  {}", pdf_path.display());
                     println!("  DOCX: {}", docx_path.display());
                     println!("  HTML: {}", html_path.display());
                 }
+                None => {
+                    for format in &formats {
+                        dependencies::DependencyChecker::validate_for_build(format, &config.tools)?;
+                    }
+
+                    println!("🔨 Building {}...", formats.join(", "));
+                    let failed: Vec<String> =
+                        builders::build_concurrently(&config, &formats, &files.markdown_files)
+                            .await
+                            .into_iter()
+                            .filter(|build| build.result.is_err())
+                            .map(|build| build.format)
+                            .collect();
+                    if !failed.is_empty() {
+                        return Err(DocPilotError::Build {
+                            message: format!("Failed to build {}", failed.join(", ")),
+                        });
+                    }
+                }
             }
         }
 
-        Commands::Check => {
-            info!("Checking dependencies");
+        Commands::Serve { port, pdf } => {
+            let config = load_project_config(cli.config.as_deref())?;
+            dependencies::DependencyChecker::validate_for_build(
+                if pdf { "pdf" } else { "html" },
+                &config.tools,
+            )?;
+
+            serve::PreviewServer::new(config)
+                .with_port(port)
+                .with_pdf(pdf)
+                .with_config_file(cli.config.clone())
+                .run()
+                .await?;
+        }
 
-            match dependencies::DependencyChecker::check_all() {
-                Ok(deps) => {
-                    println!("🔍 DEPENDENCY CHECK");
-                    println!("===================");
+        Commands::Check { json, strict } => {
+            info!("Checking dependencies");
 
-                    let mut all_good = true;
+            // Outside a project only the core tools are of interest
+            let config = load_project_config(cli.config.as_deref()).ok();
+            let usage = config
+                .as_ref()
+                .and_then(|config| {
+                    discovery::FileDiscovery::new(config.clone())
+                        .discover_all()
+                        .ok()
+                })
+                .map(|files| dependencies::ProjectUsage::detect(&files))
+                .unwrap_or_default();
+            let tools = config.map(|config| config.tools).unwrap_or_default();
+
+            let deps = match dependencies::DependencyChecker::check_project(&usage, &tools) {
+                Ok(deps) => deps,
+                Err(e) => {
+                    error!("Failed to check dependencies: {}", e);
+                    return Err(e);
+                }
+            };
+            let report = dependencies::CheckReport::new(deps);
 
-                    for dep in deps {
-                        let status_icon = if dep.available { "🟢" } else { "🔴" };
-                        let required_text = if dep.required { "REQUIRED" } else { "OPTIONAL" };
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report).map_err(|e| {
+                        DocPilotError::Build {
+                            message: format!("Failed to serialize check result: {}", e),
+                        }
+                    })?
+                );
+            } else {
+                println!("🔍 DEPENDENCY CHECK");
+                println!("===================");
+                println!(
+                    "📦 Package manager: {} ({})",
+                    report.package_manager.name(),
+                    report.os
+                );
+                println!();
 
-                        println!(
-                            "{} {}: {} ({})",
-                            status_icon,
-                            dep.name,
-                            if dep.available {
-                                "Available"
-                            } else {
-                                "Missing"
-                            },
-                            required_text
-                        );
+                for dep in &report.dependencies {
+                    let status_icon = if !dep.available {
+                        "🔴"
+                    } else if dep.is_usable() {
+                        "🟢"
+                    } else {
+                        "🟡"
+                    };
+                    let required_text = if dep.required {
+                        "REQUIRED"
+                    } else if dep.used {
+                        "USED BY PROJECT"
+                    } else {
+                        "OPTIONAL"
+                    };
+
+                    println!(
+                        "{} {}: {} ({})",
+                        status_icon,
+                        dep.name,
+                        if dep.available {
+                            "Available"
+                        } else {
+                            "Missing"
+                        },
+                        required_text
+                    );
+                    println!("    Used for: {}", dep.purpose);
+                    if dep.command.as_os_str() != dep.name.as_str() {
+                        println!("    Path: {}", dep.command.display());
+                    }
 
-                        if let Some(version) = &dep.version {
-                            println!("    Version: {}", version);
-                        }
+                    if let Some(version) = &dep.version {
+                        println!("    Version: {}", version);
+                    }
+                    if let Some(requirement) = &dep.version_requirement {
+                        println!("    Requires: {}", requirement);
+                    }
+                    for problem in &dep.version_issues {
+                        println!("    ⚠️  {}", problem);
+                    }
 
-                        if !dep.available {
-                            if let Some(hint) = &dep.install_hint {
-                                println!("    Install: {}", hint);
-                            }
-                            if dep.required {
-                                all_good = false;
-                            }
+                    if !dep.is_usable() {
+                        if let Some(hint) = &dep.install_hint {
+                            println!("    Install: {}", hint);
                         }
-                        println!();
                     }
+                    println!();
+                }
 
-                    if all_good {
+                match report.outcome {
+                    dependencies::CheckOutcome::Ok => {
                         println!("✅ All required dependencies are available!");
-                    } else {
+                    }
+                    dependencies::CheckOutcome::MissingRequired => {
+                        println!(
+                            "❌ Some required dependencies are missing or outdated. Install them to continue."
+                        );
+                    }
+                    dependencies::CheckOutcome::MissingUsed => {
                         println!(
-                            "❌ Some required dependencies are missing. Install them to continue."
+                            "⚠️  Tools this project uses are missing or outdated. Install them to continue."
                         );
                     }
                 }
-                Err(e) => {
-                    error!("Failed to check dependencies: {}", e);
-                    return Err(e);
+            }
+
+            if json || strict {
+                let code = report.outcome.exit_code();
+                if code != 0 {
+                    std::process::exit(code);
                 }
             }
         }
@@ -281,7 +812,7 @@ async fn main() -> Result<()> {
         Commands::Status => {
             info!("Checking project status");
 
-            let config = config::ProjectConfig::default();
+            let config = load_project_config(cli.config.as_deref())?;
             let discovery = discovery::FileDiscovery::new(config.clone());
 
             match discovery.discover_all() {
@@ -315,7 +846,7 @@ async fn main() -> Result<()> {
         Commands::Clean => {
             info!("Cleaning generated files");
 
-            let config = config::ProjectConfig::default();
+            let config = load_project_config(cli.config.as_deref())?;
 
             if config.output_dir.exists() {
                 std::fs::remove_dir_all(&config.output_dir)?;
@@ -329,19 +860,42 @@ async fn main() -> Result<()> {
         }
 
         Commands::Templates { action } => {
-            let config = config::ProjectConfig::default();
-            let template_manager = templates::TemplateManager::new(config.templates_dir);
+            let config = load_project_config(cli.config.as_deref())?;
+            let template_manager = templates::TemplateManager::new(config.templates_dir.clone())
+                .with_pandoc(config.tools.command("pandoc"));
 
             match action {
-                Some(TemplateCommands::List) => match template_manager.list_templates() {
-                    Ok(templates) => {
+                Some(TemplateCommands::List) => match template_manager.list_packages() {
+                    Ok(packages) => {
                         println!("📋 Available templates:");
-                        if templates.is_empty() {
+                        if packages.is_empty() {
                             println!("  No templates installed");
-                            println!("  Use 'templates download-eisvogel' to get started");
+                            println!(
+                                "  Bundled: eisvogel {} (used for PDF when nothing else is installed)",
+                                templates::EISVOGEL_VERSION
+                            );
                         } else {
-                            for template in templates {
-                                println!("  • {}", template);
+                            for package in packages {
+                                let files = package
+                                    .manifest
+                                    .formats
+                                    .values()
+                                    .map(|f| f.display().to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(", ");
+                                let version = package
+                                    .manifest
+                                    .version
+                                    .as_deref()
+                                    .map(|v| format!(" v{}", v))
+                                    .unwrap_or_default();
+                                println!(
+                                    "  • {}{} [{}] ({})",
+                                    package.name(),
+                                    version,
+                                    package.formats().join(", "),
+                                    files
+                                );
                             }
                         }
                     }
@@ -350,21 +904,162 @@ async fn main() -> Result<()> {
                         return Err(e);
                     }
                 },
-                Some(TemplateCommands::DownloadEisvogel) => {
-                    match template_manager.download_eisvogel().await {
-                        Ok(()) => {
-                            println!("📥 Eisvogel template downloaded successfully!");
+                Some(TemplateCommands::Info { name }) => {
+                    let package = template_manager.find_package(&name)?.ok_or_else(|| {
+                        DocPilotError::Config {
+                            message: format!("Template '{}' not found", name),
+                        }
+                    })?;
+                    let manifest = &package.manifest;
+
+                    println!("📋 Template: {}", manifest.name);
+                    if let Some(description) = &manifest.description {
+                        println!("   {}", description);
+                    }
+                    println!();
+                    println!(
+                        "Version:   {}",
+                        manifest.version.as_deref().unwrap_or("unknown")
+                    );
+                    println!(
+                        "Source:    {}",
+                        manifest.source.as_deref().unwrap_or("unknown")
+                    );
+                    println!("Location:  {}", package.root.display());
+                    println!(
+                        "Manifest:  {}",
+                        if package.has_manifest {
+                            "yes"
+                        } else {
+                            "no (inferred from file names)"
+                        }
+                    );
+                    println!();
+                    println!("Formats:");
+                    for (format, file) in &manifest.formats {
+                        println!("  {:<5} {}", format, file.display());
+                    }
+                    if !manifest.variables.is_empty() {
+                        println!();
+                        println!("Variables:");
+                        for variable in &manifest.variables {
+                            println!("  • {}", variable);
+                        }
+                    }
+                    if !manifest.latex_packages.is_empty() {
+                        println!();
+                        println!("Required LaTeX packages:");
+                        println!("  {}", manifest.latex_packages.join(", "));
+                    }
+                }
+                Some(TemplateCommands::Vars { name }) => {
+                    for (format, variables) in template_manager.variables(&name)? {
+                        println!("📋 Variables of {} ({}):", name, format);
+                        if variables.all.is_empty() {
+                            println!("  (none)");
+                        }
+                        for variable in &variables.all {
+                            if variables.required.contains(variable) {
+                                println!("  • {} (required)", variable);
+                            } else {
+                                println!("  • {}", variable);
+                            }
+                        }
+                        println!();
+                    }
+                }
+                Some(TemplateCommands::New { kind, name }) => {
+                    let files = discovery::FileDiscovery::new(config.clone()).discover_all()?;
+                    let metadata = discovery::MetadataParser::merge_metadata(&files.markdown_files);
+                    let theme = config.theme.with_metadata_fallbacks(&metadata);
+
+                    match template_manager.generate(&name, kind.format(), &theme) {
+                        Ok(path) => {
+                            println!(
+                                "🎨 Generated {} template: {}",
+                                kind.format(),
+                                path.display()
+                            );
+                            println!(
+                                "   Select it with templates.{}_template: {} in docpilot.yml",
+                                kind.format(),
+                                name
+                            );
                         }
                         Err(e) => {
-                            error!("Failed to download template: {}", e);
+                            error!("Failed to generate template: {}", e);
                             return Err(e);
                         }
                     }
                 }
-                Some(TemplateCommands::Install { path }) => {
-                    match template_manager.install_template(&path) {
-                        Ok(()) => {
+                Some(TemplateCommands::Preview { sample, formats }) => {
+                    dependencies::DependencyChecker::validate_for_build("html", &config.tools)?;
+
+                    let preview = preview::TemplatePreview::new(config.clone());
+                    let project_files = discovery::FileDiscovery::new(config.clone())
+                        .discover_all()?
+                        .markdown_files;
+                    let files = if sample || project_files.is_empty() {
+                        preview.write_sample()?
+                    } else {
+                        project_files
+                    };
+
+                    let (index, entries) = preview.generate(&files, &formats).await?;
+                    for entry in &entries {
+                        match &entry.error {
+                            None => println!("  ✅ {} ({})", entry.template, entry.format),
+                            Some(_) => println!("  ❌ {} ({})", entry.template, entry.format),
+                        }
+                    }
+                    println!("🖼️  Template preview: {}", index.display());
+                }
+                Some(TemplateCommands::Update { name }) => {
+                    update_templates(&template_manager, name.as_deref()).await?;
+                }
+                Some(TemplateCommands::DownloadEisvogel) => {
+                    update_templates(&template_manager, Some(templates::EISVOGEL_NAME)).await?;
+                }
+                Some(TemplateCommands::Install {
+                    source,
+                    name,
+                    reference,
+                    sha256,
+                    locked,
+                }) => {
+                    let result = match source {
+                        Some(source) => {
+                            let options = templates::InstallOptions {
+                                name,
+                                reference,
+                                sha256,
+                                locked,
+                            };
+                            template_manager
+                                .install(&source, &options)
+                                .await
+                                .map(|installed| vec![installed])
+                        }
+                        None if locked => template_manager.install_locked().await,
+                        None => Err(DocPilotError::Config {
+                            message: "Specify a template source, or --locked to install from templates.lock"
+                                .to_string(),
+                        }),
+                    };
+
+                    match result {
+                        Ok(installed) => {
                             println!("📦 Template installed successfully!");
+                            for template in &installed {
+                                println!("   {} ({})", template.name, template.path.display());
+                                if let Some(commit) = &template.commit {
+                                    println!("   Commit: {}", commit);
+                                }
+                                println!("   SHA-256: {}", template.sha256);
+                            }
+                            if locked {
+                                println!("🔒 Verified against templates.lock");
+                            }
                         }
                         Err(e) => {
                             error!("Failed to install template: {}", e);
@@ -376,20 +1071,34 @@ async fn main() -> Result<()> {
                     println!("📋 Template management");
                     println!("Available commands:");
                     println!("  list              - List installed templates");
-                    println!("  download-eisvogel - Download Eisvogel LaTeX template");
-                    println!("  install <path>    - Install template from file");
+                    println!("  info <name>       - Show template details");
+                    println!("  vars <name>       - List template variables");
+                    println!("  new docx|html     - Generate a themed template");
+                    println!("  preview           - Compare templates on a contact sheet");
+                    println!("  update [name]     - Fetch newer template versions (network)");
+                    println!(
+                        "  install <source>  - Install template from a file, archive, git or URL"
+                    );
                 }
             }
         }
 
-        Commands::Diagrams => {
+        Commands::Diagrams { keep_going } => {
             info!("Generating diagrams");
 
-            let config = config::ProjectConfig::default();
+            let config = load_project_config(cli.config.as_deref())?;
             let discovery = discovery::FileDiscovery::new(config.clone());
             let files = discovery.discover_all()?;
 
-            if files.mermaid_files.is_empty() {
+            let inline_count: usize = files
+                .markdown_files
+                .iter()
+                .filter(|f| f.has_inline_mermaid)
+                .map(|f| diagrams::DiagramProcessor::find_inline_diagrams(&f.content).len())
+                .sum();
+            let total = files.mermaid_files.len() + inline_count;
+
+            if total == 0 {
                 println!("🎨 No Mermaid files found");
                 println!("Create .mmd files or add Mermaid code blocks to your markdown");
                 return Ok(());
@@ -397,12 +1106,28 @@ async fn main() -> Result<()> {
 
             let processor = diagrams::DiagramProcessor::new(config.clone());
 
-            println!(
-                "🎨 Processing {} Mermaid diagrams...",
-                files.mermaid_files.len()
-            );
+            println!("🎨 Processing {} Mermaid diagrams...", total);
+
+            let report = processor
+                .process_all(&files.mermaid_files, &files.markdown_files)
+                .await?;
+            report.print_summary();
 
-            processor.process_all(&files.mermaid_files).await?;
+            let failed = report.failure_count();
+            if failed > 0 && !keep_going {
+                return Err(DocPilotError::Build {
+                    message: format!(
+                        "Failed to render Mermaid diagrams ({} of {}):\n{}",
+                        failed,
+                        report.outcomes.len(),
+                        report
+                            .failures()
+                            .map(|f| format!("  {}", f))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    ),
+                });
+            }
 
             println!("🎨 Diagram processing complete!");
         }
@@ -0,0 +1,451 @@
+use crate::config::{MarkdownFile, ProjectConfig};
+use crate::diagrams::{DiagramFormat, DiagramProcessor};
+use crate::directives;
+use crate::errors::{DocPilotError, Result};
+use regex::{Captures, Regex};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Rewrites Markdown sources before they are handed to pandoc.
+///
+/// Files that need no changes are passed through untouched. Rewritten files
+/// are staged under `<output_dir>/.build/<format>/` so pandoc still resolves
+/// images relative to the project root.
+///
+/// A referenced diagram that is missing or fails to render stops the build
+/// with the referencing file and line, unless `keep_going` is set in the
+/// config, in which case the link is left as it is and only a warning is
+/// logged.
+pub struct Preprocessor {
+    config: ProjectConfig,
+}
+
+impl Preprocessor {
+    pub fn new(config: ProjectConfig) -> Self {
+        Self { config }
+    }
+
+    pub async fn prepare(&self, files: &[MarkdownFile], format: &str) -> Result<Vec<MarkdownFile>> {
+        let staging_dir = self.config.output_dir.join(".build").join(format);
+        let diagram_format = DiagramFormat::for_output(format);
+        let mut processor: Option<DiagramProcessor> = None;
+        let mut prepared = Vec::with_capacity(files.len());
+
+        for file in files {
+            let original = fs::read_to_string(&file.path)?;
+            let base_dir = file.path.parent().unwrap_or_else(|| Path::new("."));
+            let source = directives::expand(&original, base_dir, &file.path)?;
+
+            let mut diagrams = Vec::new();
+            for reference in Self::find_diagram_references(&source) {
+                let diagram_path = base_dir.join(&reference);
+                let artifact = match self
+                    .resolve_diagram(&diagram_path, diagram_format, &mut processor)
+                    .await
+                {
+                    Ok(artifact) => Some(artifact),
+                    Err(message) => {
+                        self.diagram_failed(
+                            &Self::link_location(&file.path, &original, &reference),
+                            &format!("diagram {}", reference),
+                            &message,
+                        )?;
+                        None
+                    }
+                };
+                diagrams.push((reference, artifact));
+            }
+            let inline = self
+                .resolve_inline_diagrams(
+                    &file.path,
+                    &original,
+                    &source,
+                    diagram_format,
+                    &mut processor,
+                )
+                .await?;
+
+            let rewritten = Self::rewrite_diagram_links(&source, |reference| {
+                diagrams
+                    .iter()
+                    .find(|(r, _)| r == reference)
+                    .and_then(|(_, artifact)| artifact.clone())
+            });
+            let rewritten = Self::replace_inline_diagrams(&rewritten, &inline);
+
+            if rewritten == original {
+                prepared.push(file.clone());
+                continue;
+            }
+
+            fs::create_dir_all(&staging_dir)?;
+            let file_name = file.path.file_name().unwrap_or_default();
+            let staged_path = staging_dir.join(file_name);
+            fs::write(&staged_path, rewritten)?;
+            debug!(
+                "Staged {} as {}",
+                file.path.display(),
+                staged_path.display()
+            );
+
+            let mut staged = file.clone();
+            staged.path = staged_path;
+            prepared.push(staged);
+        }
+
+        Ok(prepared)
+    }
+
+    /// Render the stale `.mmd` diagrams referenced from `files` and their
+    /// inline Mermaid blocks as `format` artifacts, so builds running side by
+    /// side don't render the same file
+    pub async fn render_diagrams(
+        &self,
+        files: &[MarkdownFile],
+        format: DiagramFormat,
+    ) -> Result<()> {
+        let mut processor: Option<DiagramProcessor> = None;
+
+        for file in files {
+            let original = fs::read_to_string(&file.path)?;
+            let base_dir = file.path.parent().unwrap_or_else(|| Path::new("."));
+            let source = directives::expand(&original, base_dir, &file.path)?;
+
+            for reference in Self::find_diagram_references(&source) {
+                if let Err(message) = self
+                    .resolve_diagram(&base_dir.join(&reference), format, &mut processor)
+                    .await
+                {
+                    self.diagram_failed(
+                        &Self::link_location(&file.path, &original, &reference),
+                        &format!("diagram {}", reference),
+                        &message,
+                    )?;
+                }
+            }
+            self.resolve_inline_diagrams(&file.path, &original, &source, format, &mut processor)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Artifacts of the inline Mermaid blocks in `source`, the expanded
+    /// content of `markdown_path`, rendering stale ones. `None` marks a block
+    /// that failed with `keep_going` set.
+    async fn resolve_inline_diagrams(
+        &self,
+        markdown_path: &Path,
+        original: &str,
+        source: &str,
+        format: DiagramFormat,
+        processor: &mut Option<DiagramProcessor>,
+    ) -> Result<Vec<Option<PathBuf>>> {
+        // Directives never produce Mermaid blocks, so the original has the same ones
+        let lines: Vec<usize> = DiagramProcessor::find_inline_diagrams(original)
+            .iter()
+            .map(|diagram| diagram.line)
+            .collect();
+
+        let mut artifacts = Vec::new();
+        for (index, diagram) in DiagramProcessor::find_inline_diagrams(source)
+            .iter()
+            .enumerate()
+        {
+            let artifact =
+                DiagramProcessor::inline_artifact_path(&self.config, markdown_path, index, format);
+            let result = if DiagramProcessor::is_up_to_date(markdown_path, &artifact) {
+                Ok(artifact)
+            } else {
+                processor
+                    .get_or_insert_with(|| DiagramProcessor::new(self.config.clone()))
+                    .ensure_inline_rendered(markdown_path, index, &diagram.code, format)
+                    .await
+                    .map_err(|e| e.to_string())
+            };
+
+            match result {
+                Ok(artifact) => artifacts.push(Some(artifact)),
+                Err(message) => {
+                    let line = lines.get(index).copied().unwrap_or(diagram.line);
+                    self.diagram_failed(
+                        &format!("{}:{}", markdown_path.display(), line),
+                        "inline Mermaid diagram",
+                        &message,
+                    )?;
+                    artifacts.push(None);
+                }
+            }
+        }
+
+        Ok(artifacts)
+    }
+
+    /// Replace inline Mermaid blocks with image links to their `artifacts`.
+    /// Blocks without an artifact are left as they are.
+    pub fn replace_inline_diagrams(content: &str, artifacts: &[Option<PathBuf>]) -> String {
+        if artifacts.iter().all(Option::is_none) {
+            return content.to_string();
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut result: Vec<String> = Vec::with_capacity(lines.len());
+        let mut next = 0;
+        for (diagram, artifact) in DiagramProcessor::find_inline_diagrams(content)
+            .iter()
+            .zip(artifacts)
+        {
+            let Some(artifact) = artifact else {
+                continue;
+            };
+            let fence = lines[diagram.line - 1];
+            let indent = &fence[..fence.len() - fence.trim_start().len()];
+
+            result.extend(lines[next..diagram.line - 1].iter().map(|l| l.to_string()));
+            result.push(format!(
+                "{}![]({})",
+                indent,
+                artifact.to_string_lossy().replace('\\', "/")
+            ));
+            next = diagram.end_line;
+        }
+        result.extend(lines[next..].iter().map(|l| l.to_string()));
+
+        let mut rewritten = result.join("\n");
+        if content.ends_with('\n') {
+            rewritten.push('\n');
+        }
+        rewritten
+    }
+
+    /// Where `reference` is linked from: `file:line`, or just the file
+    fn link_location(markdown_path: &Path, content: &str, reference: &str) -> String {
+        match Self::reference_line(content, reference) {
+            Some(line) => format!("{}:{}", markdown_path.display(), line),
+            None => markdown_path.display().to_string(),
+        }
+    }
+
+    /// Report a diagram at `location` that could not be resolved: an error,
+    /// or a warning with `keep_going`
+    fn diagram_failed(&self, location: &str, what: &str, message: &str) -> Result<()> {
+        let message = format!("{}: {}: {}", location, what, message);
+
+        if self.config.keep_going {
+            warn!("{}", message);
+            Ok(())
+        } else {
+            Err(DocPilotError::Build { message })
+        }
+    }
+
+    /// 1-based line of the first link to `reference` in `content`
+    fn reference_line(content: &str, reference: &str) -> Option<usize> {
+        Self::diagram_link_regex()
+            .captures_iter(content)
+            .find(|cap| &cap[2] == reference)
+            .map(|cap| content[..cap.get(0).unwrap().start()].matches('\n').count() + 1)
+    }
+
+    async fn resolve_diagram(
+        &self,
+        diagram_path: &Path,
+        format: DiagramFormat,
+        processor: &mut Option<DiagramProcessor>,
+    ) -> std::result::Result<PathBuf, String> {
+        if !diagram_path.exists() {
+            return Err(format!("not found: {}", diagram_path.display()));
+        }
+
+        let artifact = DiagramProcessor::artifact_path(&self.config, diagram_path, format);
+        if DiagramProcessor::is_up_to_date(diagram_path, &artifact) {
+            return Ok(artifact);
+        }
+
+        // Starting the renderer is expensive, so only do it once a stale diagram shows up
+        let processor = processor.get_or_insert_with(|| DiagramProcessor::new(self.config.clone()));
+
+        match processor.ensure_rendered(diagram_path, format).await {
+            Ok(artifact) => {
+                info!("Rendered diagram: {}", artifact.display());
+                Ok(artifact)
+            }
+            Err(e) => Err(format!("failed to render: {}", e)),
+        }
+    }
+
+    fn diagram_link_regex() -> Regex {
+        Regex::new(r#"!\[([^\]]*)\]\(\s*([^)\s]+\.mmd)(\s+"[^"]*")?\s*\)"#).unwrap()
+    }
+
+    /// Collect the `.mmd` targets of all Markdown image links
+    pub fn find_diagram_references(content: &str) -> Vec<String> {
+        let mut references: Vec<String> = Vec::new();
+
+        for cap in Self::diagram_link_regex().captures_iter(content) {
+            let reference = cap[2].to_string();
+            if !references.contains(&reference) {
+                references.push(reference);
+            }
+        }
+
+        references
+    }
+
+    /// Replace `.mmd` image targets with the artifact returned by `resolve`.
+    /// Links that cannot be resolved are left as they are.
+    pub fn rewrite_diagram_links<F>(content: &str, resolve: F) -> String
+    where
+        F: Fn(&str) -> Option<PathBuf>,
+    {
+        Self::diagram_link_regex()
+            .replace_all(content, |cap: &Captures| match resolve(&cap[2]) {
+                Some(artifact) => format!(
+                    "![{}]({}{})",
+                    &cap[1],
+                    artifact.to_string_lossy().replace('\\', "/"),
+                    cap.get(3).map_or("", |m| m.as_str())
+                ),
+                None => cap[0].to_string(),
+            })
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_diagram_references() {
+        let content = "![Deploy](diagrams/deploy.mmd)\n![Logo](images/logo.png)\n![Again](diagrams/deploy.mmd \"Title\")";
+
+        let references = Preprocessor::find_diagram_references(content);
+        assert_eq!(references, vec!["diagrams/deploy.mmd".to_string()]);
+    }
+
+    #[test]
+    fn test_rewrite_diagram_links() {
+        let content =
+            "See ![Deployment](diagrams/deploy.mmd \"Flow\"){width=80%} and ![Other](other.mmd).";
+
+        let rewritten = Preprocessor::rewrite_diagram_links(content, |reference| {
+            (reference == "diagrams/deploy.mmd")
+                .then(|| PathBuf::from("output/diagrams/deploy.png"))
+        });
+
+        assert_eq!(
+            rewritten,
+            "See ![Deployment](output/diagrams/deploy.png \"Flow\"){width=80%} and ![Other](other.mmd)."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prepare_uses_fresh_artifact() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = ProjectConfig {
+            output_dir: temp_dir.path().join("output"),
+            ..Default::default()
+        };
+
+        let diagram = temp_dir.path().join("flow.mmd");
+        fs::write(&diagram, "graph TD\n    A --> B").unwrap();
+
+        let artifact = DiagramProcessor::artifact_path(&config, &diagram, DiagramFormat::Svg);
+        fs::create_dir_all(artifact.parent().unwrap()).unwrap();
+        fs::write(&artifact, "<svg/>").unwrap();
+
+        let md_path = temp_dir.path().join("01-intro.md");
+        fs::write(&md_path, "# Intro\n\n![Flow](flow.mmd)\n").unwrap();
+        let file = crate::discovery::MetadataParser::parse_file(&md_path).unwrap();
+
+        let prepared = Preprocessor::new(config.clone())
+            .prepare(&[file], "html")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            prepared[0].path,
+            config.output_dir.join(".build/html/01-intro.md")
+        );
+        let staged = fs::read_to_string(&prepared[0].path).unwrap();
+        assert!(staged.contains(&format!("![Flow]({})", artifact.display())));
+    }
+
+    #[tokio::test]
+    async fn test_prepare_reports_missing_diagram_location() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = ProjectConfig {
+            output_dir: temp_dir.path().join("output"),
+            ..Default::default()
+        };
+
+        let md_path = temp_dir.path().join("01-intro.md");
+        fs::write(&md_path, "# Intro\n\nSee ![Flow](missing.mmd).\n").unwrap();
+        let file = crate::discovery::MetadataParser::parse_file(&md_path).unwrap();
+
+        let error = Preprocessor::new(config.clone())
+            .prepare(std::slice::from_ref(&file), "html")
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains(&format!("{}:3: diagram missing.mmd", md_path.display())));
+
+        // With keep_going the link is left alone
+        let keep_going = ProjectConfig {
+            keep_going: true,
+            ..config
+        };
+        let prepared = Preprocessor::new(keep_going)
+            .prepare(&[file], "html")
+            .await
+            .unwrap();
+        assert_eq!(prepared[0].path, md_path);
+    }
+
+    #[test]
+    fn test_replace_inline_diagrams() {
+        let content = "# Flow\n\n```mermaid\ngraph TD\n```\n\n- Item\n\n  ```mermaid\ngraph LR\n  ```\n\n```mermaid\nbroken\n```\n";
+        let artifacts = vec![
+            Some(PathBuf::from("output/diagrams/01-intro-diagram-1.svg")),
+            Some(PathBuf::from("output/diagrams/01-intro-diagram-2.svg")),
+            None,
+        ];
+
+        assert_eq!(
+            Preprocessor::replace_inline_diagrams(content, &artifacts),
+            "# Flow\n\n![](output/diagrams/01-intro-diagram-1.svg)\n\n- Item\n\n  ![](output/diagrams/01-intro-diagram-2.svg)\n\n```mermaid\nbroken\n```\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prepare_links_inline_diagram_artifacts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = ProjectConfig {
+            output_dir: temp_dir.path().join("output"),
+            ..Default::default()
+        };
+
+        let md_path = temp_dir.path().join("01-intro.md");
+        fs::write(
+            &md_path,
+            "# Intro\n\n```mermaid\ngraph TD\n    A --> B\n```\n",
+        )
+        .unwrap();
+        let artifact =
+            DiagramProcessor::inline_artifact_path(&config, &md_path, 0, DiagramFormat::Svg);
+        fs::create_dir_all(artifact.parent().unwrap()).unwrap();
+        fs::write(&artifact, "<svg/>").unwrap();
+        let file = crate::discovery::MetadataParser::parse_file(&md_path).unwrap();
+
+        let prepared = Preprocessor::new(config)
+            .prepare(&[file], "html")
+            .await
+            .unwrap();
+
+        let staged = fs::read_to_string(&prepared[0].path).unwrap();
+        assert_eq!(staged, format!("# Intro\n\n![]({})\n", artifact.display()));
+    }
+}
@@ -0,0 +1,368 @@
+use crate::builders;
+use crate::config::{MarkdownFile, ProjectConfig};
+use crate::discovery::MetadataParser;
+use crate::errors::Result;
+use crate::templates::{TemplateManager, EISVOGEL_NAME};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+const SAMPLE_SETUP: &str = r#"---
+title: "Template Preview"
+subtitle: "A sample document for comparing templates"
+author: ["docPilot"]
+date: "2024-01-01"
+lang: "en"
+toc: true
+titlepage: true
+---
+"#;
+
+const SAMPLE_CONTENT: &str = r#"# Introduction
+
+This sample shows how a template renders **headings**, *emphasis*, `inline code`,
+[links](https://pandoc.org) and footnotes.[^note]
+
+[^note]: Footnotes appear at the bottom of the page or the end of the document.
+
+## Lists and quotes
+
+- First item
+- Second item with a nested list
+  1. Numbered
+  2. Items
+
+> Quotations are set apart from the body text.
+
+## Tables and code
+
+| Template | Formats   | Notes            |
+|----------|-----------|------------------|
+| Alpha    | PDF, HTML | Corporate layout |
+| Beta     | DOCX      | Reference styles |
+
+: A sample table
+
+```rust
+fn main() {
+    println!("Hello from docPilot");
+}
+```
+
+# Conclusion
+
+Equations such as $E = mc^2$ and display math
+
+$$\int_0^1 x^2 \, dx = \frac{1}{3}$$
+
+show the math typography.
+"#;
+
+/// One template rendered to one format
+#[derive(Debug, Clone)]
+pub struct PreviewEntry {
+    pub template: String,
+    pub format: String,
+    pub output: PathBuf,
+    /// First page as an image, if it could be rendered
+    pub thumbnail: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+/// Builds one document with every installed template and collects the
+/// results on a contact sheet at `<output_dir>/preview/index.html`
+pub struct TemplatePreview {
+    config: ProjectConfig,
+}
+
+impl TemplatePreview {
+    pub fn new(config: ProjectConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn preview_dir(&self) -> PathBuf {
+        self.config.output_dir.join("preview")
+    }
+
+    /// Write the bundled sample document and parse it
+    pub fn write_sample(&self) -> Result<Vec<MarkdownFile>> {
+        let sample_dir = self.preview_dir().join("sample");
+        fs::create_dir_all(&sample_dir)?;
+
+        let mut files = Vec::new();
+        for (name, content) in [
+            ("00-setup.md", SAMPLE_SETUP),
+            ("01-sample.md", SAMPLE_CONTENT),
+        ] {
+            let path = sample_dir.join(name);
+            fs::write(&path, content)?;
+            files.push(MetadataParser::parse_file(&path)?);
+        }
+        Ok(files)
+    }
+
+    /// Template names and the formats each one provides, restricted to `formats`
+    pub fn templates(&self, formats: &[String]) -> Result<Vec<(String, Vec<String>)>> {
+        let manager = TemplateManager::new(self.config.templates_dir.clone());
+        let mut templates: Vec<(String, Vec<String>)> = manager
+            .list_packages()?
+            .iter()
+            .map(|package| {
+                let supported = package
+                    .formats()
+                    .into_iter()
+                    .filter(|format| formats.iter().any(|f| f == format))
+                    .map(|format| format.to_string())
+                    .collect();
+                (package.name().to_string(), supported)
+            })
+            .filter(|(_, supported): &(String, Vec<String>)| !supported.is_empty())
+            .collect();
+
+        // The bundled Eisvogel copy is always available for PDF
+        if formats.iter().any(|f| f == "pdf") && !templates.iter().any(|(n, _)| n == EISVOGEL_NAME)
+        {
+            templates.push((EISVOGEL_NAME.to_string(), vec!["pdf".to_string()]));
+            templates.sort();
+        }
+
+        Ok(templates)
+    }
+
+    pub async fn generate(
+        &self,
+        files: &[MarkdownFile],
+        formats: &[String],
+    ) -> Result<(PathBuf, Vec<PreviewEntry>)> {
+        let preview_dir = self.preview_dir();
+        fs::create_dir_all(&preview_dir)?;
+
+        let mut entries = Vec::new();
+        for (template, template_formats) in self.templates(formats)? {
+            let template_dir = preview_dir.join(&template);
+            fs::create_dir_all(&template_dir)?;
+
+            for format in template_formats {
+                info!("Previewing template {} ({})", template, format);
+
+                let mut config = self.config.clone();
+                config.templates.set(&format, Some(template.clone()));
+                let output = template_dir.join(format!("{}.{}", template, format));
+
+                let entry = match builders::build_format(&config, &format, files, &output).await {
+                    Ok(()) => PreviewEntry {
+                        thumbnail: (format == "pdf")
+                            .then(|| Self::render_thumbnail(&output, &template_dir))
+                            .flatten(),
+                        template: template.clone(),
+                        format,
+                        output,
+                        error: None,
+                    },
+                    Err(e) => {
+                        warn!("Preview of {} ({}) failed: {}", template, format, e);
+                        PreviewEntry {
+                            template: template.clone(),
+                            format,
+                            output,
+                            thumbnail: None,
+                            error: Some(e.to_string()),
+                        }
+                    }
+                };
+                entries.push(entry);
+            }
+        }
+
+        let index = preview_dir.join("index.html");
+        fs::write(&index, Self::contact_sheet(&entries, &preview_dir))?;
+        Ok((index, entries))
+    }
+
+    /// First page of a PDF as PNG, via pdftoppm when it is installed
+    fn render_thumbnail(pdf: &Path, dir: &Path) -> Option<PathBuf> {
+        if which::which("pdftoppm").is_err() {
+            debug!(
+                "pdftoppm not found, skipping thumbnail for {}",
+                pdf.display()
+            );
+            return None;
+        }
+
+        let prefix = dir.join("page-1");
+        let output = Command::new("pdftoppm")
+            .args([
+                "-png",
+                "-f",
+                "1",
+                "-l",
+                "1",
+                "-scale-to",
+                "480",
+                "-singlefile",
+            ])
+            .arg(pdf)
+            .arg(&prefix)
+            .output()
+            .ok()?;
+
+        let thumbnail = prefix.with_extension("png");
+        (output.status.success() && thumbnail.exists()).then_some(thumbnail)
+    }
+
+    pub fn contact_sheet(entries: &[PreviewEntry], preview_dir: &Path) -> String {
+        let relative = |path: &Path| {
+            path.strip_prefix(preview_dir)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/")
+        };
+
+        let mut cards = String::new();
+        for entry in entries {
+            let href = relative(&entry.output);
+            let preview = match (&entry.error, &entry.thumbnail) {
+                (Some(error), _) => format!("<pre class=\"error\">{}</pre>", html_escape(error)),
+                (None, Some(thumbnail)) => format!(
+                    "<a href=\"{}\"><img src=\"{}\" alt=\"First page of {}\"></a>",
+                    href,
+                    relative(thumbnail),
+                    html_escape(&entry.template)
+                ),
+                (None, None) if entry.format == "html" => format!(
+                    "<div class=\"frame\"><iframe src=\"{}\" loading=\"lazy\" tabindex=\"-1\"></iframe></div>",
+                    href
+                ),
+                (None, None) => format!(
+                    "<a class=\"placeholder\" href=\"{}\">Open {}</a>",
+                    href,
+                    entry.format.to_uppercase()
+                ),
+            };
+
+            cards.push_str(&format!(
+                "<figure>{}<figcaption><strong>{}</strong> · <a href=\"{}\">{}</a></figcaption></figure>\n",
+                preview,
+                html_escape(&entry.template),
+                href,
+                entry.format.to_uppercase()
+            ));
+        }
+
+        if entries.is_empty() {
+            cards.push_str("<p>No templates found for the requested formats.</p>\n");
+        }
+
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>docPilot template preview</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 2rem; background: #f4f4f4; }}
+main {{ display: grid; grid-template-columns: repeat(auto-fill, minmax(260px, 1fr)); gap: 1.5rem; }}
+figure {{ margin: 0; background: #fff; padding: 0.75rem; box-shadow: 0 1px 4px rgba(0,0,0,.15); }}
+figure img {{ width: 100%; display: block; }}
+.frame {{ height: 340px; overflow: hidden; }}
+.frame iframe {{ width: 400%; height: 400%; border: 0; transform: scale(.25); transform-origin: 0 0; pointer-events: none; }}
+.placeholder {{ display: flex; height: 340px; align-items: center; justify-content: center; background: #eee; }}
+.error {{ height: 340px; overflow: auto; white-space: pre-wrap; color: #a00; font-size: .75rem; }}
+figcaption {{ margin-top: .5rem; }}
+</style>
+</head>
+<body>
+<h1>Template preview</h1>
+<main>
+{}</main>
+</body>
+</html>
+"#,
+            cards
+        )
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_templates_include_bundled_eisvogel() {
+        let temp_dir = TempDir::new().unwrap();
+        let templates_dir = temp_dir.path().join("templates");
+        fs::create_dir_all(&templates_dir).unwrap();
+        fs::write(templates_dir.join("memo.html"), "$body$").unwrap();
+        fs::write(templates_dir.join("memo.docx"), "docx").unwrap();
+
+        let preview = TemplatePreview::new(ProjectConfig {
+            templates_dir,
+            ..Default::default()
+        });
+        let formats = vec!["pdf".to_string(), "html".to_string()];
+
+        assert_eq!(
+            preview.templates(&formats).unwrap(),
+            vec![
+                ("eisvogel".to_string(), vec!["pdf".to_string()]),
+                ("memo".to_string(), vec!["html".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_sample() {
+        let temp_dir = TempDir::new().unwrap();
+        let preview = TemplatePreview::new(ProjectConfig {
+            output_dir: temp_dir.path().join("output"),
+            ..Default::default()
+        });
+
+        let files = preview.write_sample().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].metadata.title.as_deref(), Some("Template Preview"));
+    }
+
+    #[test]
+    fn test_contact_sheet() {
+        let preview_dir = PathBuf::from("output/preview");
+        let entries = vec![
+            PreviewEntry {
+                template: "eisvogel".to_string(),
+                format: "pdf".to_string(),
+                output: preview_dir.join("eisvogel/eisvogel.pdf"),
+                thumbnail: Some(preview_dir.join("eisvogel/page-1.png")),
+                error: None,
+            },
+            PreviewEntry {
+                template: "memo".to_string(),
+                format: "html".to_string(),
+                output: preview_dir.join("memo/memo.html"),
+                thumbnail: None,
+                error: None,
+            },
+            PreviewEntry {
+                template: "broken".to_string(),
+                format: "pdf".to_string(),
+                output: preview_dir.join("broken/broken.pdf"),
+                thumbnail: None,
+                error: Some("Pandoc failed: <oops>".to_string()),
+            },
+        ];
+
+        let html = TemplatePreview::contact_sheet(&entries, &preview_dir);
+        assert!(html.contains("<img src=\"eisvogel/page-1.png\""));
+        assert!(html.contains("<iframe src=\"memo/memo.html\""));
+        assert!(html.contains("Pandoc failed: &lt;oops&gt;"));
+    }
+}
@@ -0,0 +1,477 @@
+//! `docpilot serve`: a small HTTP server for the output directory that
+//! reloads the browser after every watch rebuild.
+//!
+//! Pages get a script injected that listens on [`EVENTS_PATH`] for
+//! server-sent events: `reload` after a successful build, `failed` with the
+//! error message and `building` when a rebuild starts.
+
+use crate::builders;
+use crate::config::ProjectConfig;
+use crate::errors::{DocPilotError, Result};
+use crate::watcher::{BuildStatus, FileWatcher};
+use std::path::{Component, Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tracing::{debug, warn};
+
+/// Server-sent events endpoint used by the injected script
+pub const EVENTS_PATH: &str = "/__docpilot/events";
+
+const LIVE_RELOAD_SCRIPT: &str = include_str!("../assets/serve/live-reload.js");
+
+/// Longest request head we accept; the server only answers simple GETs
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+pub struct PreviewServer {
+    config: ProjectConfig,
+    config_file: Option<PathBuf>,
+    port: u16,
+    pdf: bool,
+}
+
+impl PreviewServer {
+    pub fn new(config: ProjectConfig) -> Self {
+        Self {
+            config,
+            config_file: None,
+            port: 3000,
+            pdf: false,
+        }
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Also keep the PDF up to date and serve it
+    pub fn with_pdf(mut self, pdf: bool) -> Self {
+        self.pdf = pdf;
+        self
+    }
+
+    /// Reload the config from this file instead of the default names
+    pub fn with_config_file(mut self, config_file: Option<PathBuf>) -> Self {
+        self.config_file = config_file;
+        self
+    }
+
+    /// Serve the output directory and rebuild on changes until interrupted
+    pub async fn run(self) -> Result<()> {
+        let listener = TcpListener::bind(("127.0.0.1", self.port))
+            .await
+            .map_err(|e| DocPilotError::Build {
+                message: format!("Cannot listen on port {}: {}", self.port, e),
+            })?;
+        let address = listener.local_addr()?;
+
+        let html = file_name(&builders::output_path(&self.config, "html"));
+        let site = Site {
+            root: self.config.output_dir.clone(),
+            index: html,
+        };
+
+        println!("🌐 Preview: http://{}/", address);
+        let mut formats = vec!["html".to_string()];
+        if self.pdf {
+            let pdf = file_name(&builders::output_path(&self.config, "pdf"));
+            println!("📄 PDF:     http://{}/{}", address, pdf);
+            formats.push("pdf".to_string());
+        }
+
+        let (status, receiver) = watch::channel(BuildStatus::Building);
+        tokio::spawn(accept_loop(listener, site, receiver));
+
+        FileWatcher::new(self.config)
+            .with_config_file(self.config_file)
+            .with_status(status)
+            .watch_and_build(&formats)
+            .await
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// The files the preview serves
+#[derive(Debug, Clone)]
+struct Site {
+    root: PathBuf,
+    /// HTML output served for `/`
+    index: String,
+}
+
+impl Site {
+    /// Map a request path to a file below the root; `None` for paths that
+    /// would leave it
+    fn resolve(&self, request_path: &str) -> Option<PathBuf> {
+        let decoded = percent_decode(request_path);
+        let relative = decoded.trim_start_matches('/');
+        if relative.is_empty() {
+            return Some(self.root.join(&self.index));
+        }
+
+        let relative = Path::new(relative);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+
+        Some(self.root.join(relative))
+    }
+}
+
+async fn accept_loop(listener: TcpListener, site: Site, status: watch::Receiver<BuildStatus>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let site = site.clone();
+                let status = status.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = respond(stream, &site, status).await {
+                        debug!("Preview request failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => warn!("Preview server error: {}", e),
+        }
+    }
+}
+
+async fn respond(
+    mut stream: TcpStream,
+    site: &Site,
+    status: watch::Receiver<BuildStatus>,
+) -> std::io::Result<()> {
+    let head = read_request_head(&mut stream).await?;
+    let Some((method, target)) = parse_request_line(&head) else {
+        return write_response(
+            &mut stream,
+            "400 Bad Request",
+            "text/plain",
+            b"Bad Request",
+            false,
+        )
+        .await;
+    };
+    let head_only = method == "HEAD";
+    if method != "GET" && !head_only {
+        return write_response(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method Not Allowed",
+            false,
+        )
+        .await;
+    }
+
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    if path == EVENTS_PATH {
+        return stream_events(stream, status).await;
+    }
+
+    let Some(file) = site.resolve(path) else {
+        return write_response(
+            &mut stream,
+            "404 Not Found",
+            "text/plain",
+            b"Not Found",
+            head_only,
+        )
+        .await;
+    };
+
+    let is_index = file == site.root.join(&site.index);
+    match tokio::fs::read(&file).await {
+        Ok(body) if content_type(&file) == "text/html; charset=utf-8" => {
+            let page = inject_script(&String::from_utf8_lossy(&body));
+            write_response(
+                &mut stream,
+                "200 OK",
+                content_type(&file),
+                page.as_bytes(),
+                head_only,
+            )
+            .await
+        }
+        Ok(body) => {
+            write_response(&mut stream, "200 OK", content_type(&file), &body, head_only).await
+        }
+        // The first build has not finished yet; the script reloads once it has
+        Err(_) if is_index => {
+            let page = inject_script(
+                "<!DOCTYPE html>\n<html><head><title>docpilot</title></head>\
+                 <body><p>Building preview…</p></body></html>\n",
+            );
+            write_response(
+                &mut stream,
+                "200 OK",
+                "text/html; charset=utf-8",
+                page.as_bytes(),
+                head_only,
+            )
+            .await
+        }
+        Err(_) => {
+            write_response(
+                &mut stream,
+                "404 Not Found",
+                "text/plain",
+                b"Not Found",
+                head_only,
+            )
+            .await
+        }
+    }
+}
+
+async fn read_request_head(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") && head.len() < MAX_REQUEST_HEAD {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Method and target of an HTTP/1.x request line
+fn parse_request_line(head: &str) -> Option<(&str, &str)> {
+    let mut parts = head.lines().next()?.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    parts
+        .next()
+        .filter(|version| version.starts_with("HTTP/1."))?;
+    Some((method, target))
+}
+
+async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+    head_only: bool,
+) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    if !head_only {
+        stream.write_all(body).await?;
+    }
+    stream.flush().await
+}
+
+async fn stream_events(
+    mut stream: TcpStream,
+    mut status: watch::Receiver<BuildStatus>,
+) -> std::io::Result<()> {
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+        )
+        .await?;
+
+    // A page opened while a build is running or after one failed shows that
+    // right away; a reload now would only loop
+    let current = status.borrow_and_update().clone();
+    if current != BuildStatus::Succeeded {
+        stream.write_all(sse_event(&current).as_bytes()).await?;
+    }
+
+    // Ends when the watcher stops or the browser goes away
+    while status.changed().await.is_ok() {
+        let event = sse_event(&status.borrow_and_update().clone());
+        stream.write_all(event.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+/// Encode a build status as a server-sent event
+fn sse_event(status: &BuildStatus) -> String {
+    match status {
+        BuildStatus::Building => "event: building\ndata:\n\n".to_string(),
+        BuildStatus::Succeeded => "event: reload\ndata:\n\n".to_string(),
+        BuildStatus::Failed(message) => {
+            let mut event = "event: failed\n".to_string();
+            for line in message.lines() {
+                event.push_str("data: ");
+                event.push_str(line);
+                event.push('\n');
+            }
+            event.push('\n');
+            event
+        }
+    }
+}
+
+/// Add the live-reload script before `</body>`, or at the end of the page
+fn inject_script(html: &str) -> String {
+    let script = format!("<script>\n{}</script>\n", LIVE_RELOAD_SCRIPT);
+    match html.to_ascii_lowercase().rfind("</body>") {
+        Some(index) => format!("{}{}{}", &html[..index], script, &html[index..]),
+        None => format!("{}{}", html, script),
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "epub" => "application/epub+zip",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Decode `%XX` escapes; invalid escapes are kept as they are
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escape = (bytes[i] == b'%')
+            .then(|| input.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn site(root: &Path) -> Site {
+        Site {
+            root: root.to_path_buf(),
+            index: "document.html".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_stays_in_output_dir() {
+        let site = site(Path::new("output"));
+        assert_eq!(
+            site.resolve("/"),
+            Some(PathBuf::from("output/document.html"))
+        );
+        assert_eq!(
+            site.resolve("/my%20doc.pdf"),
+            Some(PathBuf::from("output/my doc.pdf"))
+        );
+        assert_eq!(site.resolve("/../docpilot.yml"), None);
+        assert_eq!(site.resolve("/%2e%2e/docpilot.yml"), None);
+    }
+
+    #[test]
+    fn test_inject_script_and_events() {
+        let page = inject_script("<html><BODY><p>Hi</p></BODY></html>");
+        assert!(page.contains("<p>Hi</p><script>"));
+        assert!(page.ends_with("</script>\n</BODY></html>"));
+
+        assert_eq!(
+            sse_event(&BuildStatus::Succeeded),
+            "event: reload\ndata:\n\n"
+        );
+        assert_eq!(
+            sse_event(&BuildStatus::Failed("Pandoc failed:\nline 3".to_string())),
+            "event: failed\ndata: Pandoc failed:\ndata: line 3\n\n"
+        );
+        assert_eq!(
+            parse_request_line("GET /index.html HTTP/1.1\r\nHost: x\r\n\r\n"),
+            Some(("GET", "/index.html"))
+        );
+        assert_eq!(parse_request_line("garbage"), None);
+    }
+
+    #[tokio::test]
+    async fn test_serves_pages_and_reload_events() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("document.html"),
+            "<html><body>Preview</body></html>",
+        )
+        .unwrap();
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (status, receiver) = watch::channel(BuildStatus::Succeeded);
+        tokio::spawn(accept_loop(listener, site(temp_dir.path()), receiver));
+
+        let request = |request: &'static str| async move {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let page = request("GET / HTTP/1.1\r\n\r\n").await;
+        assert!(page.starts_with("HTTP/1.1 200 OK"));
+        assert!(page.contains("Preview<script>"));
+        assert!(page.contains(EVENTS_PATH));
+
+        let missing = request("GET /../secret HTTP/1.1\r\n\r\n").await;
+        assert!(missing.starts_with("HTTP/1.1 404"));
+
+        let mut events = TcpStream::connect(address).await.unwrap();
+        events
+            .write_all(format!("GET {} HTTP/1.1\r\n\r\n", EVENTS_PATH).as_bytes())
+            .await
+            .unwrap();
+        let mut buffer = [0u8; 512];
+        let read = events.read(&mut buffer).await.unwrap();
+        assert!(String::from_utf8_lossy(&buffer[..read]).contains("text/event-stream"));
+
+        status.send_replace(BuildStatus::Failed("Pandoc failed".to_string()));
+        let mut received = String::new();
+        while !received.contains("\n\n") {
+            let read = events.read(&mut buffer).await.unwrap();
+            received.push_str(&String::from_utf8_lossy(&buffer[..read]));
+        }
+        assert_eq!(received, "event: failed\ndata: Pandoc failed\n\n");
+    }
+}
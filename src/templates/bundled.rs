@@ -0,0 +1,142 @@
+//! The Eisvogel LaTeX template, embedded so `init` and PDF builds work offline.
+
+use super::{TemplateManifest, MANIFEST_FILE};
+use crate::errors::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+pub const EISVOGEL_NAME: &str = "eisvogel";
+
+/// Eisvogel 3 single-file template, see `assets/templates/eisvogel/LICENSE`
+pub const EISVOGEL_LATEX: &str = include_str!("../../assets/templates/eisvogel/eisvogel.latex");
+
+/// Release tag the bundled copy was taken from; the checksum pins the exact file
+pub const EISVOGEL_VERSION: &str = "3.2.0";
+
+/// SHA-256 of [`EISVOGEL_LATEX`], recorded in the generated manifest
+pub const EISVOGEL_SHA256: &str =
+    "3a355b2c5db3c2742b780449e3a7e01e79636edeff2d1f03985b13b77f751fa2";
+
+/// Release archive fetched by `templates update eisvogel`
+pub const EISVOGEL_RELEASE_URL: &str =
+    "https://github.com/Wandmalfarbe/pandoc-latex-template/releases/latest/download/Eisvogel.zip";
+
+const EISVOGEL_FILE: &str = "eisvogel.latex";
+
+pub fn eisvogel_manifest() -> TemplateManifest {
+    TemplateManifest {
+        name: EISVOGEL_NAME.to_string(),
+        description: Some(format!(
+            "Eisvogel LaTeX template bundled with docpilot (sha256 {})",
+            EISVOGEL_SHA256
+        )),
+        version: Some(EISVOGEL_VERSION.to_string()),
+        source: Some("https://github.com/Wandmalfarbe/pandoc-latex-template".to_string()),
+        formats: BTreeMap::from([("pdf".to_string(), PathBuf::from(EISVOGEL_FILE))]),
+        variables: [
+            "titlepage",
+            "titlepage-color",
+            "titlepage-text-color",
+            "titlepage-rule-color",
+            "titlepage-logo",
+            "titlepage-background",
+            "page-background",
+            "logo-width",
+            "toc-own-page",
+            "disable-header-and-footer",
+            "header-left",
+            "header-center",
+            "header-right",
+            "footer-left",
+            "footer-center",
+            "footer-right",
+            "listings-disable-line-numbers",
+            "code-block-font-size",
+            "footnotes-pretty",
+            "book",
+            "watermark",
+        ]
+        .iter()
+        .map(|v| v.to_string())
+        .collect(),
+        latex_packages: [
+            "adjustbox",
+            "babel",
+            "background",
+            "bookmark",
+            "caption",
+            "csquotes",
+            "footmisc",
+            "footnotebackref",
+            "fvextra",
+            "listings",
+            "mdframed",
+            "pagecolor",
+            "scrlayer-scrpage",
+            "sourcecodepro",
+            "sourcesanspro",
+            "titling",
+            "xcolor",
+            "xurl",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect(),
+    }
+}
+
+/// Files of the bundled package, relative to the templates directory
+pub fn eisvogel_files() -> Result<Vec<(PathBuf, String)>> {
+    let package_dir = PathBuf::from(EISVOGEL_NAME);
+    Ok(vec![
+        (package_dir.join(EISVOGEL_FILE), EISVOGEL_LATEX.to_string()),
+        (
+            package_dir.join(MANIFEST_FILE),
+            serde_yaml::to_string(&eisvogel_manifest())?,
+        ),
+    ])
+}
+
+/// Write the bundled package to `<dir>/eisvogel/` and return the template path.
+/// Existing files are only rewritten when their content differs.
+pub fn write_eisvogel(dir: &Path) -> Result<PathBuf> {
+    let template_path = dir.join(EISVOGEL_NAME).join(EISVOGEL_FILE);
+
+    if fs::read_to_string(&template_path).ok().as_deref() == Some(EISVOGEL_LATEX) {
+        return Ok(template_path);
+    }
+
+    for (file, content) in eisvogel_files()? {
+        let path = dir.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+    }
+    debug!(
+        "Wrote bundled Eisvogel template: {}",
+        template_path.display()
+    );
+
+    Ok(template_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bundled_checksum_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_eisvogel(temp_dir.path()).unwrap();
+
+        assert_eq!(
+            super::super::install::checksum(&path).unwrap(),
+            EISVOGEL_SHA256
+        );
+        assert!(path.with_file_name(MANIFEST_FILE).exists());
+    }
+}
@@ -0,0 +1,575 @@
+//! Fetching templates from local paths, archives, git repositories and URLs,
+//! and pinning them in `templates.lock`.
+
+use crate::errors::{DocPilotError, Result};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, info};
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+/// Lock file name, stored next to the templates directory
+pub const LOCK_FILE: &str = "templates.lock";
+
+/// Where a template is installed from
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateSource {
+    /// A single template file
+    File(PathBuf),
+    /// A package directory
+    Directory(PathBuf),
+    /// A local `.zip`, `.tar`, `.tar.gz` or `.tgz` archive
+    Archive(PathBuf),
+    /// A git repository, optionally at a branch, tag or commit
+    Git {
+        url: String,
+        reference: Option<String>,
+    },
+    /// A file or archive served over HTTP(S)
+    Url(String),
+}
+
+impl TemplateSource {
+    /// Interpret a source string from the command line or lock file.
+    ///
+    /// Git sources are recognised by a `git+` prefix, a `.git` suffix or an
+    /// scp-style `git@host:` address; a `#ref` suffix selects the revision.
+    pub fn parse(source: &str, reference: Option<&str>) -> Self {
+        let (location, fragment) = match source.rsplit_once('#') {
+            Some((location, fragment)) if !fragment.is_empty() => (location, Some(fragment)),
+            _ => (source, None),
+        };
+        let reference = reference.or(fragment).map(|r| r.to_string());
+
+        let is_git = location.starts_with("git+")
+            || location.starts_with("git@")
+            || location.starts_with("ssh://")
+            || location.ends_with(".git");
+
+        if is_git {
+            return TemplateSource::Git {
+                url: location.trim_start_matches("git+").to_string(),
+                reference,
+            };
+        }
+
+        if source.starts_with("http://") || source.starts_with("https://") {
+            return TemplateSource::Url(source.to_string());
+        }
+
+        let path = PathBuf::from(source);
+        if path.is_dir() {
+            TemplateSource::Directory(path)
+        } else if is_archive(source) {
+            TemplateSource::Archive(path)
+        } else {
+            TemplateSource::File(path)
+        }
+    }
+
+    /// Name used when neither the user nor a manifest provides one
+    pub fn default_name(&self) -> String {
+        let last_segment = |s: &str| {
+            s.trim_end_matches('/')
+                .rsplit(['/', ':'])
+                .next()
+                .unwrap_or(s)
+                .split(['?', '#'])
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        let name = match self {
+            TemplateSource::File(path)
+            | TemplateSource::Directory(path)
+            | TemplateSource::Archive(path) => path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            TemplateSource::Git { url, .. } => last_segment(url),
+            TemplateSource::Url(url) => last_segment(url),
+        };
+
+        strip_known_extension(&name).to_string()
+    }
+}
+
+pub fn is_archive(name: &str) -> bool {
+    let name = name.to_lowercase();
+    [".zip", ".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+fn strip_known_extension(name: &str) -> &str {
+    for ext in [".tar.gz", ".tgz", ".tar", ".zip", ".git"] {
+        if let Some(stripped) = name.strip_suffix(ext) {
+            return stripped;
+        }
+    }
+    name
+}
+
+/// One pinned template in `templates.lock`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedTemplate {
+    pub name: String,
+    pub source: String,
+    /// Requested git branch or tag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// Resolved git commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemplateLock {
+    #[serde(default)]
+    pub templates: Vec<LockedTemplate>,
+}
+
+impl TemplateLock {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+        serde_yaml::from_str(&content).map_err(|e| DocPilotError::Config {
+            message: format!("Invalid {}: {}", path.display(), e),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(self)?;
+        fs::write(
+            path,
+            format!(
+                "# Generated by docpilot. Commit this file to pin template versions.\n{}",
+                content
+            ),
+        )?;
+        Ok(())
+    }
+
+    pub fn find(&self, name: &str) -> Option<&LockedTemplate> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+
+    pub fn find_by_source(&self, source: &str) -> Option<&LockedTemplate> {
+        self.templates.iter().find(|t| t.source == source)
+    }
+
+    /// Insert or replace the entry with the same name
+    pub fn record(&mut self, entry: LockedTemplate) {
+        match self.templates.iter_mut().find(|t| t.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => self.templates.push(entry),
+        }
+        self.templates.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+}
+
+/// Template material fetched into a staging directory
+pub struct Fetched {
+    /// A single file or a package directory
+    pub path: PathBuf,
+    /// Resolved git commit, if fetched from git
+    pub commit: Option<String>,
+}
+
+/// Download or clone `source` below `staging`; local sources are used in place
+pub async fn fetch(source: &TemplateSource, staging: &Path) -> Result<Fetched> {
+    match source {
+        TemplateSource::Url(url) => download(url, staging).await,
+        _ => fetch_local(source, staging),
+    }
+}
+
+/// Fetch `source` as a directory tree, extracting archives below `staging`
+pub async fn fetch_directory(source: &TemplateSource, staging: &Path) -> Result<PathBuf> {
+    let fetched = fetch(source, staging).await?;
+
+    if fetched.path.is_dir() {
+        Ok(fetched.path)
+    } else if is_archive(&fetched.path.to_string_lossy()) {
+        let extracted = staging.join("extracted");
+        extract_archive(&fetched.path, &extracted)?;
+        package_root(&extracted)
+    } else {
+        Err(DocPilotError::Config {
+            message: format!(
+                "{} is not a directory, archive or git repository",
+                fetched.path.display()
+            ),
+        })
+    }
+}
+
+/// Fetch any source that needs no network access beyond git
+pub fn fetch_local(source: &TemplateSource, staging: &Path) -> Result<Fetched> {
+    match source {
+        TemplateSource::File(path)
+        | TemplateSource::Directory(path)
+        | TemplateSource::Archive(path) => {
+            if !path.exists() {
+                return Err(DocPilotError::Build {
+                    message: format!("Template file not found: {}", path.display()),
+                });
+            }
+            Ok(Fetched {
+                path: path.clone(),
+                commit: None,
+            })
+        }
+        TemplateSource::Git { url, reference } => {
+            let checkout = staging.join(source.default_name());
+            clone_repository(url, reference.as_deref(), &checkout)?;
+            let commit = git_output(&checkout, &["rev-parse", "HEAD"]);
+            Ok(Fetched {
+                path: checkout,
+                commit,
+            })
+        }
+        TemplateSource::Url(url) => Err(DocPilotError::Build {
+            message: format!("{} must be downloaded first", url),
+        }),
+    }
+}
+
+async fn download(url: &str, staging: &Path) -> Result<Fetched> {
+    info!("Downloading template: {}", url);
+
+    let response = reqwest::get(url).await.map_err(|e| DocPilotError::Build {
+        message: format!("Failed to download template: {}", e),
+    })?;
+
+    if !response.status().is_success() {
+        return Err(DocPilotError::Build {
+            message: format!("Failed to download {}: HTTP {}", url, response.status()),
+        });
+    }
+
+    let bytes = response.bytes().await.map_err(|e| DocPilotError::Build {
+        message: format!("Failed to read template content: {}", e),
+    })?;
+
+    let file_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|u| u.trim_end_matches('/').rsplit('/').next())
+        .filter(|n| !n.is_empty() && !n.contains(':'))
+        .unwrap_or("template");
+
+    let path = staging.join(file_name);
+    fs::write(&path, &bytes)?;
+    Ok(Fetched { path, commit: None })
+}
+
+fn clone_repository(url: &str, reference: Option<&str>, target: &Path) -> Result<()> {
+    info!("Cloning template repository: {}", url);
+
+    let run = |args: &[&str], dir: Option<&Path>| {
+        let mut command = Command::new("git");
+        command.args(args);
+        if let Some(dir) = dir {
+            command.current_dir(dir);
+        }
+        command.output().map_err(|e| DocPilotError::Dependency {
+            tool: "git".to_string(),
+            hint: format!("Failed to run git: {}", e),
+        })
+    };
+
+    let target_str = target.to_string_lossy().to_string();
+
+    // Branches and tags can be fetched shallowly; commits need a full clone
+    if let Some(reference) = reference {
+        let output = run(
+            &[
+                "clone",
+                "--depth",
+                "1",
+                "--branch",
+                reference,
+                url,
+                &target_str,
+            ],
+            None,
+        )?;
+        if output.status.success() {
+            return Ok(());
+        }
+        debug!(
+            "Shallow clone of {} failed, retrying with full history",
+            reference
+        );
+        let _ = fs::remove_dir_all(target);
+    }
+
+    let mut args = vec!["clone", url, &target_str];
+    if reference.is_none() {
+        args.splice(1..1, ["--depth", "1"]);
+    }
+    let output = run(&args, None)?;
+    if !output.status.success() {
+        return Err(DocPilotError::Build {
+            message: format!(
+                "git clone {} failed: {}",
+                url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+
+    if let Some(reference) = reference {
+        let output = run(&["checkout", "--quiet", reference], Some(target))?;
+        if !output.status.success() {
+            return Err(DocPilotError::Build {
+                message: format!(
+                    "git checkout {} failed: {}",
+                    reference,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Extract an archive into `target`
+pub fn extract_archive(archive: &Path, target: &Path) -> Result<()> {
+    fs::create_dir_all(target)?;
+    let name = archive.to_string_lossy().to_lowercase();
+    let file = fs::File::open(archive)?;
+
+    if name.ends_with(".zip") {
+        let mut zip = ZipArchive::new(file).map_err(|e| DocPilotError::Build {
+            message: format!("Failed to open archive {}: {}", archive.display(), e),
+        })?;
+        zip.extract(target).map_err(|e| DocPilotError::Build {
+            message: format!("Failed to extract {}: {}", archive.display(), e),
+        })?;
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        tar::Archive::new(GzDecoder::new(file)).unpack(target)?;
+    } else {
+        tar::Archive::new(file).unpack(target)?;
+    }
+
+    Ok(())
+}
+
+/// If a directory only wraps a single subdirectory (as most release archives
+/// do), return that subdirectory
+pub fn package_root(dir: &Path) -> Result<PathBuf> {
+    let entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    match entries.as_slice() {
+        [single] if single.is_dir() => package_root(single),
+        _ => Ok(dir.to_path_buf()),
+    }
+}
+
+/// SHA-256 of a file, or of a directory tree (relative paths and contents,
+/// in sorted order, ignoring `.git`)
+pub fn checksum(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+
+    if path.is_dir() {
+        let mut files = Vec::new();
+        for entry in WalkDir::new(path)
+            .into_iter()
+            .filter_entry(|e| e.file_name() != ".git")
+        {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                files.push(entry.into_path());
+            }
+        }
+        files.sort();
+
+        for file in files {
+            let relative = file.strip_prefix(path).unwrap_or(&file);
+            hasher.update(relative.to_string_lossy().replace('\\', "/").as_bytes());
+            hasher.update([0]);
+            hasher.update(fs::read(&file)?);
+            hasher.update([0]);
+        }
+    } else {
+        let mut file = fs::File::open(path)?;
+        let mut buffer = [0u8; 8192];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Fail unless `actual` matches the expected checksum
+pub fn verify_checksum(name: &str, expected: &str, actual: &str) -> Result<()> {
+    if expected.trim().eq_ignore_ascii_case(actual) {
+        Ok(())
+    } else {
+        Err(DocPilotError::Build {
+            message: format!(
+                "Checksum mismatch for template '{}': expected {}, got {}",
+                name,
+                expected.trim(),
+                actual
+            ),
+        })
+    }
+}
+
+/// Copy a directory tree, skipping `.git`
+pub fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    for entry in WalkDir::new(from)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+    {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(from).unwrap_or(entry.path());
+        let target = to.join(relative);
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_sources() {
+        assert_eq!(
+            TemplateSource::parse("https://github.com/acme/report-template.git#v1.2", None),
+            TemplateSource::Git {
+                url: "https://github.com/acme/report-template.git".to_string(),
+                reference: Some("v1.2".to_string()),
+            }
+        );
+        assert_eq!(
+            TemplateSource::parse("git+https://example.com/tpl", Some("main")),
+            TemplateSource::Git {
+                url: "https://example.com/tpl".to_string(),
+                reference: Some("main".to_string()),
+            }
+        );
+        assert_eq!(
+            TemplateSource::parse("https://example.com/eisvogel.latex", None),
+            TemplateSource::Url("https://example.com/eisvogel.latex".to_string())
+        );
+        assert_eq!(
+            TemplateSource::parse("downloads/corporate.tar.gz", None),
+            TemplateSource::Archive(PathBuf::from("downloads/corporate.tar.gz"))
+        );
+    }
+
+    #[test]
+    fn test_default_name() {
+        let git = TemplateSource::parse("git@github.com:acme/report-template.git", None);
+        assert_eq!(git.default_name(), "report-template");
+
+        let archive = TemplateSource::parse("corporate-1.0.tgz", None);
+        assert_eq!(archive.default_name(), "corporate-1.0");
+    }
+
+    #[test]
+    fn test_checksum_of_tree_ignores_git_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let package = temp_dir.path().join("package");
+        fs::create_dir_all(package.join(".git")).unwrap();
+        fs::write(package.join("template.latex"), "content").unwrap();
+
+        let before = checksum(&package).unwrap();
+        fs::write(package.join(".git/HEAD"), "ref").unwrap();
+        assert_eq!(checksum(&package).unwrap(), before);
+
+        fs::write(package.join("template.latex"), "changed").unwrap();
+        assert_ne!(checksum(&package).unwrap(), before);
+    }
+
+    #[test]
+    fn test_checksum_of_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("a.txt");
+        fs::write(&file, "abc").unwrap();
+
+        assert_eq!(
+            checksum(&file).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(verify_checksum(
+            "a",
+            "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD",
+            &checksum(&file).unwrap()
+        )
+        .is_ok());
+        assert!(verify_checksum("a", "00", &checksum(&file).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_lock_record_replaces_by_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(LOCK_FILE);
+
+        let mut lock = TemplateLock::default();
+        lock.record(LockedTemplate {
+            name: "eisvogel".to_string(),
+            source: "https://example.com/a".to_string(),
+            reference: None,
+            commit: None,
+            sha256: "aa".to_string(),
+        });
+        lock.record(LockedTemplate {
+            name: "eisvogel".to_string(),
+            source: "https://example.com/b".to_string(),
+            reference: None,
+            commit: None,
+            sha256: "bb".to_string(),
+        });
+        lock.save(&lock_path).unwrap();
+
+        let loaded = TemplateLock::load(&lock_path).unwrap();
+        assert_eq!(loaded.templates.len(), 1);
+        assert_eq!(loaded.find("eisvogel").unwrap().sha256, "bb");
+    }
+}
@@ -0,0 +1,1010 @@
+mod bundled;
+mod install;
+mod theme;
+mod variables;
+
+pub use bundled::{eisvogel_files, EISVOGEL_NAME, EISVOGEL_VERSION};
+pub use install::{fetch_directory, LockedTemplate, TemplateLock, TemplateSource, LOCK_FILE};
+pub use variables::TemplateVariables;
+
+use crate::config::{MarkdownFile, ThemeSettings};
+use crate::errors::{DocPilotError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tempfile::TempDir;
+use tracing::{debug, info, warn};
+
+/// Manifest file name inside a template package directory
+pub const MANIFEST_FILE: &str = "template.yml";
+
+/// Description of a template package, read from `templates/<name>/template.yml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemplateManifest {
+    /// Defaults to the package directory name
+    #[serde(default)]
+    pub name: String,
+    pub description: Option<String>,
+    pub version: Option<String>,
+    /// Where the template came from (URL, repository, vendor)
+    pub source: Option<String>,
+    /// Template file per output format, relative to the package directory
+    #[serde(default)]
+    pub formats: BTreeMap<String, PathBuf>,
+    /// Template variables that can be set in the frontmatter
+    #[serde(default)]
+    pub variables: Vec<String>,
+    /// LaTeX packages the template needs
+    #[serde(default)]
+    pub latex_packages: Vec<String>,
+}
+
+/// A named template, either a package directory or loose files sharing a stem
+#[derive(Debug, Clone)]
+pub struct TemplatePackage {
+    pub manifest: TemplateManifest,
+    /// Directory the format paths are relative to
+    pub root: PathBuf,
+    /// Whether the package has a manifest or was inferred from loose files
+    pub has_manifest: bool,
+}
+
+impl TemplatePackage {
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    pub fn formats(&self) -> Vec<&str> {
+        self.manifest.formats.keys().map(|f| f.as_str()).collect()
+    }
+
+    pub fn file_for(&self, format: &str) -> Option<PathBuf> {
+        self.manifest
+            .formats
+            .get(format)
+            .map(|file| self.root.join(file))
+    }
+}
+
+/// Output format served by a loose template file, based on its extension
+fn format_for_extension(extension: &str) -> Option<&'static str> {
+    match extension {
+        "latex" | "tex" => Some("pdf"),
+        "docx" => Some("docx"),
+        "html" => Some("html"),
+        "css" => Some("epub"),
+        _ => None,
+    }
+}
+
+/// Options for [`TemplateManager::install`]
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Install under this name instead of the manifest or source name
+    pub name: Option<String>,
+    /// Git branch, tag or commit
+    pub reference: Option<String>,
+    /// Expected SHA-256 of the downloaded file or package contents
+    pub sha256: Option<String>,
+    /// Verify against `templates.lock` instead of updating it
+    pub locked: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct InstalledTemplate {
+    pub name: String,
+    pub path: PathBuf,
+    pub sha256: String,
+    pub commit: Option<String>,
+}
+
+/// Reject template names that are not a single directory name, so a package
+/// can never be written or removed outside the templates directory
+fn check_package_name(name: &str) -> Result<()> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(part)), None) if part == name => Ok(()),
+        _ => Err(DocPilotError::Config {
+            message: format!(
+                "Invalid template name '{}': expected a plain name without path separators",
+                name
+            ),
+        }),
+    }
+}
+
+pub struct TemplateManager {
+    templates_dir: PathBuf,
+    /// pandoc used to print its default templates for `generate`
+    pandoc: PathBuf,
+}
+
+impl TemplateManager {
+    pub fn new(templates_dir: PathBuf) -> Self {
+        Self {
+            templates_dir,
+            pandoc: PathBuf::from("pandoc"),
+        }
+    }
+
+    pub fn with_pandoc(mut self, pandoc: PathBuf) -> Self {
+        self.pandoc = pandoc;
+        self
+    }
+
+    /// Fetch newer versions over the network: every template in
+    /// `templates.lock`, or only `name`. Eisvogel comes from its latest
+    /// release when it is not pinned yet.
+    pub async fn update(&self, name: Option<&str>) -> Result<Vec<InstalledTemplate>> {
+        let lock = self.load_lock()?;
+        let mut entries: Vec<LockedTemplate> = lock
+            .templates
+            .into_iter()
+            .filter(|entry| name.is_none_or(|n| entry.name == n))
+            .collect();
+
+        if entries.is_empty() {
+            match name {
+                Some(EISVOGEL_NAME) | None => entries.push(LockedTemplate {
+                    name: EISVOGEL_NAME.to_string(),
+                    source: bundled::EISVOGEL_RELEASE_URL.to_string(),
+                    reference: None,
+                    commit: None,
+                    sha256: String::new(),
+                }),
+                Some(name) => {
+                    return Err(DocPilotError::Config {
+                        message: format!(
+                            "Template '{}' is not in {}; use 'templates install' instead",
+                            name,
+                            self.lock_path().display()
+                        ),
+                    })
+                }
+            }
+        }
+
+        let mut installed = Vec::new();
+        for entry in entries {
+            info!("Updating template {} from {}", entry.name, entry.source);
+            let options = InstallOptions {
+                name: Some(entry.name),
+                reference: entry.reference,
+                ..Default::default()
+            };
+            installed.push(self.install(&entry.source, &options).await?);
+        }
+        Ok(installed)
+    }
+
+    /// Like [`resolve`](Self::resolve), but falls back to the bundled
+    /// Eisvogel copy for PDF when it is selected or nothing else is
+    /// installed. The copy is written below `cache_dir`.
+    pub fn resolve_or_bundled(
+        &self,
+        format: &str,
+        selected: Option<&str>,
+        cache_dir: &Path,
+    ) -> Result<Option<PathBuf>> {
+        let bundled_allowed = format == "pdf" && matches!(selected, None | Some(EISVOGEL_NAME));
+
+        if bundled_allowed && selected.is_some() && self.find_package(EISVOGEL_NAME)?.is_none() {
+            return bundled::write_eisvogel(cache_dir).map(Some);
+        }
+
+        match self.resolve(format, selected)? {
+            None if bundled_allowed => {
+                debug!("No PDF template installed, using bundled Eisvogel");
+                bundled::write_eisvogel(cache_dir).map(Some)
+            }
+            resolved => Ok(resolved),
+        }
+    }
+
+    /// All installed templates, sorted by name
+    pub fn list_packages(&self) -> Result<Vec<TemplatePackage>> {
+        let mut packages: BTreeMap<String, TemplatePackage> = BTreeMap::new();
+
+        if !self.templates_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = fs::read_dir(&self.templates_dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+
+        for path in entries {
+            if path.is_dir() {
+                let manifest_path = path.join(MANIFEST_FILE);
+                if !manifest_path.exists() {
+                    continue;
+                }
+
+                match Self::load_manifest(&manifest_path) {
+                    Ok(mut manifest) => {
+                        if manifest.name.is_empty() {
+                            manifest.name = path
+                                .file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_default();
+                        }
+                        packages.insert(
+                            manifest.name.clone(),
+                            TemplatePackage {
+                                manifest,
+                                root: path,
+                                has_manifest: true,
+                            },
+                        );
+                    }
+                    Err(e) => warn!("Skipping template {}: {}", path.display(), e),
+                }
+                continue;
+            }
+
+            let format = path
+                .extension()
+                .and_then(|ext| format_for_extension(&ext.to_string_lossy()));
+            let (Some(format), Some(stem)) = (format, path.file_stem()) else {
+                continue;
+            };
+            let name = stem.to_string_lossy().to_string();
+            let file_name = PathBuf::from(path.file_name().unwrap_or_default());
+
+            let package = packages
+                .entry(name.clone())
+                .or_insert_with(|| TemplatePackage {
+                    manifest: TemplateManifest {
+                        name,
+                        ..Default::default()
+                    },
+                    root: self.templates_dir.clone(),
+                    has_manifest: false,
+                });
+
+            // Packages with a manifest take precedence over loose files of the same name
+            if !package.has_manifest {
+                package
+                    .manifest
+                    .formats
+                    .entry(format.to_string())
+                    .or_insert(file_name);
+            }
+        }
+
+        Ok(packages.into_values().collect())
+    }
+
+    pub fn find_package(&self, name: &str) -> Result<Option<TemplatePackage>> {
+        Ok(self
+            .list_packages()?
+            .into_iter()
+            .find(|package| package.name() == name))
+    }
+
+    /// Pick the template file for an output format.
+    ///
+    /// `selected` is the name from `templates.<format>_template` in the config,
+    /// or a path to a template file. Without a selection the `eisvogel`
+    /// package is preferred for PDF, then the first package by name.
+    pub fn resolve(&self, format: &str, selected: Option<&str>) -> Result<Option<PathBuf>> {
+        let packages = self.list_packages()?;
+
+        if let Some(selected) = selected {
+            if let Some(package) = packages.iter().find(|p| p.name() == selected) {
+                return package
+                    .file_for(format)
+                    .map(Some)
+                    .ok_or_else(|| DocPilotError::Config {
+                        message: format!(
+                            "Template '{}' has no {} template (available: {})",
+                            selected,
+                            format,
+                            package.formats().join(", ")
+                        ),
+                    });
+            }
+
+            let path = PathBuf::from(selected);
+            if path.is_file() {
+                return Ok(Some(path));
+            }
+
+            return Err(DocPilotError::Config {
+                message: format!(
+                    "Template '{}' not found. Run 'docpilot templates list' to see installed templates",
+                    selected
+                ),
+            });
+        }
+
+        let preferred = (format == "pdf")
+            .then(|| packages.iter().find(|p| p.name() == "eisvogel"))
+            .flatten()
+            .and_then(|p| p.file_for(format));
+
+        Ok(preferred.or_else(|| packages.iter().find_map(|p| p.file_for(format))))
+    }
+
+    /// Create or extend the package `name` with a themed DOCX reference
+    /// document or HTML template generated from pandoc's defaults.
+    /// Returns the generated template file.
+    pub fn generate(&self, name: &str, format: &str, theme: &ThemeSettings) -> Result<PathBuf> {
+        check_package_name(name)?;
+        let package_dir = self.templates_dir.join(name);
+        fs::create_dir_all(&package_dir)?;
+
+        let manifest_path = package_dir.join(MANIFEST_FILE);
+        let mut manifest = if manifest_path.exists() {
+            Self::load_manifest(&manifest_path)?
+        } else {
+            TemplateManifest {
+                name: name.to_string(),
+                description: Some("Generated by docpilot from the project theme".to_string()),
+                ..Default::default()
+            }
+        };
+
+        // Keep a copy of the logo inside the package so it travels with it
+        let logo = match &theme.logo {
+            Some(logo) => {
+                let file_name = logo.file_name().ok_or_else(|| DocPilotError::Config {
+                    message: format!("Invalid logo path: {}", logo.display()),
+                })?;
+                let packaged = package_dir.join(file_name);
+                if !logo.exists() {
+                    return Err(DocPilotError::Config {
+                        message: format!("Logo not found: {}", logo.display()),
+                    });
+                }
+                if logo.as_path() != packaged.as_path() {
+                    fs::copy(logo, &packaged)?;
+                }
+                Some(packaged)
+            }
+            None => None,
+        };
+
+        let template_path = match format {
+            "docx" => {
+                let path = package_dir.join("reference.docx");
+                theme::write_reference_docx(&self.pandoc, theme, &path)?;
+                manifest
+                    .formats
+                    .insert("docx".to_string(), PathBuf::from("reference.docx"));
+                path
+            }
+            "html" => {
+                let path = package_dir.join(format!("{}.html", name));
+                let stylesheet = package_dir.join(format!("{}.css", name));
+                let logo_src = logo
+                    .as_ref()
+                    .map(|logo| logo.to_string_lossy().replace('\\', "/"));
+                theme::write_html_template(
+                    &self.pandoc,
+                    theme,
+                    &path,
+                    &stylesheet,
+                    logo_src.as_deref(),
+                )?;
+                manifest
+                    .formats
+                    .insert("html".to_string(), PathBuf::from(format!("{}.html", name)));
+                // The same stylesheet themes EPUB output
+                manifest
+                    .formats
+                    .insert("epub".to_string(), PathBuf::from(format!("{}.css", name)));
+                path
+            }
+            other => {
+                return Err(DocPilotError::Config {
+                    message: format!("Cannot generate a {} template (use docx or html)", other),
+                })
+            }
+        };
+
+        fs::write(&manifest_path, serde_yaml::to_string(&manifest)?)?;
+        info!("Generated {} template: {}", format, template_path.display());
+        Ok(template_path)
+    }
+
+    /// Variables used by each text template of a package, keyed by format
+    pub fn variables(&self, name: &str) -> Result<Vec<(String, TemplateVariables)>> {
+        let Some(package) = self.find_package(name)? else {
+            if name == EISVOGEL_NAME {
+                return Ok(vec![(
+                    "pdf".to_string(),
+                    TemplateVariables::parse(bundled::EISVOGEL_LATEX),
+                )]);
+            }
+            return Err(DocPilotError::Config {
+                message: format!("Template '{}' not found", name),
+            });
+        };
+
+        let mut variables = Vec::new();
+        for format in package.formats() {
+            let Some(path) = package.file_for(format) else {
+                continue;
+            };
+            // Reference documents such as .docx are not text templates
+            if let Ok(content) = fs::read_to_string(&path) {
+                variables.push((format.to_string(), TemplateVariables::parse(&content)));
+            }
+        }
+        Ok(variables)
+    }
+
+    /// Cross-check the frontmatter of `files` against a template file.
+    /// Variables listed in a sibling `template.yml` count as supported.
+    pub fn frontmatter_warnings(
+        template: &Path,
+        files: &[MarkdownFile],
+        extra_keys: &[&str],
+    ) -> Vec<String> {
+        let Ok(content) = fs::read_to_string(template) else {
+            return Vec::new();
+        };
+
+        let manifest_variables = template
+            .parent()
+            .map(|dir| dir.join(MANIFEST_FILE))
+            .filter(|path| path.exists())
+            .and_then(|path| Self::load_manifest(&path).ok())
+            .map(|manifest| manifest.variables)
+            .unwrap_or_default();
+
+        TemplateVariables::parse(&content).check_frontmatter(files, &manifest_variables, extra_keys)
+    }
+
+    /// Manifest of the package a resolved template file belongs to
+    pub fn manifest_for(template: &Path) -> Option<TemplateManifest> {
+        let path = template.parent()?.join(MANIFEST_FILE);
+        if !path.is_file() {
+            return None;
+        }
+        Self::load_manifest(&path).map_err(|e| warn!("{}", e)).ok()
+    }
+
+    fn load_manifest(path: &Path) -> Result<TemplateManifest> {
+        let content = fs::read_to_string(path)?;
+        serde_yaml::from_str(&content).map_err(|e| DocPilotError::Config {
+            message: format!("Invalid template manifest {}: {}", path.display(), e),
+        })
+    }
+
+    /// Install a template from a file, directory, archive, git repository or
+    /// URL, verify its checksum and record it in `templates.lock`
+    pub async fn install(
+        &self,
+        source: &str,
+        options: &InstallOptions,
+    ) -> Result<InstalledTemplate> {
+        let parsed = TemplateSource::parse(source, options.reference.as_deref());
+        // Files, directories and archives have no revisions to pick from
+        if options.reference.is_some() && !matches!(parsed, TemplateSource::Git { .. }) {
+            return Err(DocPilotError::Config {
+                message: format!(
+                    "--ref only applies to git sources, but {} is not a git repository",
+                    source
+                ),
+            });
+        }
+        let staging = TempDir::new()?;
+        let fetched = install::fetch(&parsed, staging.path()).await?;
+        self.install_fetched(source, &parsed, fetched, options)
+    }
+
+    /// Reinstall every template in `templates.lock`, verifying each checksum
+    pub async fn install_locked(&self) -> Result<Vec<InstalledTemplate>> {
+        let lock = self.load_lock()?;
+        if lock.templates.is_empty() {
+            return Err(DocPilotError::Config {
+                message: format!("No templates pinned in {}", self.lock_path().display()),
+            });
+        }
+
+        let mut installed = Vec::new();
+        for entry in &lock.templates {
+            let options = InstallOptions {
+                name: Some(entry.name.clone()),
+                // Check out the exact commit that was locked
+                reference: entry.commit.clone().or_else(|| entry.reference.clone()),
+                sha256: Some(entry.sha256.clone()),
+                locked: true,
+            };
+            installed.push(self.install(&entry.source, &options).await?);
+        }
+        Ok(installed)
+    }
+
+    fn install_fetched(
+        &self,
+        source: &str,
+        parsed: &TemplateSource,
+        fetched: install::Fetched,
+        options: &InstallOptions,
+    ) -> Result<InstalledTemplate> {
+        // Archives are checksummed as downloaded, directories by their contents
+        let sha256 = install::checksum(&fetched.path)?;
+        let staging = TempDir::new()?;
+        let content =
+            if fetched.path.is_file() && install::is_archive(&fetched.path.to_string_lossy()) {
+                let extracted = staging.path().join("extracted");
+                install::extract_archive(&fetched.path, &extracted)?;
+                install::package_root(&extracted)?
+            } else {
+                fetched.path.clone()
+            };
+
+        let name = match &options.name {
+            Some(name) => name.clone(),
+            None if content.is_dir() => {
+                let manifest_path = content.join(MANIFEST_FILE);
+                manifest_path
+                    .exists()
+                    .then(|| Self::load_manifest(&manifest_path))
+                    .transpose()?
+                    .map(|m| m.name)
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| parsed.default_name())
+            }
+            None => content
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| parsed.default_name()),
+        };
+
+        // The name can come from a downloaded manifest and becomes a path
+        check_package_name(&name)?;
+
+        let mut lock = self.load_lock()?;
+        let locked_entry = lock
+            .find(&name)
+            .or_else(|| lock.find_by_source(source))
+            .cloned();
+
+        if let Some(expected) = &options.sha256 {
+            install::verify_checksum(&name, expected, &sha256)?;
+        }
+        if options.locked {
+            let entry = locked_entry.as_ref().ok_or_else(|| DocPilotError::Config {
+                message: format!(
+                    "Template '{}' is not pinned in {}",
+                    name,
+                    self.lock_path().display()
+                ),
+            })?;
+            install::verify_checksum(&name, &entry.sha256, &sha256)?;
+        }
+
+        self.ensure_templates_dir()?;
+        let path = if content.is_dir() {
+            self.install_package(&name, source, &content)?
+        } else {
+            let extension = content
+                .extension()
+                .map(|e| format!(".{}", e.to_string_lossy()))
+                .unwrap_or_default();
+            let dest_path = self.templates_dir.join(format!("{}{}", name, extension));
+            fs::copy(&content, &dest_path)?;
+            dest_path
+        };
+
+        if !options.locked {
+            lock.record(LockedTemplate {
+                name: name.clone(),
+                source: source
+                    .rsplit_once('#')
+                    .filter(|_| matches!(parsed, TemplateSource::Git { .. }))
+                    .map_or(source, |(location, _)| location)
+                    .to_string(),
+                reference: match parsed {
+                    TemplateSource::Git { reference, .. } => reference.clone(),
+                    _ => None,
+                },
+                commit: fetched.commit.clone(),
+                sha256: sha256.clone(),
+            });
+            lock.save(&self.lock_path())?;
+        }
+
+        Ok(InstalledTemplate {
+            name,
+            path,
+            sha256,
+            commit: fetched.commit,
+        })
+    }
+
+    /// Copy a package directory to `templates/<name>/`, writing a manifest
+    /// when the package has none
+    fn install_package(&self, name: &str, source: &str, content: &Path) -> Result<PathBuf> {
+        let target = self.templates_dir.join(name);
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        install::copy_tree(content, &target)?;
+
+        let manifest_path = target.join(MANIFEST_FILE);
+        if !manifest_path.exists() {
+            let mut manifest = TemplateManifest {
+                name: name.to_string(),
+                source: Some(source.to_string()),
+                ..Default::default()
+            };
+
+            let mut files = fs::read_dir(&target)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            files.sort();
+
+            for file in files {
+                let format = file
+                    .extension()
+                    .and_then(|ext| format_for_extension(&ext.to_string_lossy()));
+                if let (Some(format), Some(file_name)) = (format, file.file_name()) {
+                    manifest
+                        .formats
+                        .entry(format.to_string())
+                        .or_insert_with(|| PathBuf::from(file_name));
+                }
+            }
+
+            fs::write(&manifest_path, serde_yaml::to_string(&manifest)?)?;
+        }
+
+        Ok(target)
+    }
+
+    /// `templates.lock` lives next to the templates directory
+    pub fn lock_path(&self) -> PathBuf {
+        self.templates_dir
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(LOCK_FILE)
+    }
+
+    pub fn load_lock(&self) -> Result<TemplateLock> {
+        TemplateLock::load(&self.lock_path())
+    }
+
+    fn ensure_templates_dir(&self) -> Result<()> {
+        if !self.templates_dir.exists() {
+            fs::create_dir_all(&self.templates_dir)?;
+            debug!(
+                "Created templates directory: {}",
+                self.templates_dir.display()
+            );
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_template_manager_creation() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = TemplateManager::new(temp_dir.path().to_path_buf());
+
+        assert_eq!(manager.templates_dir, temp_dir.path());
+    }
+
+    #[test]
+    fn test_list_packages_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = TemplateManager::new(temp_dir.path().to_path_buf());
+
+        let result = manager.list_packages();
+        assert!(result.is_ok());
+
+        let packages = result.unwrap();
+        assert!(packages.is_empty());
+    }
+
+    #[test]
+    fn test_list_packages_with_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let templates_dir = temp_dir.path().join("templates");
+        fs::create_dir_all(&templates_dir).unwrap();
+
+        // Create test template files
+        fs::write(templates_dir.join("template1.latex"), "test content").unwrap();
+        fs::write(templates_dir.join("template2.html"), "test content").unwrap();
+        fs::write(templates_dir.join("template2.docx"), "test content").unwrap();
+
+        let manager = TemplateManager::new(templates_dir);
+        let packages = manager.list_packages().unwrap();
+
+        let names: Vec<&str> = packages.iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["template1", "template2"]);
+        assert_eq!(packages[1].formats(), vec!["docx", "html"]);
+    }
+
+    #[test]
+    fn test_list_packages_reads_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        let package_dir = temp_dir.path().join("corporate");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(package_dir.join("corporate.latex"), "latex").unwrap();
+        fs::write(
+            package_dir.join(MANIFEST_FILE),
+            "version: \"1.2.0\"\nformats:\n  pdf: corporate.latex\nlatex_packages: [mdframed]\n",
+        )
+        .unwrap();
+
+        let manager = TemplateManager::new(temp_dir.path().to_path_buf());
+        let package = manager.find_package("corporate").unwrap().unwrap();
+
+        assert_eq!(package.manifest.version.as_deref(), Some("1.2.0"));
+        assert_eq!(package.manifest.latex_packages, vec!["mdframed"]);
+        assert_eq!(
+            package.file_for("pdf"),
+            Some(package_dir.join("corporate.latex"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_prefers_selection_then_eisvogel() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("alpha.latex"), "a").unwrap();
+        fs::write(temp_dir.path().join("eisvogel.latex"), "e").unwrap();
+
+        let manager = TemplateManager::new(temp_dir.path().to_path_buf());
+
+        assert_eq!(
+            manager.resolve("pdf", None).unwrap(),
+            Some(temp_dir.path().join("eisvogel.latex"))
+        );
+        assert_eq!(
+            manager.resolve("pdf", Some("alpha")).unwrap(),
+            Some(temp_dir.path().join("alpha.latex"))
+        );
+        assert_eq!(manager.resolve("docx", None).unwrap(), None);
+        assert!(manager.resolve("pdf", Some("missing")).is_err());
+        assert!(manager.resolve("html", Some("alpha")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_or_bundled_falls_back_to_eisvogel() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_dir = temp_dir.path().join("cache");
+        let manager = TemplateManager::new(temp_dir.path().join("templates"));
+
+        let bundled = cache_dir.join("eisvogel/eisvogel.latex");
+        assert_eq!(
+            manager.resolve_or_bundled("pdf", None, &cache_dir).unwrap(),
+            Some(bundled.clone())
+        );
+        assert_eq!(
+            manager
+                .resolve_or_bundled("pdf", Some("eisvogel"), &cache_dir)
+                .unwrap(),
+            Some(bundled)
+        );
+        assert_eq!(
+            manager
+                .resolve_or_bundled("html", None, &cache_dir)
+                .unwrap(),
+            None
+        );
+
+        // An installed copy takes precedence
+        let installed = bundled::write_eisvogel(&manager.templates_dir).unwrap();
+        assert_eq!(
+            manager.resolve_or_bundled("pdf", None, &cache_dir).unwrap(),
+            Some(installed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_install_template_success() {
+        let temp_dir = TempDir::new().unwrap();
+        let templates_dir = temp_dir.path().join("templates");
+
+        // Create source template
+        let source_path = temp_dir.path().join("source.latex");
+        fs::write(&source_path, "template content").unwrap();
+
+        let manager = TemplateManager::new(templates_dir.clone());
+        let result = manager
+            .install(source_path.to_str().unwrap(), &InstallOptions::default())
+            .await;
+        assert!(result.is_ok());
+
+        // Verify template was installed
+        let installed_path = templates_dir.join("source.latex");
+        assert!(installed_path.exists());
+
+        let content = fs::read_to_string(installed_path).unwrap();
+        assert_eq!(content, "template content");
+    }
+
+    #[tokio::test]
+    async fn test_install_rejects_manifest_name_outside_templates_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let templates_dir = temp_dir.path().join("templates");
+        let source_dir = temp_dir.path().join("evil");
+        fs::create_dir_all(&source_dir).unwrap();
+        fs::write(source_dir.join(MANIFEST_FILE), "name: ..\n").unwrap();
+        fs::write(source_dir.join("report.latex"), "latex").unwrap();
+        fs::write(temp_dir.path().join("keep.md"), "project file").unwrap();
+
+        let manager = TemplateManager::new(templates_dir.clone());
+        let error = manager
+            .install(source_dir.to_str().unwrap(), &InstallOptions::default())
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("Invalid template name '..'"));
+        assert!(temp_dir.path().join("keep.md").exists());
+        assert!(!templates_dir.exists());
+
+        for name in ["/tmp/x", "a/b", ".", ""] {
+            assert!(check_package_name(name).is_err(), "accepted {:?}", name);
+        }
+        assert!(check_package_name("corporate-theme").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_install_template_missing_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let templates_dir = temp_dir.path().join("templates");
+        let missing_path = temp_dir.path().join("missing.latex");
+
+        let manager = TemplateManager::new(templates_dir);
+        let result = manager
+            .install(missing_path.to_str().unwrap(), &InstallOptions::default())
+            .await;
+
+        assert!(result.is_err());
+        match result.unwrap_err() {
+            DocPilotError::Build { message } => {
+                assert!(message.contains("Template file not found"));
+            }
+            _ => panic!("Expected Build error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_install_directory_records_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        let templates_dir = temp_dir.path().join("templates");
+        let source_dir = temp_dir.path().join("corporate-theme");
+        fs::create_dir_all(&source_dir).unwrap();
+        fs::write(source_dir.join("report.latex"), "latex").unwrap();
+        fs::write(source_dir.join("report.css"), "css").unwrap();
+
+        let manager = TemplateManager::new(templates_dir.clone());
+        let installed = manager
+            .install(source_dir.to_str().unwrap(), &InstallOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(installed.name, "corporate-theme");
+        let package = manager.find_package("corporate-theme").unwrap().unwrap();
+        assert_eq!(package.formats(), vec!["epub", "pdf"]);
+
+        let lock = manager.load_lock().unwrap();
+        assert_eq!(manager.lock_path(), temp_dir.path().join(LOCK_FILE));
+        assert_eq!(
+            lock.find("corporate-theme").unwrap().sha256,
+            installed.sha256
+        );
+
+        // Reinstalling with --locked succeeds while the contents are unchanged
+        let locked = InstallOptions {
+            locked: true,
+            ..Default::default()
+        };
+        assert!(manager
+            .install(source_dir.to_str().unwrap(), &locked)
+            .await
+            .is_ok());
+
+        fs::write(source_dir.join("report.latex"), "tampered").unwrap();
+        let error = manager
+            .install(source_dir.to_str().unwrap(), &locked)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Checksum mismatch"));
+    }
+
+    #[tokio::test]
+    async fn test_install_zip_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("memo.zip");
+
+        let mut zip = zip::ZipWriter::new(fs::File::create(&archive_path).unwrap());
+        let options = zip::write::FileOptions::default();
+        zip.add_directory("memo-1.0/", options).unwrap();
+        zip.start_file("memo-1.0/template.yml", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"name: memo\nformats:\n  html: memo.html\n").unwrap();
+        zip.start_file("memo-1.0/memo.html", options).unwrap();
+        std::io::Write::write_all(&mut zip, b"<html>$body$</html>").unwrap();
+        zip.finish().unwrap();
+
+        let manager = TemplateManager::new(temp_dir.path().join("templates"));
+        let wrong = InstallOptions {
+            sha256: Some("0".repeat(64)),
+            ..Default::default()
+        };
+        assert!(manager
+            .install(archive_path.to_str().unwrap(), &wrong)
+            .await
+            .is_err());
+
+        let installed = manager
+            .install(archive_path.to_str().unwrap(), &InstallOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(installed.name, "memo");
+        assert_eq!(
+            manager.resolve("html", Some("memo")).unwrap(),
+            Some(temp_dir.path().join("templates/memo/memo.html"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_install_rejects_ref_for_non_git_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("letter.latex");
+        fs::write(&source_path, "latex").unwrap();
+
+        let manager = TemplateManager::new(temp_dir.path().join("templates"));
+        let options = InstallOptions {
+            reference: Some("v2".to_string()),
+            ..Default::default()
+        };
+
+        let error = manager
+            .install(source_path.to_str().unwrap(), &options)
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("--ref only applies to git sources"));
+        assert!(!temp_dir.path().join("templates/letter").exists());
+    }
+
+    #[tokio::test]
+    async fn test_install_locked_requires_lock_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("letter.latex");
+        fs::write(&source_path, "latex").unwrap();
+
+        let manager = TemplateManager::new(temp_dir.path().join("templates"));
+        let options = InstallOptions {
+            locked: true,
+            ..Default::default()
+        };
+
+        assert!(manager
+            .install(source_path.to_str().unwrap(), &options)
+            .await
+            .is_err());
+        assert!(manager.install_locked().await.is_err());
+    }
+
+    #[test]
+    fn test_ensure_templates_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let templates_dir = temp_dir.path().join("new_templates");
+
+        assert!(!templates_dir.exists());
+
+        let manager = TemplateManager::new(templates_dir.clone());
+        let result = manager.ensure_templates_dir();
+        assert!(result.is_ok());
+
+        assert!(templates_dir.exists());
+        assert!(templates_dir.is_dir());
+    }
+}
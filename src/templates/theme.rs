@@ -0,0 +1,743 @@
+//! `templates new docx|html`: pandoc's default reference document and HTML
+//! template, restyled with the project theme.
+
+use crate::config::ThemeSettings;
+use crate::errors::{DocPilotError, Result};
+use regex::{NoExpand, Regex};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::Command;
+use tracing::{debug, warn};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Order of run properties in `<w:rPr>` required by the OOXML schema
+const RPR_ORDER: &[&str] = &[
+    "rStyle",
+    "rFonts",
+    "b",
+    "bCs",
+    "i",
+    "iCs",
+    "caps",
+    "smallCaps",
+    "strike",
+    "dstrike",
+    "outline",
+    "shadow",
+    "emboss",
+    "imprint",
+    "noProof",
+    "snapToGrid",
+    "vanish",
+    "webHidden",
+    "color",
+    "spacing",
+    "w",
+    "kern",
+    "position",
+    "sz",
+    "szCs",
+    "highlight",
+    "u",
+    "effect",
+    "bdr",
+    "shd",
+    "fitText",
+    "vertAlign",
+    "rtl",
+    "cs",
+    "em",
+    "lang",
+    "eastAsianLayout",
+    "specVanish",
+    "oMath",
+];
+
+const HEADING_STYLES: &[&str] = &[
+    "Title", "Subtitle", "Heading1", "Heading2", "Heading3", "Heading4", "Heading5", "Heading6",
+    "Heading7", "Heading8", "Heading9",
+];
+
+const HEADER_PART: &str = "word/header-docpilot.xml";
+const HEADER_RELATIONSHIP: &str = "rIdDocpilotHeader";
+
+/// Logo width in the DOCX header (1.5in in EMU)
+const LOGO_WIDTH_EMU: u64 = 1_371_600;
+
+fn pandoc_output(pandoc: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    debug!("Pandoc command: {} {}", pandoc.display(), args.join(" "));
+
+    let output =
+        Command::new(pandoc)
+            .args(args)
+            .output()
+            .map_err(|_| DocPilotError::Dependency {
+                tool: "pandoc".to_string(),
+                hint: "pandoc is needed to start from its default templates".to_string(),
+            })?;
+
+    if !output.status.success() {
+        return Err(DocPilotError::Build {
+            message: format!(
+                "pandoc {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+    Ok(output.stdout)
+}
+
+/// Write a themed `reference.docx` to `target`
+pub fn write_reference_docx(pandoc: &Path, theme: &ThemeSettings, target: &Path) -> Result<()> {
+    let original = pandoc_output(pandoc, &["--print-default-data-file", "reference.docx"])?;
+    let themed = theme_docx(&original, theme)?;
+    fs::write(target, themed)?;
+    Ok(())
+}
+
+/// Restyle a reference document held in memory
+pub fn theme_docx(docx: &[u8], theme: &ThemeSettings) -> Result<Vec<u8>> {
+    let zip_error = |e: zip::result::ZipError| DocPilotError::Build {
+        message: format!("Invalid reference document: {}", e),
+    };
+
+    let mut archive = ZipArchive::new(std::io::Cursor::new(docx)).map_err(zip_error)?;
+    let mut parts: Vec<(String, Vec<u8>)> = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(zip_error)?;
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        parts.push((entry.name().to_string(), content));
+    }
+
+    let logo = match &theme.logo {
+        Some(path) => Some((fs::read(path)?, logo_extension(path)?)),
+        None => None,
+    };
+
+    for (name, content) in parts.iter_mut() {
+        let edited = match name.as_str() {
+            "word/styles.xml" => Some(theme_styles(&String::from_utf8_lossy(content), theme)),
+            "word/document.xml" => {
+                let mut document = String::from_utf8_lossy(content).to_string();
+                if let Some(margin) = &theme.margin {
+                    document = set_page_margins(&document, margin)?;
+                }
+                if logo.is_some() {
+                    document = add_header_reference(&document);
+                }
+                Some(document)
+            }
+            "word/_rels/document.xml.rels" if logo.is_some() => Some(add_relationship(
+                &String::from_utf8_lossy(content),
+                HEADER_RELATIONSHIP,
+                "http://schemas.openxmlformats.org/officeDocument/2006/relationships/header",
+                "header-docpilot.xml",
+            )),
+            "[Content_Types].xml" => logo.as_ref().map(|(_, extension)| {
+                add_content_types(&String::from_utf8_lossy(content), extension)
+            }),
+            _ => None,
+        };
+
+        if let Some(edited) = edited {
+            *content = edited.into_bytes();
+        }
+    }
+
+    if let Some((image, extension)) = logo {
+        let (width, height) = image_size(&image).unwrap_or((1, 1));
+        let height_emu = LOGO_WIDTH_EMU * u64::from(height) / u64::from(width.max(1));
+        let media = format!("media/docpilot-logo.{}", extension);
+
+        parts.push((
+            HEADER_PART.to_string(),
+            header_xml(LOGO_WIDTH_EMU, height_emu).into_bytes(),
+        ));
+        parts.push((
+            "word/_rels/header-docpilot.xml.rels".to_string(),
+            add_relationship(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\"></Relationships>",
+                "rIdDocpilotLogo",
+                "http://schemas.openxmlformats.org/officeDocument/2006/relationships/image",
+                &media,
+            )
+            .into_bytes(),
+        ));
+        parts.push((format!("word/{}", media), image));
+    }
+
+    let mut output = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in parts {
+        output.start_file(name, options).map_err(zip_error)?;
+        output.write_all(&content)?;
+    }
+    Ok(output.finish().map_err(zip_error)?.into_inner())
+}
+
+/// Apply fonts, sizes and colors to `word/styles.xml`
+fn theme_styles(styles: &str, theme: &ThemeSettings) -> String {
+    let mut styles = styles.to_string();
+
+    let mut defaults = Vec::new();
+    if let Some(font) = &theme.body_font {
+        defaults.push(fonts_element(font));
+    }
+    if let Some(color) = theme.text_color.as_deref().and_then(docx_color) {
+        defaults.push(format!("<w:color w:val=\"{}\"/>", color));
+    }
+    if let Some(size) = theme.font_size.as_deref().and_then(points) {
+        let half_points = (size * 2.0).round() as u32;
+        defaults.push(format!("<w:sz w:val=\"{}\"/>", half_points));
+        defaults.push(format!("<w:szCs w:val=\"{}\"/>", half_points));
+    }
+    styles = edit_block_rpr(&styles, r"(?s)<w:rPrDefault>.*?</w:rPrDefault>", &defaults);
+
+    let mut heading = Vec::new();
+    if let Some(font) = &theme.heading_font {
+        heading.push(fonts_element(font));
+    }
+    if let Some(color) = theme.heading_color.as_deref().and_then(docx_color) {
+        heading.push(format!("<w:color w:val=\"{}\"/>", color));
+    }
+    for style in HEADING_STYLES {
+        styles = edit_style(&styles, style, &heading);
+    }
+
+    if let Some(color) = theme.link_color.as_deref().and_then(docx_color) {
+        styles = edit_style(
+            &styles,
+            "Hyperlink",
+            &[format!("<w:color w:val=\"{}\"/>", color)],
+        );
+    }
+
+    if let Some(font) = &theme.mono_font {
+        for style in ["VerbatimChar", "SourceCode"] {
+            styles = edit_style(&styles, style, &[fonts_element(font)]);
+        }
+    }
+
+    for (name, value) in [
+        ("text_color", &theme.text_color),
+        ("heading_color", &theme.heading_color),
+        ("link_color", &theme.link_color),
+    ] {
+        if let Some(value) = value.as_deref().filter(|v| docx_color(v).is_none()) {
+            warn!(
+                "Ignoring theme.{} '{}': DOCX needs a hex color",
+                name, value
+            );
+        }
+    }
+
+    styles
+}
+
+fn edit_style(styles: &str, style_id: &str, properties: &[String]) -> String {
+    let pattern = format!(
+        r#"(?s)<w:style\b[^>]*w:styleId="{}"[^>]*>.*?</w:style>"#,
+        regex::escape(style_id)
+    );
+    edit_block_rpr(styles, &pattern, properties)
+}
+
+/// Set run properties in the last `<w:rPr>` of the first block matching `pattern`
+fn edit_block_rpr(xml: &str, pattern: &str, properties: &[String]) -> String {
+    if properties.is_empty() {
+        return xml.to_string();
+    }
+    let Some(block) = Regex::new(pattern).unwrap().find(xml) else {
+        return xml.to_string();
+    };
+    let mut edited = block.as_str().to_string();
+
+    let rpr = match (edited.rfind("<w:rPr>"), edited.rfind("</w:rPr>")) {
+        (Some(start), Some(end)) if start < end => start + "<w:rPr>".len()..end,
+        _ => {
+            // No run properties yet: add them before the closing tag
+            let close = edited.rfind("</").unwrap_or(edited.len());
+            edited.insert_str(close, "<w:rPr></w:rPr>");
+            close + "<w:rPr>".len()..close + "<w:rPr>".len()
+        }
+    };
+
+    let mut inner = edited[rpr.clone()].to_string();
+    for property in properties {
+        inner = set_run_property(&inner, property);
+    }
+    edited.replace_range(rpr, &inner);
+
+    format!("{}{}{}", &xml[..block.start()], edited, &xml[block.end()..])
+}
+
+/// Replace or insert a single run property element, keeping schema order
+fn set_run_property(rpr: &str, element: &str) -> String {
+    let name = element
+        .trim_start_matches("<w:")
+        .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .next()
+        .unwrap_or_default();
+
+    let existing =
+        Regex::new(&format!(r"(?s)<w:{}\b[^>]*?(?:/>|>.*?</w:{}>)", name, name)).unwrap();
+    if existing.is_match(rpr) {
+        return existing.replace(rpr, NoExpand(element)).into_owned();
+    }
+
+    let position = RPR_ORDER.iter().position(|n| *n == name).unwrap_or(0);
+    let insert_at = RPR_ORDER[position + 1..]
+        .iter()
+        .filter_map(|later| {
+            Regex::new(&format!(r"<w:{}\b", later))
+                .unwrap()
+                .find(rpr)
+                .map(|m| m.start())
+        })
+        .min()
+        .unwrap_or(rpr.len());
+
+    format!("{}{}{}", &rpr[..insert_at], element, &rpr[insert_at..])
+}
+
+fn fonts_element(font: &str) -> String {
+    let font = xml_escape(font);
+    format!(
+        "<w:rFonts w:ascii=\"{0}\" w:hAnsi=\"{0}\" w:eastAsia=\"{0}\" w:cs=\"{0}\"/>",
+        font
+    )
+}
+
+/// Set all four margins of `<w:pgMar>` in `word/document.xml`
+fn set_page_margins(document: &str, margin: &str) -> Result<String> {
+    let twips = twips(margin).ok_or_else(|| DocPilotError::Config {
+        message: format!(
+            "Invalid theme margin '{}' (use e.g. 2.5cm, 20mm or 1in)",
+            margin
+        ),
+    })?;
+
+    let page_margin = Regex::new(r"<w:pgMar\b[^>]*/>").unwrap();
+    let Some(element) = page_margin.find(document) else {
+        warn!("Reference document has no page margins to update");
+        return Ok(document.to_string());
+    };
+
+    let mut updated = element.as_str().to_string();
+    for side in ["top", "right", "bottom", "left"] {
+        let attribute = Regex::new(&format!(r#"w:{}="[^"]*""#, side)).unwrap();
+        let value = format!("w:{}=\"{}\"", side, twips);
+        updated = if attribute.is_match(&updated) {
+            attribute.replace(&updated, NoExpand(&value)).into_owned()
+        } else {
+            updated.replacen("<w:pgMar", &format!("<w:pgMar {}", value), 1)
+        };
+    }
+
+    Ok(document.replacen(element.as_str(), &updated, 1))
+}
+
+fn add_header_reference(document: &str) -> String {
+    let section = Regex::new(r"<w:sectPr\b[^>]*>").unwrap();
+    let Some(start) = section.find(document) else {
+        warn!("Reference document has no section properties; logo header skipped");
+        return document.to_string();
+    };
+
+    let reference = format!(
+        "<w:headerReference xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\" w:type=\"default\" r:id=\"{}\"/>",
+        HEADER_RELATIONSHIP
+    );
+    format!(
+        "{}{}{}",
+        &document[..start.end()],
+        reference,
+        &document[start.end()..]
+    )
+}
+
+fn add_relationship(rels: &str, id: &str, kind: &str, target: &str) -> String {
+    let relationship = format!(
+        "<Relationship Id=\"{}\" Type=\"{}\" Target=\"{}\"/>",
+        id, kind, target
+    );
+    rels.replacen(
+        "</Relationships>",
+        &format!("{}</Relationships>", relationship),
+        1,
+    )
+}
+
+fn add_content_types(types: &str, image_extension: &str) -> String {
+    let mut additions = format!(
+        "<Override PartName=\"/{}\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml\"/>",
+        HEADER_PART
+    );
+    if !types.contains(&format!("Extension=\"{}\"", image_extension)) {
+        let mime = if image_extension == "png" {
+            "image/png"
+        } else {
+            "image/jpeg"
+        };
+        additions.push_str(&format!(
+            "<Default Extension=\"{}\" ContentType=\"{}\"/>",
+            image_extension, mime
+        ));
+    }
+    types.replacen("</Types>", &format!("{}</Types>", additions), 1)
+}
+
+fn header_xml(width: u64, height: u64) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:hdr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture"><w:p><w:pPr><w:jc w:val="right"/></w:pPr><w:r><w:drawing><wp:inline distT="0" distB="0" distL="0" distR="0"><wp:extent cx="{w}" cy="{h}"/><wp:docPr id="1001" name="Logo"/><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic><pic:nvPicPr><pic:cNvPr id="0" name="Logo"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="rIdDocpilotLogo"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{w}" cy="{h}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p></w:hdr>"#,
+        w = width,
+        h = height
+    )
+}
+
+fn logo_extension(path: &Path) -> Result<String> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => Ok("png".to_string()),
+        "jpg" | "jpeg" => Ok("jpeg".to_string()),
+        _ => Err(DocPilotError::Config {
+            message: format!(
+                "Logo {} must be a PNG or JPEG image for DOCX output",
+                path.display()
+            ),
+        }),
+    }
+}
+
+/// Pixel dimensions of a PNG or JPEG image
+fn image_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") && bytes.len() >= 24 {
+        let width = u32::from_be_bytes(bytes[16..20].try_into().ok()?);
+        let height = u32::from_be_bytes(bytes[20..24].try_into().ok()?);
+        return Some((width, height));
+    }
+
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        let mut index = 2;
+        while index + 9 < bytes.len() {
+            if bytes[index] != 0xFF {
+                index += 1;
+                continue;
+            }
+            let marker = bytes[index + 1];
+            let length = usize::from(u16::from_be_bytes([bytes[index + 2], bytes[index + 3]]));
+            // Start-of-frame markers carry the image size
+            if matches!(marker, 0xC0..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF) {
+                let height = u16::from_be_bytes([bytes[index + 5], bytes[index + 6]]);
+                let width = u16::from_be_bytes([bytes[index + 7], bytes[index + 8]]);
+                return Some((u32::from(width), u32::from(height)));
+            }
+            index += 2 + length;
+        }
+    }
+
+    None
+}
+
+/// Write a themed copy of pandoc's HTML5 template plus a stylesheet.
+/// `logo_src` is the logo path as seen from the project root.
+pub fn write_html_template(
+    pandoc: &Path,
+    theme: &ThemeSettings,
+    template_path: &Path,
+    stylesheet_path: &Path,
+    logo_src: Option<&str>,
+) -> Result<()> {
+    let template = String::from_utf8_lossy(&pandoc_output(pandoc, &["-D", "html5"])?).to_string();
+    let css = theme_css(theme);
+
+    // The default template pulls in its CSS through this partial
+    if template.contains("styles.html()") {
+        if let Ok(partial) = pandoc_output(
+            pandoc,
+            &["--print-default-data-file", "templates/styles.html"],
+        ) {
+            if let Some(dir) = template_path.parent() {
+                fs::write(dir.join("styles.html"), partial)?;
+            }
+        }
+    }
+
+    fs::write(template_path, theme_html(&template, &css, logo_src))?;
+    fs::write(stylesheet_path, css)?;
+    Ok(())
+}
+
+fn theme_html(template: &str, css: &str, logo_src: Option<&str>) -> String {
+    let mut html = template.replacen("</head>", &format!("<style>\n{}</style>\n</head>", css), 1);
+
+    if let Some(src) = logo_src {
+        let body = Regex::new(r"<body\b[^>]*>").unwrap();
+        if let Some(tag) = body.find(&html) {
+            let logo = format!(
+                "\n<header class=\"docpilot-logo\"><img src=\"{}\" alt=\"\" /></header>",
+                xml_escape(src)
+            );
+            html.insert_str(tag.end(), &logo);
+        }
+    }
+
+    html
+}
+
+pub fn theme_css(theme: &ThemeSettings) -> String {
+    let mut css = String::from("/* Generated by docpilot templates new */\n");
+
+    let mut body = Vec::new();
+    if let Some(font) = &theme.body_font {
+        body.push(format!("font-family: {}, serif;", css_font(font)));
+    }
+    if let Some(size) = &theme.font_size {
+        let size = size.trim();
+        if size.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            body.push(format!("font-size: {}pt;", size));
+        } else {
+            body.push(format!("font-size: {};", size));
+        }
+    }
+    if let Some(color) = &theme.text_color {
+        body.push(format!("color: {};", css_color(color)));
+    }
+    push_rule(&mut css, "body", &body);
+
+    let mut headings = Vec::new();
+    if let Some(font) = &theme.heading_font {
+        headings.push(format!("font-family: {}, sans-serif;", css_font(font)));
+    }
+    if let Some(color) = &theme.heading_color {
+        headings.push(format!("color: {};", css_color(color)));
+    }
+    push_rule(
+        &mut css,
+        "h1, h2, h3, h4, h5, h6, .title, .subtitle",
+        &headings,
+    );
+
+    if let Some(color) = &theme.link_color {
+        push_rule(
+            &mut css,
+            "a, a:visited",
+            &[format!("color: {};", css_color(color))],
+        );
+    }
+    if let Some(font) = &theme.mono_font {
+        push_rule(
+            &mut css,
+            "code, pre, kbd, samp",
+            &[format!("font-family: {}, monospace;", css_font(font))],
+        );
+    }
+    if let Some(margin) = &theme.margin {
+        push_rule(&mut css, "@page", &[format!("margin: {};", margin)]);
+    }
+    if theme.logo.is_some() {
+        push_rule(
+            &mut css,
+            ".docpilot-logo",
+            &["text-align: right;".to_string()],
+        );
+        push_rule(
+            &mut css,
+            ".docpilot-logo img",
+            &["max-height: 4em;".to_string()],
+        );
+    }
+
+    css
+}
+
+fn push_rule(css: &mut String, selector: &str, declarations: &[String]) {
+    if declarations.is_empty() {
+        return;
+    }
+    css.push_str(&format!("{} {{\n", selector));
+    for declaration in declarations {
+        css.push_str(&format!("  {}\n", declaration));
+    }
+    css.push_str("}\n");
+}
+
+fn css_font(font: &str) -> String {
+    format!("\"{}\"", font.replace('"', ""))
+}
+
+fn css_color(color: &str) -> String {
+    match docx_color(color) {
+        Some(hex) if !color.starts_with('#') => format!("#{}", hex),
+        _ => color.to_string(),
+    }
+}
+
+/// `#1f4e79`, `1F4E79` or `#abc` as six uppercase hex digits
+fn docx_color(color: &str) -> Option<String> {
+    let hex = color.trim().trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        6 => Some(hex.to_uppercase()),
+        3 => Some(
+            hex.chars()
+                .flat_map(|c| [c, c])
+                .collect::<String>()
+                .to_uppercase(),
+        ),
+        _ => None,
+    }
+}
+
+/// Font size in points from `11pt` or `11`
+fn points(size: &str) -> Option<f32> {
+    size.trim().trim_end_matches("pt").trim().parse().ok()
+}
+
+/// A length such as `2.5cm`, `20mm`, `1in` or `72pt` in twentieths of a point
+fn twips(length: &str) -> Option<u32> {
+    let length = length.trim();
+    let split = length
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(length.len());
+    let value: f64 = length[..split].parse().ok()?;
+    let factor = match length[split..].trim() {
+        "cm" => 1440.0 / 2.54,
+        "mm" => 144.0 / 2.54,
+        "in" => 1440.0,
+        "pt" => 20.0,
+        _ => return None,
+    };
+    Some((value * factor).round() as u32)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STYLES: &str = r#"<w:styles><w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:asciiTheme="minorHAnsi" w:hAnsiTheme="minorHAnsi"/><w:sz w:val="24"/><w:szCs w:val="24"/><w:lang w:val="en-US"/></w:rPr></w:rPrDefault></w:docDefaults><w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:pPr><w:keepNext/></w:pPr><w:rPr><w:b/><w:color w:val="4F81BD" w:themeColor="accent1"/><w:sz w:val="32"/></w:rPr></w:style><w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/></w:style></w:styles>"#;
+
+    #[test]
+    fn test_theme_styles() {
+        let theme = ThemeSettings {
+            body_font: Some("Source Serif Pro".to_string()),
+            heading_font: Some("Fira Sans".to_string()),
+            font_size: Some("11pt".to_string()),
+            heading_color: Some("#1f4e79".to_string()),
+            link_color: Some("c00".to_string()),
+            ..Default::default()
+        };
+
+        let styles = theme_styles(STYLES, &theme);
+
+        assert!(styles.contains(r#"<w:rPr><w:rFonts w:ascii="Source Serif Pro" w:hAnsi="Source Serif Pro" w:eastAsia="Source Serif Pro" w:cs="Source Serif Pro"/><w:sz w:val="22"/><w:szCs w:val="22"/><w:lang"#));
+        assert!(styles.contains(r#"<w:rPr><w:rFonts w:ascii="Fira Sans" w:hAnsi="Fira Sans" w:eastAsia="Fira Sans" w:cs="Fira Sans"/><w:b/><w:color w:val="1F4E79"/><w:sz w:val="32"/></w:rPr>"#));
+        assert!(styles.contains(
+            r#"<w:name w:val="Hyperlink"/><w:rPr><w:color w:val="CC0000"/></w:rPr></w:style>"#
+        ));
+    }
+
+    #[test]
+    fn test_set_page_margins() {
+        let document = r#"<w:sectPr><w:pgSz w:w="12240" w:h="15840"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="720"/></w:sectPr>"#;
+
+        let updated = set_page_margins(document, "2.5cm").unwrap();
+        assert!(updated.contains(
+            r#"<w:pgMar w:top="1417" w:right="1417" w:bottom="1417" w:left="1417" w:header="720"/>"#
+        ));
+        assert!(set_page_margins(document, "wide").is_err());
+    }
+
+    #[test]
+    fn test_theme_docx_adds_logo_header() {
+        let mut writer = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, content) in [
+            ("[Content_Types].xml", "<Types></Types>"),
+            (
+                "word/document.xml",
+                "<w:body><w:sectPr><w:pgMar w:top=\"1\"/></w:sectPr></w:body>",
+            ),
+            (
+                "word/_rels/document.xml.rels",
+                "<Relationships></Relationships>",
+            ),
+            ("word/styles.xml", STYLES),
+        ] {
+            writer.start_file(name, FileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        let docx = writer.finish().unwrap().into_inner();
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let logo = temp_dir.path().join("logo.png");
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend_from_slice(&200u32.to_be_bytes());
+        png.extend_from_slice(&100u32.to_be_bytes());
+        fs::write(&logo, &png).unwrap();
+
+        let theme = ThemeSettings {
+            logo: Some(logo),
+            ..Default::default()
+        };
+        let themed = theme_docx(&docx, &theme).unwrap();
+
+        let mut archive = ZipArchive::new(std::io::Cursor::new(themed)).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            content
+        };
+
+        assert!(read("word/document.xml").contains("r:id=\"rIdDocpilotHeader\""));
+        assert!(read("word/header-docpilot.xml").contains("cx=\"1371600\" cy=\"685800\""));
+        assert!(read("word/_rels/header-docpilot.xml.rels").contains("media/docpilot-logo.png"));
+        assert!(read("[Content_Types].xml").contains("Extension=\"png\""));
+    }
+
+    #[test]
+    fn test_theme_css_and_html() {
+        let theme = ThemeSettings {
+            body_font: Some("Inter".to_string()),
+            font_size: Some("11".to_string()),
+            link_color: Some("1F4E79".to_string()),
+            logo: Some("images/logo.png".into()),
+            ..Default::default()
+        };
+
+        let css = theme_css(&theme);
+        assert!(css.contains("body {\n  font-family: \"Inter\", serif;\n  font-size: 11pt;\n}"));
+        assert!(css.contains("a, a:visited {\n  color: #1F4E79;\n}"));
+
+        let html = theme_html(
+            "<html><head></head><body class=\"x\">$body$</body></html>",
+            &css,
+            Some("templates/corporate/logo.png"),
+        );
+        assert!(html.contains("<style>\n/* Generated"));
+        assert!(html.contains(
+            "<body class=\"x\">\n<header class=\"docpilot-logo\"><img src=\"templates/corporate/logo.png\""
+        ));
+    }
+}
@@ -0,0 +1,362 @@
+//! Pandoc template introspection: which variables a template reads, and
+//! which frontmatter keys it would silently ignore.
+
+use crate::config::MarkdownFile;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Variables, metadata fields and options pandoc itself understands,
+/// independent of the template
+const PANDOC_KEYS: &[&str] = &[
+    "abstract",
+    "abstract-title",
+    "author",
+    "bibliography",
+    "biblio-style",
+    "biblio-title",
+    "biblatexoptions",
+    "block-headings",
+    "category",
+    "citation-style",
+    "citecolor",
+    "classoption",
+    "colorlinks",
+    "csl",
+    "css",
+    "date",
+    "description",
+    "dir",
+    "documentclass",
+    "filecolor",
+    "fontenc",
+    "fontfamily",
+    "fontfamilyoptions",
+    "fontsize",
+    "geometry",
+    "header-includes",
+    "hyperrefoptions",
+    "include-after",
+    "include-before",
+    "indent",
+    "institute",
+    "keywords",
+    "lang",
+    "linestretch",
+    "link-bibliography",
+    "link-citations",
+    "linkcolor",
+    "lof",
+    "lot",
+    "mainfont",
+    "mainfontoptions",
+    "margin-bottom",
+    "margin-left",
+    "margin-right",
+    "margin-top",
+    "mathfont",
+    "monofont",
+    "monofontoptions",
+    "natbiboptions",
+    "nocite",
+    "notes-after-punctuation",
+    "numbersections",
+    "pagestyle",
+    "pagetitle",
+    "papersize",
+    "reference-section-title",
+    "references",
+    "sansfont",
+    "sansfontoptions",
+    "secnumdepth",
+    "subject",
+    "subparagraph",
+    "subtitle",
+    "suppress-bibliography",
+    "thanks",
+    "title",
+    "title-prefix",
+    "toc",
+    "toc-depth",
+    "toc-title",
+    "toccolor",
+    "urlcolor",
+];
+
+/// Variables pandoc fills in on its own; templates may use them unguarded
+const PANDOC_PROVIDED: &[&str] = &[
+    "author-meta",
+    "body",
+    "date-meta",
+    "header-includes",
+    "highlighting-css",
+    "highlighting-macros",
+    "include-after",
+    "include-before",
+    "lang",
+    "math",
+    "outputfile",
+    "pagetitle",
+    "sourcefile",
+    "table-of-contents",
+    "title-meta",
+    "toc",
+];
+
+/// Keys docpilot reads from the frontmatter itself
+const DOCPILOT_KEYS: &[&str] = &[
+    "babel_lang",
+    "book",
+    "link_citations",
+    "output_dir",
+    "toc_depth",
+    "top-level-division",
+    "top_level_division",
+];
+
+/// Variables referenced by a pandoc template
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateVariables {
+    /// Every variable the template reads, guarded or not
+    pub all: BTreeSet<String>,
+    /// Template-specific variables used outside any `$if()$`/`$for()$` guard
+    pub required: BTreeSet<String>,
+}
+
+impl TemplateVariables {
+    /// Parse `$var$`, `${var}`, `$if(var)$`, `$for(var)$` and friends
+    pub fn parse(template: &str) -> Self {
+        let mut variables = Self::default();
+        let mut depth = 0usize;
+        let mut rest = template;
+
+        while let Some(start) = rest.find('$') {
+            rest = &rest[start + 1..];
+
+            // `$$` is a literal dollar sign
+            if let Some(after) = rest.strip_prefix('$') {
+                rest = after;
+                continue;
+            }
+            // `$--` comments run to the end of the line
+            if rest.starts_with("--") {
+                rest = rest.find('\n').map_or("", |end| &rest[end..]);
+                continue;
+            }
+
+            let (directive, after) = if let Some(braced) = rest.strip_prefix('{') {
+                match braced.find('}') {
+                    Some(end) => (&braced[..end], &braced[end + 1..]),
+                    None => break,
+                }
+            } else {
+                match rest.find('$') {
+                    Some(end) => (&rest[..end], &rest[end + 1..]),
+                    None => break,
+                }
+            };
+            rest = after;
+
+            let directive = directive.trim();
+            match directive {
+                "else" | "sep" | "" => {}
+                "endif" | "endfor" => depth = depth.saturating_sub(1),
+                _ => {
+                    let guard = ["if(", "for(", "elseif("]
+                        .iter()
+                        .find_map(|prefix| directive.strip_prefix(prefix))
+                        .and_then(|inner| inner.strip_suffix(')'));
+
+                    if let Some(name) = guard {
+                        if !directive.starts_with("elseif(") {
+                            depth += 1;
+                        }
+                        variables.record(name, false);
+                    } else {
+                        variables.record(directive, depth == 0);
+                    }
+                }
+            }
+        }
+
+        variables
+    }
+
+    fn record(&mut self, expression: &str, unguarded: bool) {
+        // `name.key/pipe`, `name:partial()` and bare `partial()` forms
+        let expression = expression.split('/').next().unwrap_or_default().trim();
+        let name = match expression.split_once(':') {
+            Some((name, _)) => name,
+            None if expression.ends_with("()") => return,
+            None => expression,
+        };
+        let name = name.split('.').next().unwrap_or_default();
+
+        let identifier = Regex::new(r"^[A-Za-z][A-Za-z0-9_-]*$").unwrap();
+        if name == "it" || !identifier.is_match(name) {
+            return;
+        }
+
+        self.all.insert(name.to_string());
+        // Standard pandoc variables render empty when unset, by convention
+        if unguarded && !PANDOC_PROVIDED.contains(&name) && !PANDOC_KEYS.contains(&name) {
+            self.required.insert(name.to_string());
+        }
+    }
+
+    /// Warnings for frontmatter keys neither the template nor pandoc
+    /// understand, and for required variables nobody sets.
+    /// `extra_keys` are provided outside the frontmatter, e.g. `--metadata`.
+    pub fn check_frontmatter(
+        &self,
+        files: &[MarkdownFile],
+        extra_variables: &[String],
+        extra_keys: &[&str],
+    ) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut present: BTreeSet<String> = extra_keys.iter().map(|k| k.to_string()).collect();
+
+        let known = |key: &str| {
+            self.all.contains(key)
+                || extra_variables.iter().any(|v| v == key)
+                || PANDOC_KEYS.contains(&key)
+                || DOCPILOT_KEYS.contains(&key)
+        };
+
+        for file in files {
+            for key in frontmatter_keys(file) {
+                if !known(&key) {
+                    let suggestion = self
+                        .all
+                        .iter()
+                        .map(|v| v.as_str())
+                        .chain(PANDOC_KEYS.iter().copied())
+                        .filter(|candidate| edit_distance(candidate, &key) <= 2)
+                        .min_by_key(|candidate| edit_distance(candidate, &key));
+
+                    warnings.push(match suggestion {
+                        Some(candidate) => format!(
+                            "{}: unknown frontmatter key '{}' (did you mean '{}'?)",
+                            display_name(&file.path),
+                            key,
+                            candidate
+                        ),
+                        None => format!(
+                            "{}: unknown frontmatter key '{}' is not used by the template or pandoc",
+                            display_name(&file.path),
+                            key
+                        ),
+                    });
+                }
+                present.insert(key.replace('_', "-"));
+                present.insert(key);
+            }
+        }
+
+        for variable in &self.required {
+            if !present.contains(variable) {
+                warnings.push(format!(
+                    "Template variable '{}' is required but not set in the frontmatter",
+                    variable
+                ));
+            }
+        }
+
+        warnings
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Keys actually written in a file's frontmatter
+fn frontmatter_keys(file: &MarkdownFile) -> Vec<String> {
+    let Ok(serde_yaml::Value::Mapping(mapping)) = serde_yaml::to_value(&file.metadata) else {
+        return Vec::new();
+    };
+
+    mapping
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .filter_map(|(key, _)| key.as_str().map(|k| k.to_string()))
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::MetadataParser;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_template_variables() {
+        let template = "$if(titlepage)$\\begin{titlepage}$title$\\end{titlepage}$endif$\n\
+            $for(author)$$author.name$$sep$, $endfor$\n\
+            Price: $$5 ${logo} $footer/uppercase$ $-- comment $ignored$\n\
+            $styles.html()$ $body$\n";
+
+        let variables = TemplateVariables::parse(template);
+
+        let all: Vec<&str> = variables.all.iter().map(|v| v.as_str()).collect();
+        assert_eq!(
+            all,
+            vec!["author", "body", "footer", "logo", "title", "titlepage"]
+        );
+        let required: Vec<&str> = variables.required.iter().map(|v| v.as_str()).collect();
+        assert_eq!(required, vec!["footer", "logo"]);
+    }
+
+    #[test]
+    fn test_check_frontmatter_reports_typos_and_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("00-setup.md");
+        fs::write(
+            &path,
+            "---\ntitle: \"Report\"\ntitlepage: true\ntitlepage-colour: \"06386e\"\n---\n\n# Intro\n",
+        )
+        .unwrap();
+        let file = MetadataParser::parse_file(&path).unwrap();
+
+        let variables = TemplateVariables::parse(
+            "$if(titlepage)$$titlepage-color$$endif$ $logo$ $title$ $body$",
+        );
+        let warnings = variables.check_frontmatter(&[file], &[], &[]);
+
+        assert_eq!(
+            warnings,
+            vec![
+                "00-setup.md: unknown frontmatter key 'titlepage-colour' (did you mean 'titlepage-color'?)".to_string(),
+                "Template variable 'logo' is required but not set in the frontmatter".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bundled_eisvogel_needs_no_variables() {
+        let variables = TemplateVariables::parse(super::super::bundled::EISVOGEL_LATEX);
+
+        assert!(variables.all.contains("titlepage-color"));
+        assert!(variables.required.is_empty(), "{:?}", variables.required);
+    }
+}
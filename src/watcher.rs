@@ -1,26 +1,321 @@
-use crate::builders::{DocxBuilder, HtmlBuilder, PdfBuilder};
-use crate::config::ProjectConfig;
+use crate::builders::{self, FormatBuild};
+use crate::config::{MarkdownFile, ProjectConfig};
+use crate::config_file::{DocPilotConfig, CONFIG_FILE_NAMES};
+use crate::diagrams::{DiagramFormat, DiagramProcessor};
 use crate::discovery::FileDiscovery;
 use crate::errors::{DocPilotError, Result};
+use crate::templates::TemplateManager;
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
-use std::sync::mpsc::channel;
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
 use std::time::Duration;
-use tracing::{error, info};
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::sync::watch;
+use tokio::time::{sleep_until, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Output formats the watcher knows how to rebuild
+const FORMATS: [&str; 4] = ["pdf", "docx", "html", "epub"];
+
+/// Coalesces the burst of events an editor produces for one save: a rebuild
+/// is due once no relevant change has been seen for `delay`.
+#[derive(Debug)]
+struct Debouncer {
+    delay: Duration,
+    changed: Vec<PathBuf>,
+    deadline: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            changed: Vec::new(),
+            deadline: None,
+        }
+    }
+
+    /// Record a change; every change pushes the deadline back
+    fn record(&mut self, path: PathBuf, now: Instant) {
+        if !self.changed.contains(&path) {
+            self.changed.push(path);
+        }
+        self.deadline = Some(now + self.delay);
+    }
+
+    fn is_pending(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// The paths changed since the last rebuild, clearing the deadline
+    fn take(&mut self) -> Vec<PathBuf> {
+        self.deadline = None;
+        std::mem::take(&mut self.changed)
+    }
+}
+
+/// What a changed file means for the watched build
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Impact {
+    /// Build output, caches and files no build reads
+    Ignore,
+    /// The project config changed; reload it and rebuild
+    ReloadConfig,
+    /// A Mermaid diagram no chapter references; render it on its own
+    RenderDiagram,
+    /// Rebuild these formats
+    Rebuild(Vec<&'static str>),
+}
+
+impl Impact {
+    fn affects(&self, format: &str) -> bool {
+        match self {
+            Impact::Ignore => false,
+            Impact::ReloadConfig | Impact::RenderDiagram => true,
+            Impact::Rebuild(formats) => formats.contains(&format),
+        }
+    }
+}
+
+/// The files the last build read, used to decide what a change affects.
+/// All paths are absolute and lexically normalized so they compare equal to
+/// the paths reported by the file watcher.
+#[derive(Debug)]
+struct WatchGraph {
+    root: PathBuf,
+    config: ProjectConfig,
+    /// The `--config` file, or `None` to watch the default config names
+    config_file: Option<PathBuf>,
+    /// Output and cache directories, never a reason to rebuild
+    ignored: Vec<PathBuf>,
+    templates_dir: PathBuf,
+    /// Chapters and everything they reference: images, data, includes,
+    /// diagrams, bibliographies and citation styles
+    dependencies: HashSet<PathBuf>,
+    /// Template file used per output format
+    templates: Vec<(&'static str, PathBuf)>,
+}
+
+impl WatchGraph {
+    fn new(config: &ProjectConfig, config_file: Option<&Path>) -> Self {
+        let root = std::env::current_dir().unwrap_or_default();
+        let markdown_files = match FileDiscovery::new(config.clone()).discover_all() {
+            Ok(files) => files.markdown_files,
+            Err(e) => {
+                error!("Failed to collect dependencies: {}", e);
+                Vec::new()
+            }
+        };
+
+        Self::from_files(root, config, config_file, &markdown_files)
+    }
+
+    fn from_files(
+        root: PathBuf,
+        config: &ProjectConfig,
+        config_file: Option<&Path>,
+        markdown_files: &[MarkdownFile],
+    ) -> Self {
+        let absolute = |path: &Path| normalize(&root.join(path));
+
+        let mut dependencies: HashSet<PathBuf> = markdown_files
+            .iter()
+            .flat_map(|file| std::iter::once(&file.path).chain(&file.dependencies))
+            .map(|path| absolute(path))
+            .collect();
+
+        // pandoc resolves bibliographies and styles relative to the project root
+        for metadata in markdown_files.iter().map(|file| &file.metadata) {
+            for path in metadata
+                .bibliography
+                .iter()
+                .flatten()
+                .chain(metadata.csl.iter())
+            {
+                dependencies.insert(absolute(Path::new(path)));
+            }
+        }
+
+        let manager = TemplateManager::new(config.templates_dir.clone());
+        let templates = FORMATS
+            .iter()
+            .filter_map(|&format| {
+                match manager.resolve(format, config.templates.for_format(format)) {
+                    Ok(template) => template.map(|path| (format, absolute(&path))),
+                    Err(e) => {
+                        debug!("No {} template to watch: {}", format, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self {
+            config_file: config_file.map(&absolute),
+            ignored: vec![absolute(&config.output_dir), absolute(Path::new(".git"))],
+            templates_dir: absolute(&config.templates_dir),
+            config: config.clone(),
+            dependencies,
+            templates,
+            root,
+        }
+    }
+
+    fn classify(&self, path: &Path) -> Impact {
+        let path = normalize(&self.root.join(path));
+        if self.ignored.iter().any(|dir| path.starts_with(dir)) {
+            return Impact::Ignore;
+        }
+
+        let in_root = path.parent() == Some(self.root.as_path());
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let is_config = match &self.config_file {
+            Some(config_file) => *config_file == path,
+            None => in_root && CONFIG_FILE_NAMES.contains(&file_name.as_str()),
+        };
+        if is_config {
+            return Impact::ReloadConfig;
+        }
+
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if extension == "mmd" {
+            // Referenced diagrams are re-rendered by the build, which only
+            // renders diagrams whose artifact is older than the source
+            return if self.dependencies.contains(&path) {
+                Impact::Rebuild(FORMATS.to_vec())
+            } else {
+                Impact::RenderDiagram
+            };
+        }
+
+        // A template, or another file of the package it belongs to
+        let formats: Vec<&'static str> = self
+            .templates
+            .iter()
+            .filter(|(_, template)| {
+                *template == path
+                    || template.parent().is_some_and(|package| {
+                        package.starts_with(&self.templates_dir)
+                            && package != self.templates_dir
+                            && path.starts_with(package)
+                    })
+            })
+            .map(|(format, _)| *format)
+            .collect();
+        if !formats.is_empty() {
+            return Impact::Rebuild(formats);
+        }
+
+        let is_new_chapter = in_root
+            && extension == "md"
+            && !FileDiscovery::new(self.config.clone()).should_exclude(&path);
+        if self.dependencies.contains(&path)
+            || is_new_chapter
+            || matches!(extension.as_str(), "bib" | "bibtex" | "csl")
+        {
+            return Impact::Rebuild(FORMATS.to_vec());
+        }
+
+        Impact::Ignore
+    }
+}
+
+/// Resolve `.` and `..` without touching the file system, so paths of
+/// deleted files still compare equal
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Work collected from one debounced batch of changes
+#[derive(Debug, Default)]
+struct Job {
+    /// Standalone diagrams to render before anything else
+    diagrams: Vec<PathBuf>,
+    /// Formats to rebuild, in the order they were requested
+    formats: Vec<String>,
+}
+
+type BuildFuture = Pin<Box<dyn Future<Output = Result<Vec<FormatBuild>>>>>;
+
+/// State of the watched build, published to [`FileWatcher::with_status`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildStatus {
+    Building,
+    Succeeded,
+    /// The first error reported by the build
+    Failed(String),
+}
+
+impl BuildStatus {
+    fn from_builds(builds: &[FormatBuild]) -> Self {
+        match builds.iter().find_map(|build| build.result.as_ref().err()) {
+            Some(e) => BuildStatus::Failed(e.to_string()),
+            None => BuildStatus::Succeeded,
+        }
+    }
+}
 
 pub struct FileWatcher {
     config: ProjectConfig,
+    config_file: Option<PathBuf>,
+    status: Option<watch::Sender<BuildStatus>>,
 }
 
 impl FileWatcher {
     pub fn new(config: ProjectConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            config_file: None,
+            status: None,
+        }
+    }
+
+    /// Publish the build status, e.g. to reload a browser preview
+    pub fn with_status(mut self, status: watch::Sender<BuildStatus>) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    fn publish(&self, status: BuildStatus) {
+        if let Some(sender) = &self.status {
+            sender.send_replace(status);
+        }
+    }
+
+    /// Reload the config from this file instead of the default names
+    pub fn with_config_file(mut self, config_file: Option<PathBuf>) -> Self {
+        self.config_file = config_file;
+        self
     }
 
-    pub async fn watch_and_build(&self, format: &str) -> Result<()> {
-        info!("Starting file watcher for {} format", format);
+    /// Build `formats`, then rebuild the affected ones after every change
+    pub async fn watch_and_build(&self, formats: &[String]) -> Result<()> {
+        info!("Starting file watcher for {}", formats.join(", "));
 
-        let (tx, rx) = channel();
+        let (tx, mut rx) = unbounded_channel();
         let mut watcher: RecommendedWatcher = Watcher::new(
             move |res: notify::Result<Event>| match res {
                 Ok(event) => {
@@ -45,30 +340,117 @@ impl FileWatcher {
 
         info!("👀 Watching for changes... Press Ctrl+C to stop");
 
+        let mut config = self.config.clone();
+
         // Initial build
-        self.build_format(format).await?;
+        let initial = Job {
+            diagrams: Vec::new(),
+            formats: formats.to_vec(),
+        };
+        self.publish(BuildStatus::Building);
+        match Self::run(config.clone(), initial).await {
+            Ok(builds) => {
+                Self::report(&builds);
+                self.publish(BuildStatus::from_builds(&builds));
+            }
+            Err(e) => {
+                self.publish(BuildStatus::Failed(e.to_string()));
+                return Err(e);
+            }
+        }
+        let mut graph = WatchGraph::new(&config, self.config_file.as_deref());
+
+        let mut debouncer = Debouncer::new(Duration::from_millis(config.debounce_ms));
+        // Dropping the running build kills its pandoc processes
+        let mut build: Option<BuildFuture> = None;
+        let mut running: Vec<String> = Vec::new();
+        // Formats of a cancelled build, rebuilt with the next batch
+        let mut cancelled: Vec<String> = Vec::new();
 
         loop {
-            match rx.recv() {
-                Ok(event) => match event.kind {
-                    EventKind::Modify(_) | EventKind::Create(_) => {
-                        for path in event.paths {
-                            if self.should_rebuild(&path) {
-                                info!("File changed: {}", path.display());
-                                if let Err(e) = self.build_format(format).await {
-                                    error!("Build failed: {}", e);
-                                } else {
-                                    info!("✅ Rebuild complete");
-                                }
-                                break;
+            tokio::select! {
+                event = rx.recv() => {
+                    let Some(event) = event else {
+                        break;
+                    };
+                    if !matches!(
+                        event.kind,
+                        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+                    ) {
+                        continue;
+                    }
+                    for path in event.paths {
+                        let impact = graph.classify(&path);
+                        if formats.iter().any(|format| impact.affects(format)) {
+                            debouncer.record(path, Instant::now());
+                        }
+                    }
+                    if debouncer.is_pending() && build.take().is_some() {
+                        info!("⏹️  Change detected, cancelling running build");
+                        cancelled.append(&mut running);
+                    }
+                }
+                _ = sleep_until(debouncer.deadline().unwrap_or_else(Instant::now)),
+                    if debouncer.is_pending() =>
+                {
+                    let mut job = Job::default();
+                    let mut affected = std::mem::take(&mut cancelled);
+                    let mut reload = false;
+                    for path in debouncer.take() {
+                        info!("File changed: {}", path.display());
+                        match graph.classify(&path) {
+                            Impact::Ignore => {}
+                            Impact::ReloadConfig => reload = true,
+                            Impact::RenderDiagram => job.diagrams.push(path),
+                            Impact::Rebuild(rebuild) => {
+                                affected.extend(rebuild.iter().map(|format| format.to_string()))
+                            }
+                        }
+                    }
+
+                    if reload {
+                        match DocPilotConfig::resolve(self.config_file.as_deref()) {
+                            Ok(reloaded) => {
+                                info!("🔄 Reloaded configuration");
+                                config = ProjectConfig {
+                                    keep_going: config.keep_going,
+                                    ..reloaded.to_project_config()
+                                };
+                                debouncer.delay = Duration::from_millis(config.debounce_ms);
+                                graph = WatchGraph::new(&config, self.config_file.as_deref());
+                                affected.extend(formats.iter().cloned());
                             }
+                            Err(e) => error!("Keeping the previous configuration: {}", e),
+                        }
+                    }
+
+                    job.formats = formats
+                        .iter()
+                        .filter(|format| affected.contains(format))
+                        .cloned()
+                        .collect();
+                    if !job.formats.is_empty() || !job.diagrams.is_empty() {
+                        running = job.formats.clone();
+                        self.publish(BuildStatus::Building);
+                        build = Some(Box::pin(Self::run(config.clone(), job)));
+                    }
+                }
+                result = async { build.as_mut().expect("guarded by is_some").await },
+                    if build.is_some() =>
+                {
+                    build = None;
+                    running.clear();
+                    match result {
+                        Ok(builds) => {
+                            Self::report(&builds);
+                            self.publish(BuildStatus::from_builds(&builds));
+                        }
+                        Err(e) => {
+                            error!("Build failed: {}", e);
+                            self.publish(BuildStatus::Failed(e.to_string()));
                         }
                     }
-                    _ => {}
-                },
-                Err(e) => {
-                    error!("Watch error: {}", e);
-                    break;
+                    graph = WatchGraph::new(&config, self.config_file.as_deref());
                 }
             }
         }
@@ -76,59 +458,169 @@ impl FileWatcher {
         Ok(())
     }
 
-    fn should_rebuild(&self, path: &Path) -> bool {
-        if let Some(ext) = path.extension() {
-            matches!(ext.to_str(), Some("md") | Some("yaml") | Some("yml"))
-        } else {
-            false
+    /// Render the job's standalone diagrams, then build its formats side by
+    /// side. Returns one result per format.
+    async fn run(config: ProjectConfig, job: Job) -> Result<Vec<FormatBuild>> {
+        if !job.diagrams.is_empty() {
+            let processor = DiagramProcessor::new(config.clone());
+            let mut diagram_formats: Vec<DiagramFormat> = Vec::new();
+            for format in &job.formats {
+                let diagram_format = DiagramFormat::for_output(format);
+                if !diagram_formats.contains(&diagram_format) {
+                    diagram_formats.push(diagram_format);
+                }
+            }
+            if diagram_formats.is_empty() {
+                diagram_formats.push(DiagramFormat::Png);
+            }
+
+            for diagram in &job.diagrams {
+                for diagram_format in &diagram_formats {
+                    match processor.ensure_rendered(diagram, *diagram_format).await {
+                        Ok(artifact) => info!("Rendered diagram: {}", artifact.display()),
+                        Err(e) => warn!("Failed to render {}: {}", diagram.display(), e),
+                    }
+                }
+            }
         }
-    }
 
-    async fn build_format(&self, format: &str) -> Result<()> {
-        let discovery = FileDiscovery::new(self.config.clone());
-        let files = discovery.discover_all()?;
+        if job.formats.is_empty() {
+            return Ok(Vec::new());
+        }
 
+        let files = FileDiscovery::new(config.clone()).discover_all()?;
         if files.markdown_files.is_empty() {
             return Err(DocPilotError::Build {
                 message: "No markdown files found".to_string(),
             });
         }
 
-        match format {
-            "pdf" => {
-                let builder = PdfBuilder::new(self.config.clone());
-                builder.ensure_output_dir()?;
-                let output_path = self
-                    .config
-                    .output_dir
-                    .join(format!("{}.pdf", self.config.name));
-                builder.build(&files.markdown_files, &output_path).await?;
-            }
-            "docx" => {
-                let builder = DocxBuilder::new(self.config.clone());
-                builder.ensure_output_dir()?;
-                let output_path = self
-                    .config
-                    .output_dir
-                    .join(format!("{}.docx", self.config.name));
-                builder.build(&files.markdown_files, &output_path).await?;
-            }
-            "html" => {
-                let builder = HtmlBuilder::new(self.config.clone());
-                builder.ensure_output_dir()?;
-                let output_path = self
-                    .config
-                    .output_dir
-                    .join(format!("{}.html", self.config.name));
-                builder.build(&files.markdown_files, &output_path).await?;
-            }
-            _ => {
-                return Err(DocPilotError::Build {
-                    message: format!("Unsupported format for watch: {}", format),
-                });
-            }
+        Ok(builders::build_concurrently(&config, &job.formats, &files.markdown_files).await)
+    }
+
+    fn report(builds: &[FormatBuild]) {
+        let failed: Vec<&str> = builds
+            .iter()
+            .filter(|build| build.result.is_err())
+            .map(|build| build.format.as_str())
+            .collect();
+
+        if builds.is_empty() {
+            info!("✅ Diagrams rendered");
+        } else if failed.is_empty() {
+            info!("✅ Rebuild complete");
+        } else {
+            error!("Build failed for {}", failed.join(", "));
         }
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::MetadataParser;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_debouncer_coalesces_changes() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(300));
+        assert!(!debouncer.is_pending());
+
+        let start = Instant::now();
+        debouncer.record(PathBuf::from("01-intro.md"), start);
+        debouncer.record(
+            PathBuf::from("01-intro.md"),
+            start + Duration::from_millis(100),
+        );
+        debouncer.record(
+            PathBuf::from("02-usage.md"),
+            start + Duration::from_millis(200),
+        );
+
+        assert_eq!(
+            debouncer.deadline(),
+            Some(start + Duration::from_millis(500))
+        );
+        assert_eq!(
+            debouncer.take(),
+            vec![PathBuf::from("01-intro.md"), PathBuf::from("02-usage.md")]
+        );
+        assert!(!debouncer.is_pending());
+        assert!(debouncer.take().is_empty());
+    }
+
+    #[test]
+    fn test_watch_graph_classifies_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        fs::create_dir_all(root.join("images")).unwrap();
+        fs::write(root.join("images/logo.png"), "png").unwrap();
+        fs::write(root.join("images/flow.mmd"), "graph TD; A-->B").unwrap();
+        fs::create_dir_all(root.join("templates/corporate")).unwrap();
+        fs::write(
+            root.join("templates/corporate/template.yml"),
+            "formats:\n  pdf: corporate.latex\n  html: corporate.html\n",
+        )
+        .unwrap();
+        fs::write(root.join("templates/corporate/corporate.latex"), "latex").unwrap();
+        fs::write(root.join("templates/corporate/corporate.html"), "html").unwrap();
+        fs::write(root.join("templates/other.docx"), "docx").unwrap();
+        fs::write(
+            root.join("01-intro.md"),
+            "---\nbibliography: [\"refs.json\"]\n---\n\n![Flow](images/flow.mmd)\n\n![Logo](images/logo.png)\n",
+        )
+        .unwrap();
+
+        let config = ProjectConfig {
+            output_dir: root.join("output"),
+            templates_dir: root.join("templates"),
+            templates: crate::config::TemplateSelection {
+                pdf: Some("corporate".to_string()),
+                html: Some("corporate".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let files = vec![MetadataParser::parse_file(&root.join("01-intro.md")).unwrap()];
+        let graph = WatchGraph::from_files(root.clone(), &config, None, &files);
+        let all = Impact::Rebuild(FORMATS.to_vec());
+
+        assert_eq!(graph.classify(&root.join("./01-intro.md")), all);
+        assert_eq!(graph.classify(&root.join("02-new.md")), all);
+        assert_eq!(graph.classify(&root.join("README.md")), Impact::Ignore);
+        assert_eq!(graph.classify(&root.join("images/logo.png")), all);
+        assert_eq!(
+            graph.classify(&root.join("images/unused.png")),
+            Impact::Ignore
+        );
+        assert_eq!(graph.classify(&root.join("images/flow.mmd")), all);
+        assert_eq!(
+            graph.classify(&root.join("images/sketch.mmd")),
+            Impact::RenderDiagram
+        );
+        assert_eq!(graph.classify(&root.join("refs.json")), all);
+        assert_eq!(graph.classify(&root.join("style.csl")), all);
+        assert_eq!(
+            graph.classify(&root.join("templates/corporate/corporate.latex")),
+            Impact::Rebuild(vec!["pdf", "html"])
+        );
+        assert_eq!(
+            graph.classify(&root.join("templates/other.docx")),
+            Impact::Rebuild(vec!["docx"])
+        );
+        assert_eq!(
+            graph.classify(&root.join("docpilot.yml")),
+            Impact::ReloadConfig
+        );
+        assert_eq!(
+            graph.classify(&root.join("output/document.pdf")),
+            Impact::Ignore
+        );
+        assert_eq!(
+            graph.classify(&root.join("output/.cache/templates/eisvogel/eisvogel.latex")),
+            Impact::Ignore
+        );
+        assert!(!Impact::Rebuild(vec!["docx"]).affects("pdf"));
     }
 }
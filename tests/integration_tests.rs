@@ -40,6 +40,203 @@ fn test_init_command() {
     // Verify files were created
     assert!(temp_dir.path().join("00-setup.md").exists());
     assert!(temp_dir.path().join("01-introduction.md").exists());
+
+    // The bundled Eisvogel template is installed without network access
+    assert!(temp_dir
+        .path()
+        .join("templates/eisvogel/eisvogel.latex")
+        .exists());
+    assert!(temp_dir
+        .path()
+        .join("templates/eisvogel/template.yml")
+        .exists());
+}
+
+#[test]
+fn test_init_command_with_kind() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["init", "--name", "my-thesis", "--kind", "thesis"]);
+
+    cmd.assert().success();
+
+    let setup = fs::read_to_string(temp_dir.path().join("00-setup.md")).unwrap();
+    assert!(setup.contains("top-level-division: \"chapter\""));
+    assert!(setup.contains("book: true"));
+    assert!(temp_dir.path().join("01-abstract.md").exists());
+    assert!(temp_dir.path().join("90-appendix.md").exists());
+    assert!(temp_dir.path().join("references.bib").exists());
+    assert!(!temp_dir.path().join("01-introduction.md").exists());
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["init", "--kind", "memo"]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_init_into_directory_never_overwrites() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("my-doc");
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path()).args(["init", "my-doc"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Project 'my-doc' initialized"));
+
+    assert!(project.join("00-setup.md").exists());
+    assert!(project.join("templates/eisvogel/eisvogel.latex").exists());
+    assert!(!temp_dir.path().join("00-setup.md").exists());
+
+    // A second run finds the edited setup file and stops
+    fs::write(project.join("00-setup.md"), "---\ntitle: \"Mine\"\n---\n").unwrap();
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path()).args(["init", "my-doc"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Refusing to overwrite"));
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["init", "my-doc", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("exists     00-setup.md"))
+        .stdout(predicate::str::contains("unchanged  01-introduction.md"));
+    assert!(fs::read_to_string(project.join("00-setup.md"))
+        .unwrap()
+        .contains("Mine"));
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["init", "my-doc", "--force"]);
+    cmd.assert().success();
+    assert!(fs::read_to_string(project.join("00-setup.md"))
+        .unwrap()
+        .contains("title: \"my-doc\""));
+}
+
+#[test]
+fn test_init_from_starter_kit() {
+    let temp_dir = TempDir::new().unwrap();
+    let kit = temp_dir.path().join("kit");
+    let project = temp_dir.path().join("project");
+    fs::create_dir_all(&kit).unwrap();
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        kit.join("docpilot-kit.yml"),
+        "name: acme\nprompts:\n  - name: department\n    default: Legal\n",
+    )
+    .unwrap();
+    fs::write(
+        kit.join("00-setup.md"),
+        "---\ntitle: \"{{project_name}}\"\nauthor: [\"{{author}}\"]\nfooter-left: \"{{department}}\"\n---\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(&project)
+        .args(["init", "--name", "Q3", "--from"])
+        .arg(&kit)
+        .args(["--var", "author=Ada"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("initialized from acme"));
+
+    let setup = fs::read_to_string(project.join("00-setup.md")).unwrap();
+    assert!(setup.contains("title: \"Q3\""));
+    assert!(setup.contains("author: [\"Ada\"]"));
+    assert!(setup.contains("footer-left: \"Legal\""));
+    assert!(!project.join("docpilot-kit.yml").exists());
+    assert!(project.join("templates/eisvogel/eisvogel.latex").exists());
+}
+
+#[test]
+fn test_import_mdbook() {
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("book/src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(
+        src.join("SUMMARY.md"),
+        "# Summary\n\n- [Intro](intro.md)\n  - [Details](details.md)\n- [Usage](usage.md)\n",
+    )
+    .unwrap();
+    fs::write(src.join("intro.md"), "# Intro\n").unwrap();
+    fs::write(src.join("details.md"), "# Details\n").unwrap();
+    fs::write(src.join("usage.md"), "# Usage\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["import", "book/src/SUMMARY.md", "--dir", "project"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("2 chapter files"));
+
+    let project = temp_dir.path().join("project");
+    assert!(project.join("00-setup.md").exists());
+    assert_eq!(
+        fs::read_to_string(project.join("01-intro.md")).unwrap(),
+        "# Intro\n\n## Details\n"
+    );
+    assert!(project.join("02-usage.md").exists());
+}
+
+#[test]
+fn test_import_rejects_unknown_format() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("notes.txt"), "text").unwrap();
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["import", "notes.txt"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Cannot import notes.txt"));
+}
+
+#[test]
+fn test_chapter_add_and_move() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("01-intro.md"), "# Intro\n").unwrap();
+    fs::write(
+        temp_dir.path().join("02-usage.md"),
+        "# Usage\n\nSee the [intro](01-intro.md).\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path()).args([
+        "chapter",
+        "add",
+        "Deployment",
+        "--after",
+        "01",
+        "--dry-run",
+    ]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Would rename 02-usage.md → 03-usage.md",
+    ));
+    assert!(!temp_dir.path().join("02-deployment.md").exists());
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["chapter", "add", "Deployment", "--after", "01"]);
+    cmd.assert().success();
+    assert!(temp_dir.path().join("02-deployment.md").exists());
+    assert!(temp_dir.path().join("03-usage.md").exists());
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["chapter", "move", "03", "01"]);
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("01-usage.md")).unwrap(),
+        "# Usage\n\nSee the [intro](02-intro.md).\n"
+    );
 }
 
 #[test]
@@ -54,6 +251,36 @@ fn test_check_command() {
         .stdout(predicate::str::contains("DEPENDENCY CHECK"));
 }
 
+#[test]
+fn test_check_command_json() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    let output = cmd
+        .current_dir(temp_dir.path())
+        .args(["check", "--json"])
+        .output()
+        .unwrap();
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let expected_code = match report["outcome"].as_str().unwrap() {
+        "ok" => 0,
+        "missing_required" => 3,
+        "missing_used" => 4,
+        other => panic!("unexpected outcome {}", other),
+    };
+    assert_eq!(output.status.code(), Some(expected_code));
+
+    let names: Vec<&str> = report["dependencies"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|dep| dep["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"pandoc"));
+    assert!(report["package_manager"].is_string());
+}
+
 #[test]
 fn test_status_command_empty_project() {
     let temp_dir = TempDir::new().unwrap();
@@ -192,6 +419,21 @@ fn test_invalid_command() {
         .stderr(predicate::str::contains("unrecognized subcommand"));
 }
 
+#[test]
+fn test_build_formats_arguments() {
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.args(["build", "--watch"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--formats"));
+
+    let mut cmd = Command::cargo_bin("docpilot").unwrap();
+    cmd.args(["build", "--formats", "pdf,rtf"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'rtf'"));
+}
+
 #[test]
 fn test_build_help() {
     let mut cmd = Command::cargo_bin("docpilot").unwrap();